@@ -0,0 +1,249 @@
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use serde::{Serialize,Deserialize};
+use crate::game::config_parsers::GameData;
+use crate::game::config_parsers::files::{ConfigFiles, FolderFiles, MemoryFiles};
+
+// Compiled games. A compiled game is a copy of the radiance executable with the game's config files
+// appended to the end of it, so that it can be handed to players as a single file. The appended
+// bundle is laid out as:
+//
+//    [executable] [bundle as JSON] [length of the bundle JSON as 8 little endian bytes] [BUNDLE_MAGIC]
+
+// Marks the end of an executable that has a game bundled into it
+const BUNDLE_MAGIC: &[u8; 8] = b"RDNCGAME";
+
+// Size of the length and magic that follow the bundle
+const TRAILER_SIZE: u64 = 16;
+
+// The config files of a game, stored by their path relative to the game's folder. Paths always
+//    use '/' so that games compiled on one platform can be read on any other.
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    files: Vec<BundledFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundledFile {
+    path: String,
+    contents: String,
+}
+
+// Checks the game in config_path, then writes a copy of the running executable with the game's
+//    config files bundled into it to output_path
+pub fn compile(config_path: &Path, output_path: &Path) -> Result<(), Box<dyn Error>> {
+    let folder = FolderFiles::new(config_path);
+    let (_game_data, problems) = GameData::load_configs(&folder);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    let errors = problems.iter().filter(|p| p.is_error()).count();
+    if errors > 0 {
+        return Err(format!("Found {} error(s) in the game configuration files", errors).into());
+    }
+
+    let executable_path = env::current_exe()?;
+    let executable = with_bundle(fs::read(&executable_path)?, &folder)?;
+    fs::write(output_path, executable)?;
+    // Keep the executable permissions of the original
+    fs::set_permissions(output_path, fs::metadata(&executable_path)?.permissions())?;
+
+    Ok(())
+}
+
+// Reads the game bundled into the running executable, if it has one. The bundled files are given
+//    the executable's path as their root.
+pub fn embedded_files() -> Result<Option<MemoryFiles>, Box<dyn Error>> {
+    let executable_path = env::current_exe()?;
+    read_bundle(&mut fs::File::open(&executable_path)?, &executable_path)
+}
+
+// Adds the config files to the end of the executable, replacing any game that is already bundled
+//    into it
+fn with_bundle(mut executable: Vec<u8>, files: &dyn ConfigFiles) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Collect the contents of every config file
+    let mut read_errors = vec![];
+    let mut bundle = Bundle { files: vec![] };
+    for path in files.yaml_files(&mut read_errors) {
+        let relative_path = match path.strip_prefix(files.root()) {
+            Ok(relative_path) => { relative_path }
+            Err(_) => { continue; }
+        };
+        bundle.files.push(BundledFile {
+            path: bundle_path(relative_path),
+            contents: files.read_to_string(&path)?,
+        });
+    }
+    if let Some(error) = read_errors.first() {
+        return Err(error.to_string().into());
+    }
+
+    if let Some(bundle_start) = find_bundle(&executable) {
+        executable.truncate(bundle_start);
+    }
+    let bundle_json = serde_json::to_vec(&bundle)?;
+    executable.extend_from_slice(&bundle_json);
+    executable.extend_from_slice(&(bundle_json.len() as u64).to_le_bytes());
+    executable.extend_from_slice(BUNDLE_MAGIC);
+    Ok(executable)
+}
+
+// Reads the game bundled at the end of an executable, if it has one, with the bundled files placed
+//    under root
+fn read_bundle<R: Read + Seek>(executable: &mut R, root: &Path) -> Result<Option<MemoryFiles>, Box<dyn Error>> {
+    let size = executable.seek(SeekFrom::End(0))?;
+    if size < TRAILER_SIZE {
+        return Ok(None);
+    }
+
+    // Read the trailer at the very end of the file
+    let mut trailer = [0u8; TRAILER_SIZE as usize];
+    executable.seek(SeekFrom::Start(size - TRAILER_SIZE))?;
+    executable.read_exact(&mut trailer)?;
+    let bundle_size = match parse_trailer(&trailer) {
+        Some(bundle_size) if bundle_size <= size - TRAILER_SIZE => { bundle_size }
+        Some(_) => { return Err("The bundled game is damaged".into()); }
+        None => { return Ok(None); }
+    };
+
+    let mut bundle_json = vec![0u8; bundle_size as usize];
+    executable.seek(SeekFrom::Start(size - TRAILER_SIZE - bundle_size))?;
+    executable.read_exact(&mut bundle_json)?;
+    let bundle = serde_json::from_slice::<Bundle>(&bundle_json)?;
+
+    let mut files = MemoryFiles::new(root);
+    for file in bundle.files {
+        files.add_file(file.path.split('/').collect::<PathBuf>(), file.contents);
+    }
+    Ok(Some(files))
+}
+
+// Returns the default name for a compiled game, which is based on the name of its config folder
+//    (e.g. "example_game" compiles to "radiance-example_game", or "radiance-example_game.exe" on Windows)
+pub fn default_output_path(config_path: &Path) -> PathBuf {
+    let config_path = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+    let mut file_name = OsString::from("radiance-");
+    match config_path.file_name() {
+        Some(name) => { file_name.push(name); }
+        None => { file_name.push("game"); }
+    }
+    file_name.push(env::consts::EXE_SUFFIX);
+    PathBuf::from(file_name)
+}
+
+// Finds where the bundle starts in an executable, if there is one
+fn find_bundle(executable: &[u8]) -> Option<usize> {
+    let size = executable.len() as u64;
+    if size < TRAILER_SIZE {
+        return None;
+    }
+    let bundle_size = parse_trailer(&executable[(size - TRAILER_SIZE) as usize..])?;
+    if bundle_size > size - TRAILER_SIZE {
+        return None;
+    }
+    Some((size - TRAILER_SIZE - bundle_size) as usize)
+}
+
+// Returns the size of the bundle if the trailer ends with the bundle magic
+fn parse_trailer(trailer: &[u8]) -> Option<u64> {
+    if &trailer[8..] != BUNDLE_MAGIC {
+        return None;
+    }
+    let mut length = [0u8; 8];
+    length.copy_from_slice(&trailer[..8]);
+    Some(u64::from_le_bytes(length))
+}
+
+// Converts a relative path into the form stored in bundles
+fn bundle_path(relative_path: &Path) -> String {
+    relative_path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => { Some(name.to_string_lossy().to_string()) }
+            _ => { None }
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+
+    const EXECUTABLE: &[u8] = b"\x7fELF pretend executable";
+
+    fn game_files(name: &str) -> MemoryFiles {
+        let mut files = MemoryFiles::new(Path::new(name));
+        files.add_file("game.yaml", format!("name: \"{}\"", name));
+        files.add_file("maps/room.yaml", "id: \"room\"");
+        files
+    }
+
+    fn read(executable: &[u8]) -> Result<Option<MemoryFiles>, Box<dyn Error>> {
+        read_bundle(&mut Cursor::new(executable), Path::new("radiance-game"))
+    }
+
+    // The contents of every file, by its path relative to the root
+    fn contents(files: &MemoryFiles) -> Vec<(String, String)> {
+        files.yaml_files(&mut vec![]).iter()
+            .map(|path| (bundle_path(path.strip_prefix(files.root()).unwrap()), files.read_to_string(path).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn bundles_are_read_back() {
+        let executable = with_bundle(EXECUTABLE.to_vec(), &game_files("castle")).unwrap();
+        assert!(executable.starts_with(EXECUTABLE));
+        assert!(executable.ends_with(BUNDLE_MAGIC));
+
+        let trailer = &executable[executable.len() - TRAILER_SIZE as usize..];
+        let bundle_size = parse_trailer(trailer).unwrap() as usize;
+        assert_eq!(EXECUTABLE.len() + bundle_size + TRAILER_SIZE as usize, executable.len());
+        let bundle_json = &executable[EXECUTABLE.len()..EXECUTABLE.len() + bundle_size];
+        assert_eq!(serde_json::from_slice::<Bundle>(bundle_json).unwrap().files.len(), 2);
+
+        let files = read(&executable).unwrap().unwrap();
+        assert_eq!(files.root(), Path::new("radiance-game"));
+        assert_eq!(contents(&files), vec![
+            ("game.yaml".to_string(), "name: \"castle\"".to_string()),
+            ("maps/room.yaml".to_string(), "id: \"room\"".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn executables_without_bundles_have_no_files() {
+        assert!(read(EXECUTABLE).unwrap().is_none());
+        assert!(read(b"tiny").unwrap().is_none());
+        assert!(parse_trailer(b"\x10\0\0\0\0\0\0\0RDNCGAMX").is_none());
+        assert_eq!(parse_trailer(b"\x10\0\0\0\0\0\0\0RDNCGAME"), Some(16));
+    }
+
+    #[test]
+    fn damaged_bundles_are_reported() {
+        let executable = with_bundle(EXECUTABLE.to_vec(), &game_files("castle")).unwrap();
+
+        // A bundle that claims to be larger than the whole file
+        let mut too_long = executable.clone();
+        let length_start = too_long.len() - TRAILER_SIZE as usize;
+        too_long[length_start..length_start + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(read(&too_long).err().unwrap().to_string(), "The bundled game is damaged");
+
+        // A bundle with part of its JSON cut off
+        let mut truncated = executable[..EXECUTABLE.len()].to_vec();
+        truncated.extend_from_slice(&executable[EXECUTABLE.len() + 5..]);
+        assert!(read(&truncated).is_err());
+    }
+
+    #[test]
+    fn recompiling_replaces_the_bundle() {
+        let castle = with_bundle(EXECUTABLE.to_vec(), &game_files("castle")).unwrap();
+        let forest = with_bundle(castle, &game_files("forest")).unwrap();
+        assert_eq!(forest, with_bundle(EXECUTABLE.to_vec(), &game_files("forest")).unwrap());
+        assert_eq!(forest.windows(BUNDLE_MAGIC.len()).filter(|window| window == BUNDLE_MAGIC).count(), 1);
+        assert_eq!(find_bundle(&forest), Some(EXECUTABLE.len()));
+    }
+}
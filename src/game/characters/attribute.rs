@@ -1,6 +1,6 @@
+use serde::{Serialize,Deserialize};
 
-
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attribute {
     pub id: String,
     pub display_name: String,
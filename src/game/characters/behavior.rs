@@ -0,0 +1,49 @@
+use serde::{Serialize,Deserialize};
+
+// How often characters that move take a step when nothing else is configured, in game ticks
+pub const DEFAULT_MOVE_EVERY: u64 = 2;
+
+pub fn default_move_every() -> u64 {
+    DEFAULT_MOVE_EVERY
+}
+
+// How a character moves around the map on its own as the game ticks
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behavior {
+    // Stays where it was placed
+    #[default]
+    Stationary,
+    // Takes steps in random directions, staying within `radius` spaces of where it was placed
+    Wander { radius: u16 },
+    // Walks to each waypoint in turn, going back to the first after the last
+    Patrol { waypoints: Vec<Waypoint> },
+    // Walks towards the player whenever they are within `range` spaces, stopping next to them
+    Follow { range: u16 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub x: usize,
+    pub y: usize,
+}
+
+// Makes a character go after the player whenever they come within `aggro_radius` spaces, counting
+//    across and down
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hostility {
+    pub aggro_radius: u16,
+    #[serde(default)]
+    pub on_contact: OnContact,
+}
+
+// What a hostile character does once it has caught up to the player
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnContact {
+    // Starts a fight as soon as it is next to the player, or the player walks onto it
+    #[default]
+    Fight,
+    // Gets in the player's way, since it can't be walked over
+    Block,
+}
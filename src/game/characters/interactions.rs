@@ -1,11 +1,13 @@
+use serde::{Serialize,Deserialize};
+use crate::game::characters::Character;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Interactions {
     pub attacks: Vec<Attack>,
     pub object_use: Vec<ObjectUse>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attack {
     pub id: String,
     pub display_name: String,
@@ -13,16 +15,136 @@ pub struct Attack {
     pub affected_by: Vec<Modifier>,
 }
 
-#[derive(Clone, Debug)]
+impl Attack {
+    // Calculates the damage this attack does when used by the attacker. Each modifier is applied
+    //    once per point the attacker has in the modifier's attribute, and the damage is never
+    //    negative.
+    pub fn calculate_damage(&self, attacker: &Character) -> u8 {
+        let mut damage = self.base_damage as f32;
+        for modifier in &self.affected_by {
+            let points = attacker.get_attribute_value(&modifier.attribute_id);
+            for _ in 0..points {
+                damage = modifier.apply(damage);
+            }
+        }
+        damage.round().clamp(0.0, u8::MAX as f32) as u8
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObjectUse {
     pub object_id: String,
     pub set_dialog: String, // Dialog id. If empty, does not modify dialog
     pub consume_item: bool, // Is the item destroyed upon use?
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Modifier {
     pub attribute_id: String,
     pub sign: char,
     pub value: f32,
-}
\ No newline at end of file
+}
+
+impl Modifier {
+    // Applies a single point of this modifier to the provided value
+    pub fn apply(&self, val: f32) -> f32 {
+        match self.sign {
+            '+' => { val + self.value }
+            '-' => { val - self.value }
+            '*' => { val * self.value }
+            '/' => {
+                if self.value == 0.0 {
+                    val
+                } else {
+                    val / self.value
+                }
+            }
+            _ => { val }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::characters::attribute::Attribute;
+    use crate::game::characters::behavior::Behavior;
+    use crate::game::characters::{Interactions, OnDefeat};
+
+    fn modifier(sign: char, value: f32) -> Modifier {
+        Modifier {
+            attribute_id: String::from("strength"),
+            sign,
+            value,
+        }
+    }
+
+    fn fighter(strength: u8) -> Character {
+        Character {
+            id: String::from("fighter"),
+            name: String::from("Fighter"),
+            attributes: vec![Attribute {
+                id: String::from("strength"),
+                display_name: String::from("Strength"),
+                min_val: 0,
+                max_val: 10,
+                current_val: strength,
+            }],
+            inventory: Vec::new(),
+            equipment_slots: Vec::new(),
+            icon: 'F',
+            interactions: Interactions { attacks: Vec::new(), object_use: Vec::new() },
+            dialog_id: String::new(),
+            behavior: Behavior::Stationary,
+            move_every: 1,
+            home: (0, 0),
+            waypoint: 0,
+            hostile: None,
+            on_defeat: OnDefeat::default(),
+            defeated: false,
+            buffs: Vec::new(),
+        }
+    }
+
+    fn attack(base_damage: i64, affected_by: Vec<Modifier>) -> Attack {
+        Attack {
+            id: String::from("punch"),
+            display_name: String::from("Punch"),
+            base_damage,
+            affected_by,
+        }
+    }
+
+    #[test]
+    fn modifiers_apply_their_sign() {
+        assert_eq!(modifier('+', 2.0).apply(5.0), 7.0);
+        assert_eq!(modifier('-', 2.0).apply(5.0), 3.0);
+        assert_eq!(modifier('*', 2.0).apply(5.0), 10.0);
+        assert_eq!(modifier('/', 2.0).apply(5.0), 2.5);
+    }
+
+    #[test]
+    fn modifiers_leave_the_value_alone_for_unknown_signs_and_zero_divisors() {
+        assert_eq!(modifier('%', 2.0).apply(5.0), 5.0);
+        assert_eq!(modifier('/', 0.0).apply(5.0), 5.0);
+    }
+
+    #[test]
+    fn damage_applies_modifiers_once_per_attribute_point() {
+        let fighter = fighter(3);
+        assert_eq!(attack(4, vec![modifier('+', 2.0)]).calculate_damage(&fighter), 10);
+        assert_eq!(attack(4, vec![modifier('-', 1.0)]).calculate_damage(&fighter), 1);
+        assert_eq!(attack(4, vec![modifier('*', 2.0)]).calculate_damage(&fighter), 32);
+        assert_eq!(attack(40, vec![modifier('/', 2.0)]).calculate_damage(&fighter), 5);
+        assert_eq!(attack(4, vec![modifier('+', 1.0), modifier('*', 2.0)]).calculate_damage(&fighter), 56);
+    }
+
+    #[test]
+    fn damage_is_clamped_to_the_u8_range() {
+        let fighter = fighter(10);
+        assert_eq!(attack(4, vec![modifier('-', 1.0)]).calculate_damage(&fighter), 0);
+        assert_eq!(attack(-5, Vec::new()).calculate_damage(&fighter), 0);
+        assert_eq!(attack(4, vec![modifier('*', 2.0)]).calculate_damage(&fighter), 255);
+        assert_eq!(attack(1000, Vec::new()).calculate_damage(&fighter), 255);
+    }
+}
@@ -1,33 +1,218 @@
+use serde::{Serialize,Deserialize};
 use crate::game::characters::interactions::Interactions;
 use crate::game::characters::attribute::Attribute;
-use crate::game::objects::Object;
+use crate::game::characters::behavior::{Behavior, Hostility};
+use crate::game::objects::{EffectDuration, Object};
 
 pub mod attribute;
+pub mod behavior;
 pub mod interactions;
 
+// Id of the attribute that is reduced when a character takes damage in a fight
+pub const HEALTH_ATTRIBUTE_ID: &str = "health";
 
-#[derive(Clone, Debug)]
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Character {
     pub id: String,
     pub name: String,
     pub attributes: Vec<Attribute>,
     pub inventory: Vec<Vec<Option<Object>>>,
+    // The slots the character can equip items in, such as "head" or "weapon". Equipped items stay
+    //    in the inventory, marked as equipped.
+    #[serde(default)]
+    pub equipment_slots: Vec<String>,
     pub icon: char,
     pub interactions: Interactions,
     pub dialog_id: String,
+    // How the character moves around the map on its own, taking a step every `move_every` ticks
+    #[serde(default)]
+    pub behavior: Behavior,
+    #[serde(default = "behavior::default_move_every")]
+    pub move_every: u64,
+    // Where the character was placed on its map, which wandering stays close to
+    #[serde(default)]
+    pub home: (usize, usize),
+    // The index of the waypoint a patrolling character is walking to
+    #[serde(default)]
+    pub waypoint: usize,
+    #[serde(default)]
+    pub hostile: Option<Hostility>,
+    #[serde(default)]
+    pub on_defeat: OnDefeat,
+    // Whether the player has beaten the character in a fight
+    #[serde(default)]
+    pub defeated: bool,
+    // Changes to attributes that only last for a while, from consumable objects
+    #[serde(default)]
+    pub buffs: Vec<Buff>,
+}
+
+// A temporary change to one of a character's attributes, along with how much longer it lasts
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Buff {
+    pub attribute_id: String,
+    pub amount: i64,
+    pub remaining: EffectDuration,
+}
+
+// What happens to a character once the player beats it in a fight
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OnDefeat {
+    // Whether the character is taken off the map. Characters that stay are healed and are no longer
+    //    hostile.
+    #[serde(default = "default_remove")]
+    pub remove: bool,
+    // Ids of the objects the character drops on the map around where it was, the first time it is
+    //    defeated
+    #[serde(default)]
+    pub loot: Vec<String>,
+    // The dialog a character that stays has afterwards. If this is "", the dialog is not changed.
+    #[serde(default)]
+    pub set_dialog: String,
+}
+
+impl Default for OnDefeat {
+    fn default() -> OnDefeat {
+        OnDefeat { remove: true, loot: vec![], set_dialog: "".to_string() }
+    }
+}
+
+fn default_remove() -> bool {
+    true
 }
 
 impl Character {
-    // Adds specified object to inventory
-    pub fn collect_object(&mut self, object: &Object) {
+    // Adds specified object to inventory. Returns false if the inventory is full.
+    pub fn collect_object(&mut self, object: &Object) -> bool {
         for c in 0..self.inventory.len() {
             for r in 0..self.inventory[c].len() {
                 if self.inventory[c][r].is_none() {
                     self.inventory[c][r] = Option::Some::<Object>(object.to_owned());
-                    return;
+                    return true;
                 }
             }
         }
+        false
+    }
+
+    // Returns true if there is an object with the specified id in the inventory
+    pub fn has_item(&self, object_id: &str) -> bool {
+        self.inventory.iter().flatten().flatten().any(|object| object.id == object_id)
+    }
+
+    // Removes the first object with the specified id from the inventory. Returns false if there
+    //    is none.
+    pub fn take_item(&mut self, object_id: &str) -> bool {
+        for slot in self.inventory.iter_mut().flatten() {
+            if slot.as_ref().is_some_and(|object| object.id == object_id) {
+                *slot = Option::None;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Adds the amount to the attribute with the specified id, or subtracts it if the amount is
+    //    negative, without going past the attribute's minimum or maximum value
+    pub fn change_attribute(&mut self, attribute_id: &str, amount: i64) {
+        for attribute in &mut self.attributes {
+            if attribute.id == attribute_id {
+                let value = (attribute.current_val as i64 + amount)
+                    .clamp(attribute.min_val as i64, attribute.max_val as i64);
+                attribute.current_val = value as u8;
+            }
+        }
+    }
+
+    // Returns the attribute with the specified id, if the character has it
+    pub fn get_attribute(&self, attribute_id: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|a| a.id == attribute_id)
+    }
+
+    // Returns the effective value of the attribute with the specified id, which is its current
+    //    value changed by the modifiers of every equipped item and buff, or 0 if the character does
+    //    not have that attribute
+    pub fn get_attribute_value(&self, attribute_id: &str) -> u8 {
+        match self.get_attribute(attribute_id) {
+            Some(attribute) => {
+                (attribute.current_val as i64 + self.attribute_bonus(attribute_id)).clamp(0, u8::MAX as i64) as u8
+            }
+            None => { 0 }
+        }
+    }
+
+    // The total change made to the attribute with the specified id by equipment and buffs
+    pub fn attribute_bonus(&self, attribute_id: &str) -> i64 {
+        let buffs: i64 = self.buffs.iter()
+            .filter(|buff| buff.attribute_id == attribute_id)
+            .map(|buff| buff.amount)
+            .sum();
+        self.equipment_bonus(attribute_id) + buffs
+    }
+
+    // Counts down every buff that lasts a number of turns (or ticks, if `turns` is false) by one,
+    //    removing the ones that have run out
+    pub fn count_down_buffs(&mut self, turns: bool) {
+        for buff in &mut self.buffs {
+            match &mut buff.remaining {
+                EffectDuration::Turns(left) if turns => { *left = left.saturating_sub(1); }
+                EffectDuration::Ticks(left) if !turns => { *left = left.saturating_sub(1); }
+                _ => {}
+            }
+        }
+        self.buffs.retain(|buff| !matches!(buff.remaining, EffectDuration::Turns(0) | EffectDuration::Ticks(0)));
+    }
+
+    // The items in the inventory that are equipped
+    pub fn equipped_items(&self) -> impl Iterator<Item = &Object> {
+        self.inventory.iter().flatten().flatten().filter(|object| object.equipped)
+    }
+
+    // The total change made to the attribute with the specified id by everything equipped
+    pub fn equipment_bonus(&self, attribute_id: &str) -> i64 {
+        self.equipped_items()
+            .filter_map(|object| object.equip.as_ref())
+            .flat_map(|equipment| &equipment.modifiers)
+            .filter(|modifier| modifier.attribute_id == attribute_id)
+            .map(|modifier| modifier.amount)
+            .sum()
+    }
+
+    // Reduces the character's health by the specified amount, without going below the health
+    //    attribute's minimum value
+    pub fn take_damage(&mut self, damage: u8) {
+        for attribute in &mut self.attributes {
+            if attribute.id == HEALTH_ATTRIBUTE_ID {
+                attribute.current_val = attribute.current_val.saturating_sub(damage).max(attribute.min_val);
+            }
+        }
+    }
+
+    // Sets the character's health back to its maximum value
+    pub fn restore_health(&mut self) {
+        for attribute in &mut self.attributes {
+            if attribute.id == HEALTH_ATTRIBUTE_ID {
+                attribute.current_val = attribute.max_val;
+            }
+        }
+    }
+
+    // How the character goes after the player, if it is hostile and hasn't been defeated yet
+    pub fn hostility(&self) -> Option<&Hostility> {
+        match &self.hostile {
+            Some(hostility) if !self.defeated => { Some(hostility) }
+            _ => { None }
+        }
+    }
+
+    // Returns true if the character has no health left. Characters without a health attribute
+    //    can never be defeated.
+    pub fn is_defeated(&self) -> bool {
+        match self.get_attribute(HEALTH_ATTRIBUTE_ID) {
+            Some(health) => { health.current_val <= health.min_val }
+            None => { false }
+        }
     }
 
 }
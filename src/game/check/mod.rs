@@ -0,0 +1,114 @@
+use std::path::Path;
+use clap::ArgEnum;
+use serde::Serialize;
+use crate::game::config_parsers::GameData;
+use crate::game::config_parsers::errors::ConfigError;
+use crate::game::config_parsers::files::{ConfigFiles, FolderFiles};
+
+// Headless checking of a game's config files, for use in scripts and CI where there is no terminal
+// to run the game in.
+
+#[derive(Clone, Copy, Debug, ArgEnum)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+// The result of a check in the form printed by the JSON output format
+#[derive(Serialize)]
+struct CheckReport<'a> {
+    config_path: &'a Path,
+    errors: usize,
+    warnings: usize,
+    problems: &'a [ConfigError],
+}
+
+// Loads and validates the game in config_path without starting it, then prints every problem found
+//    in the requested format. Returns true if there were no errors (warnings are allowed).
+pub fn run(config_path: &Path, format: OutputFormat) -> bool {
+    let (passed, output) = check(&FolderFiles::new(config_path), format);
+    print!("{}", output);
+    passed
+}
+
+// Checks the game in the config files, returning whether it passed along with what to print
+fn check(files: &dyn ConfigFiles, format: OutputFormat) -> (bool, String) {
+    let config_path = files.root();
+    let (_game_data, problems) = GameData::load_configs(files);
+    let errors = problems.iter().filter(|p| p.is_error()).count();
+    let warnings = problems.len() - errors;
+
+    let mut output = String::new();
+    match format {
+        OutputFormat::Human => {
+            for problem in &problems {
+                output.push_str(&format!("{}\n", problem));
+            }
+            output.push_str(&format!("Checked {}: {} error(s), {} warning(s)\n", config_path.display(), errors, warnings));
+        }
+        OutputFormat::Json => {
+            let report = CheckReport { config_path, errors, warnings, problems: &problems };
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => { output.push_str(&format!("{}\n", json)); }
+                Err(err) => {
+                    eprintln!("ERROR: Unable to format the check results as JSON: {}", err);
+                    return (false, output);
+                }
+            }
+        }
+    }
+
+    (errors == 0, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use serde_json::Value;
+    use super::*;
+    use crate::game::config_parsers::files::MemoryFiles;
+
+    // A game whose only file is a game.yaml with a few things wrong with it
+    fn broken_game() -> MemoryFiles {
+        let mut files = MemoryFiles::new(Path::new("broken_game"));
+        files.add_file("game.yaml", "name: \"Broken\"\ndescription: \"\"\nauthor: \"\"\nstarting_map: 3\n");
+        files
+    }
+
+    #[test]
+    fn broken_games_fail() {
+        let (passed, output) = check(&broken_game(), OutputFormat::Human);
+        assert!(!passed);
+        assert_eq!(output, "broken_game/game.yaml:1:5: 'min_screen_size': Missing required field\n\
+                            broken_game/game.yaml:1:5: 'starting_position': Missing required field\n\
+                            broken_game/game.yaml:4:1: 'starting_map': Expected a string\n\
+                            Checked broken_game: 3 error(s), 0 warning(s)\n");
+    }
+
+    #[test]
+    fn problems_are_reported_as_json() {
+        let (passed, output) = check(&broken_game(), OutputFormat::Json);
+        assert!(!passed);
+        let report: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(report["config_path"], "broken_game");
+        assert_eq!(report["errors"], 3);
+        assert_eq!(report["warnings"], 0);
+        assert_eq!(report["problems"].as_array().unwrap().len(), 3);
+        assert_eq!(report["problems"][2], serde_json::json!({
+            "severity": "error",
+            "path": "broken_game/game.yaml",
+            "line": 4,
+            "column": 1,
+            "field": "starting_map",
+            "message": "Expected a string",
+        }));
+    }
+
+    #[test]
+    fn example_game_passes() {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+        let (passed, output) = check(&FolderFiles::new(&config_path), OutputFormat::Human);
+        assert!(passed, "{}", output);
+        assert!(output.ends_with(": 0 error(s), 0 warning(s)\n"), "{}", output);
+    }
+}
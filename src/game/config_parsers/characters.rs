@@ -1,30 +1,58 @@
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 use serde::{Serialize,Deserialize};
-use crate::game::characters::{Character, interactions};
+use crate::game::characters::{Character, OnDefeat, interactions};
 use crate::game::characters::attribute::Attribute;
+use crate::game::characters::behavior::{Behavior, DEFAULT_MOVE_EVERY, Hostility};
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile, add_source};
 use crate::game::objects::Object;
 
 
 // Reads character config file into a temporary data structure using Serde
-pub fn process_config_serde(characters: &mut HashMap<String, Character>, config_path: &Path) -> Result<(), serde_yaml::Error>{
-    let file_contents = fs::read_to_string(config_path).unwrap();
-    let doc = serde_yaml::from_str::<CharacterData>(&file_contents);
+pub fn process_config_serde(characters: &mut HashMap<String, Character>, config_path: &Path, file_contents: &str,
+                            sources: &mut ConfigSources, errors: &mut Vec<ConfigError>) {
+    let doc = serde_yaml::from_str::<CharacterData>(file_contents);
     match doc {
         Ok(parsed) =>{
-            get_character_from_data( characters, parsed);
+            let file = YamlFile::new(config_path, file_contents);
+            if let Some(character) = get_character_from_data(parsed, &file, errors) {
+                if add_source(&mut sources.characters, "character", &character.id, file, errors) {
+                    characters.insert(character.id.clone(), character);
+                }
+            }
         }
         Err(err) =>{
-            eprintln!("{}", err);
+            errors.push(ConfigError::from_serde(config_path, &err));
         }
     }
-    Ok(())
 }
 
-// Converts temporary data structure in to a Character structure and adds it to the characters list
-// so that it can later be added to the game map.
-fn get_character_from_data(characters: &mut HashMap<String, Character>, data: CharacterData) {
+// Converts temporary data structure in to a Character structure so that it can be added to the
+// characters list and later added to the game map. Returns None if the data has problems.
+fn get_character_from_data(data: CharacterData, file: &YamlFile, errors: &mut Vec<ConfigError>) -> Option<Character> {
+    let error_count = errors.len();
+    if data.inventory_size.width < 0 {
+        errors.push(file.error("inventory_size.width", "Expected a non-negative integer"));
+    }
+    if data.inventory_size.height < 0 {
+        errors.push(file.error("inventory_size.height", "Expected a non-negative integer"));
+    }
+    for (i, slot) in data.equipment_slots.iter().enumerate() {
+        if data.equipment_slots[..i].contains(slot) {
+            errors.push(file.error(&format!("equipment_slots[{}]", i),
+                                   format!("The equipment slot '{}' is already declared", slot)));
+        }
+    }
+    if data.move_every.is_some_and(|move_every| move_every <= 0) {
+        errors.push(file.error("move_every", "Expected an integer greater than 0"));
+    }
+    if matches!(&data.behavior, Behavior::Patrol { waypoints } if waypoints.is_empty()) {
+        errors.push(file.error("behavior.patrol.waypoints", "Patrols need at least one waypoint"));
+    }
+    if errors.len() > error_count {
+        return None;
+    }
+
     let interactions = interactions::Interactions {
         attacks: vec![],
         object_use: vec![],
@@ -34,9 +62,18 @@ fn get_character_from_data(characters: &mut HashMap<String, Character>, data: Ch
         name: "".to_string(),
         attributes: vec![],
         inventory: vec![],
+        equipment_slots: vec![],
         icon: ' ',
         interactions,
         dialog_id: "".to_string(),
+        behavior: Behavior::Stationary,
+        move_every: DEFAULT_MOVE_EVERY,
+        home: (0, 0),
+        waypoint: 0,
+        hostile: Option::<Hostility>::None,
+        on_defeat: OnDefeat::default(),
+        defeated: false,
+        buffs: vec![],
     };
     character.inventory.resize(data.inventory_size.width as usize, vec![] );
     for i in 0..(data.inventory_size.width) {
@@ -45,8 +82,17 @@ fn get_character_from_data(characters: &mut HashMap<String, Character>, data: Ch
     character.id = data.id;
     character.name = data.name;
     character.icon = data.icon;
+    character.equipment_slots = data.equipment_slots;
     character.dialog_id = data.dialog_id;
-    for attribute_data in data.traits {
+    character.behavior = data.behavior;
+    character.move_every = data.move_every.unwrap_or(DEFAULT_MOVE_EVERY as i64) as u64;
+    character.hostile = data.hostile;
+    character.on_defeat = data.on_defeat;
+    for (i, attribute_data) in data.traits.into_iter().enumerate() {
+        if attribute_data.starting_value > attribute_data.max_value {
+            errors.push(file.error(&format!("traits[{}].starting_value", i),
+                                   "Starting value can't be greater than max_value"));
+        }
         let attribute = Attribute {
             id: attribute_data.id,
             display_name: attribute_data.display_name,
@@ -64,25 +110,46 @@ fn get_character_from_data(characters: &mut HashMap<String, Character>, data: Ch
         };
         character.interactions.object_use.push(object_use);
     }
-    for attack_data in data.interactions.attacks {
+    for (i, attack_data) in data.interactions.attacks.into_iter().enumerate() {
         let mut attack = interactions::Attack {
             id: attack_data.id,
             display_name: attack_data.display_name,
             base_damage: attack_data.base_damage,
             affected_by: vec![],
         };
-        for modifier_data in attack_data.affected_by {
-            let sign = modifier_data.effect_per_point.chars().next().unwrap();
-            let value: f32 = modifier_data.effect_per_point[1..].parse().unwrap();
-            attack.affected_by.push(interactions::Modifier {
-                attribute_id: modifier_data.attribute_id,
-                sign,
-                value,
-            });
+        for (j, modifier_data) in attack_data.affected_by.into_iter().enumerate() {
+            let effect = &modifier_data.effect_per_point;
+            let sign = effect.chars().next().unwrap_or(' ');
+            let value = effect.get(1..).and_then(|v| v.parse::<f32>().ok());
+            match value {
+                Some(value) if sign == '/' && value == 0.0 => {
+                    errors.push(file.error(
+                        &format!("interactions.attacks[{}].affected_by[{}].effect_per_point", i, j),
+                        "Attacks can't be divided by 0"));
+                }
+                Some(value) if "+-*/".contains(sign) => {
+                    attack.affected_by.push(interactions::Modifier {
+                        attribute_id: modifier_data.attribute_id,
+                        sign,
+                        value,
+                    });
+                }
+                _ => {
+                    errors.push(file.error(
+                        &format!("interactions.attacks[{}].affected_by[{}].effect_per_point", i, j),
+                        format!("Expected one of +, -, *, or / followed by a number, found '{}'", effect)));
+                }
+            }
         }
         character.interactions.attacks.push(attack);
     }
-    characters.insert(character.id.clone(), character);
+
+    // Only add characters that were fully loaded
+    if errors.len() == error_count {
+        Some(character)
+    } else {
+        None
+    }
 }
 
 // Temporary data structure that is used for Serde parsing and nothing else
@@ -92,9 +159,20 @@ pub struct CharacterData {
     pub name: String,
     pub icon: char,
     pub inventory_size: InventorySize,
+    #[serde(default)]
+    pub equipment_slots: Vec<String>,
     pub traits: Vec<Trait>,
     pub interactions: Interactions,
     pub dialog_id: String,
+    #[serde(default)]
+    pub behavior: Behavior,
+    // How many ticks go by between each step the character takes
+    #[serde(default)]
+    pub move_every: Option<i64>,
+    #[serde(default)]
+    pub hostile: Option<Hostility>,
+    #[serde(default)]
+    pub on_defeat: OnDefeat,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -136,4 +214,112 @@ pub struct ObjectUseData {
     pub object_id: String,
     pub set_dialog: String,
     pub consume_item: bool,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::game::characters::behavior::{Behavior, Hostility, OnContact, Waypoint};
+    use crate::game::config_parsers::tests::load_example_game_with;
+    use crate::game::maps::MapData;
+
+    // The example game's enemy, which patrols the Secondary Room, with its behavior swapped out
+    fn enemy_with(behavior: &str) -> String {
+        let enemy = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game/characters/enemy_1.yaml"))
+            .unwrap();
+        enemy[..enemy.find("behavior:").unwrap()].to_string() + behavior
+    }
+
+    #[test]
+    fn characters_have_behaviors() {
+        let enemy = enemy_with("behavior:\n  wander: { radius: 2 }\n");
+        let (game_data, problems) = load_example_game_with(&[("characters/enemy_1.yaml", enemy.as_str())]);
+        assert!(problems.iter().all(|p| !p.is_error()), "{:?}", problems);
+        let map = game_data.maps.iter().find(|m| m.info.id == "Secondary Room").unwrap();
+        match &map.grid[9][1] {
+            Some(MapData::Character(character)) => {
+                assert_eq!(character.behavior, Behavior::Wander { radius: 2 });
+                assert_eq!(character.move_every, 2);
+                assert_eq!(character.home, (9, 1));
+            }
+            space => { panic!("Expected the enemy at (9, 1), found {:?}", space); }
+        }
+
+        let (game_data, _) = load_example_game_with(&[]);
+        let player = game_data.info.player.unwrap();
+        assert_eq!(player.behavior, Behavior::Stationary);
+        let map = game_data.maps.iter().find(|m| m.info.id == "Secondary Room").unwrap();
+        match &map.grid[9][1] {
+            Some(MapData::Character(character)) => {
+                assert!(matches!(&character.behavior, Behavior::Patrol { waypoints }
+                                 if waypoints[1] == Waypoint { x: 9, y: 3 }));
+                assert_eq!(character.move_every, 3);
+                assert_eq!(character.hostile, Some(Hostility { aggro_radius: 3, on_contact: OnContact::Fight }));
+                assert!(character.on_defeat.remove);
+                assert_eq!(character.on_defeat.loot, vec!["coin"]);
+            }
+            space => { panic!("Expected the enemy at (9, 1), found {:?}", space); }
+        }
+    }
+
+    #[test]
+    fn behavior_problems_are_reported() {
+        let enemy = enemy_with("behavior:\n  patrol: { waypoints: [] }\nmove_every: 0\n");
+        let (_, problems) = load_example_game_with(&[("characters/enemy_1.yaml", enemy.as_str())]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "behavior.patrol.waypoints: Patrols need at least one waypoint",
+            "move_every: Expected an integer greater than 0",
+        ]);
+
+        let enemy = enemy_with("behavior:\n  patrol: { waypoints: [ { x: 9, y: 3 }, { x: 20, y: 1 }, { x: 0, y: 2 } ] }\n");
+        let (_, problems) = load_example_game_with(&[("characters/enemy_1.yaml", enemy.as_str())]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "behavior.patrol.waypoints[1]: The waypoint (20, 1) is outside of map 'Secondary Room'",
+            "behavior.patrol.waypoints[2]: The waypoint (0, 2) in map 'Secondary Room' is on a tile that can't be walked on",
+        ]);
+
+        let enemy = enemy_with("hostile: { aggro_radius: 2, on_contact: \"block\" }\n\
+                                on_defeat: { remove: false, loot: [ \"coin\", \"gem\" ], set_dialog: \"AA beaten\" }\n");
+        let (_, problems) = load_example_game_with(&[("characters/enemy_1.yaml", enemy.as_str())]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "on_defeat.loot[1]: No object with the id 'gem' exists",
+            "on_defeat.set_dialog: No dialog with the id 'AA beaten' exists",
+        ]);
+    }
+
+    #[test]
+    fn equipment_slots_are_declared_once() {
+        let enemy = enemy_with("equipment_slots: [ \"head\", \"weapon\", \"head\" ]\n");
+        let (_, problems) = load_example_game_with(&[("characters/enemy_1.yaml", enemy.as_str())]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec!["equipment_slots[2]: The equipment slot 'head' is already declared"]);
+    }
+
+    #[test]
+    fn attack_modifier_problems_are_reported() {
+        let player = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game/characters/player.yaml"))
+            .unwrap()
+            .replace("effect_per_point: \"-1\"", "effect_per_point: \"/0\"")
+            .replace("effect_per_point: \"*1.2\"", "effect_per_point: \"^2\"");
+        let (_, problems) = load_example_game_with(&[("characters/player.yaml", player.as_str())]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "interactions.attacks[0].affected_by[1].effect_per_point: Attacks can't be divided by 0",
+            "interactions.attacks[0].affected_by[2].effect_per_point: Expected one of +, -, *, or / followed by a number, found '^2'",
+        ]);
+    }
+}
@@ -1,20 +1,315 @@
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
-use crate::game::dialogs::Dialog;
+use std::rc::Rc;
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile};
+use crate::game::config_parsers::objects::{infer_state_value, split_comparison, split_operation};
+use crate::game::dialogs::{Dialog, DialogCondition, DialogEffect};
+use crate::game::objects::{Comparison, StateValue};
 
-pub fn process_config_serde(dialogs: &mut HashMap<String, Dialog>, config_path: &Path) -> Result<(), serde_yaml::Error>{
-    let file_contents = fs::read_to_string(config_path).unwrap();
-    let doc = serde_yaml::from_str::<Vec<Dialog>>(&file_contents);
+pub fn process_config_serde(dialogs: &mut HashMap<String, Dialog>, config_path: &Path, file_contents: &str,
+                            sources: &mut ConfigSources, errors: &mut Vec<ConfigError>) {
+    let doc = serde_yaml::from_str::<Vec<Dialog>>(file_contents);
     match doc {
         Ok(parsed) =>{
-            for dialog in parsed {
-                dialogs.insert(dialog.id.clone(), dialog.clone());
+            let file = Rc::new(YamlFile::new(config_path, file_contents));
+            for (i, mut dialog) in parsed.into_iter().enumerate() {
+                if let Some((existing, _)) = sources.dialogs.get(&dialog.id) {
+                    errors.push(file.error(&format!("[{}].id", i),
+                                           format!("The dialog id '{}' is already used in {}", dialog.id,
+                                                   existing.path.display())));
+                    continue;
+                }
+                check_options(&mut dialog, &file, i, errors);
+                sources.dialogs.insert(dialog.id.clone(), (file.clone(), i));
+                dialogs.insert(dialog.id.clone(), dialog);
             }
         }
         Err(err) =>{
-            eprintln!("{}", err);
+            errors.push(ConfigError::from_serde(config_path, &err));
         }
     }
-    Ok(())
-}
\ No newline at end of file
+}
+
+// Moves any options given as option_0 and option_1 into the dialog's list of options, and makes sure
+//    the dialog has at least one option to choose. Comparisons in the options' conditions, and
+//    additions and subtractions in their effects, are split off their values.
+fn check_options(dialog: &mut Dialog, file: &YamlFile, index: usize, errors: &mut Vec<ConfigError>) {
+    if dialog.has_legacy_options() {
+        if !dialog.options.is_empty() {
+            errors.push(file.error(&format!("[{}].options", index),
+                                   "Give the options either as a list or as option_0 and option_1, not both"));
+        }
+        dialog.options = dialog.option_0.iter().chain(dialog.option_1.iter()).cloned().collect();
+    }
+    if dialog.options.is_empty() {
+        errors.push(file.error(&format!("[{}]", index), "Dialogs need at least one option to choose"));
+    }
+    for (i, option) in dialog.options.iter_mut().enumerate() {
+        split_conditions(&mut option.conditions);
+        split_effects(&mut option.effects, file, &format!("[{}].options[{}].effects", index, i), errors);
+    }
+}
+
+// Splits the comparisons off the values of conditions that compare values
+pub fn split_conditions(conditions: &mut [DialogCondition]) {
+    for condition in conditions {
+        match condition {
+            DialogCondition::Variable(variable) => {
+                (variable.comparison, variable.value) = split_value(&variable.value);
+            }
+            DialogCondition::Attribute(attribute) => {
+                (attribute.comparison, attribute.value) = split_value(&attribute.value);
+            }
+            DialogCondition::State(state) => {
+                (state.comparison, state.value) = split_value(&state.value);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Splits any "+=" or "-=" off the values of effects that change global variables. The effects are
+//    found in the given field of the file.
+pub fn split_effects(effects: &mut [DialogEffect], file: &YamlFile, field: &str, errors: &mut Vec<ConfigError>) {
+    for (i, effect) in effects.iter_mut().enumerate() {
+        if let DialogEffect::SetVariable(change) = effect {
+            let amount = match &change.value {
+                StateValue::Str(text) => { split_operation(text) }
+                _ => { None }
+            };
+            if let Some((operation, amount)) = amount {
+                match amount.trim().parse::<i64>() {
+                    Ok(amount) => {
+                        change.operation = operation;
+                        change.value = StateValue::Int(amount);
+                    }
+                    Err(_) => {
+                        errors.push(file.error(&format!("{}[{}].set_variable.value", field, i),
+                                               "Expected an integer to add or subtract"));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Splits the comparison off the start of a condition's value, like `">= 3"`, the same way as for
+//    object prereqs. Values without one must be equal.
+fn split_value(value: &StateValue) -> (Comparison, StateValue) {
+    match value {
+        StateValue::Str(text) => {
+            match split_comparison(text) {
+                Some((comparison, rest)) => { (comparison, infer_state_value(rest)) }
+                None => { (Comparison::Equal, value.clone()) }
+            }
+        }
+        _ => { (Comparison::Equal, value.clone()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::config_parsers::tests::{example_game_yaml_with_variables, load_example_game_with};
+
+    #[test]
+    fn dialogs_have_lists_of_options() {
+        let (game_data, problems) = load_example_game_with(&[("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "Hello."
+   options:
+      - dialog: "One"
+        next: "exit"
+      - dialog: "Two"
+        next: "inventory"
+      - dialog: "Three"
+        next: "old"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   options:
+      - dialog: "Bye."
+        next: "exit"
+ - id: "old"
+   npc_dialog: "Written before dialogs had lists of options."
+   option_0:
+      dialog: "Fight"
+      next: "fight"
+   option_1:
+      dialog: "Leave"
+      next: "exit"
+"##)]);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let options = |id: &str| game_data.dialogs[id].options.iter().map(|o| o.dialog.clone()).collect::<Vec<_>>();
+        assert_eq!(options("AA start"), vec!["One", "Two", "Three"]);
+        assert_eq!(options("AA give hat"), vec!["Bye."]);
+        assert_eq!(options("old"), vec!["Fight", "Leave"]);
+    }
+
+    #[test]
+    fn option_problems_are_reported() {
+        let (_, problems) = load_example_game_with(&[("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "Hello."
+   options:
+      - dialog: "One"
+        next: "exit"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   options:
+      - dialog: "Bye."
+        next: "exit"
+   option_0:
+      dialog: "Bye."
+      next: "exit"
+ - id: "silent"
+   npc_dialog: "..."
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "[1].options: Give the options either as a list or as option_0 and option_1, not both",
+            "[2]: Dialogs need at least one option to choose",
+        ]);
+
+        let (_, problems) = load_example_game_with(&[("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "Hello."
+   options:
+      - dialog: "One"
+        next: "exit"
+      - dialog: "Two"
+        next: "nowhere"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   option_0:
+      dialog: "Bye."
+      next: "gone"
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "[0].options[1].next: 'nowhere' is not a dialog id or one of the keywords exit, inventory, fight",
+            "[1].option_0.next: 'gone' is not a dialog id or one of the keywords exit, inventory, fight",
+        ]);
+    }
+
+    #[test]
+    fn condition_and_effect_problems_are_reported() {
+        let (_, problems) = load_example_game_with(&[("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "Hello."
+   options:
+      - dialog: "Everything"
+        conditions:
+          - has_item: "crown"
+          - flag: "anything"
+          - attribute: { id: "luck", value: ">= 3" }
+          - attribute: { id: "health", value: "lots" }
+          - state: { object: "door_1@Main Room:10,9", state: "unlocked", value: "> 1" }
+          - state: { object: "door_9", state: "unlocked", value: true }
+        effects:
+          - give_item: "hat"
+          - take_item: "crown"
+          - set_state: { object: "door_1@Main Room:10,9", state: "open", value: true }
+          - change_attribute: { id: "luck", amount: 1 }
+          - set_dialog: "nowhere"
+          - travel: { map: "attic", spawn: "stairs" }
+          - travel: { map: "basement", spawn: "stairs" }
+        next: "exit"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   options:
+      - dialog: "Bye."
+        next: "exit"
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        let option = "[0].options[0]";
+        assert_eq!(problems, vec![
+            format!("{}.conditions[0].has_item: No object with the id 'crown' exists", option),
+            format!("{}.conditions[1].flag: No global variable named 'anything' is declared in game.yaml", option),
+            format!("{}.conditions[2].attribute.id: The player has no attribute with the id 'luck'", option),
+            format!("{}.conditions[3].attribute.value: Attributes have integer values, but 'health' is compared to \
+                     the string 'lots'", option),
+            format!("{}.conditions[4].state: The state 'unlocked' has boolean values, but is compared to the integer 1",
+                    option),
+            format!("{}.conditions[5].state.object: No object placed in a map has the instance id 'door_9'", option),
+            format!("{}.effects[1].take_item: No object with the id 'crown' exists", option),
+            format!("{}.effects[2].set_state: The object 'door_1' has no state named 'open'", option),
+            format!("{}.effects[3].change_attribute.id: The player has no attribute with the id 'luck'", option),
+            format!("{}.effects[4].set_dialog: No dialog with the id 'nowhere' exists", option),
+            format!("{}.effects[5].travel.map: No map with the id 'attic' exists", option),
+            format!("{}.effects[6].travel.spawn: The map 'basement' has no spawn with the id 'stairs'", option),
+        ]);
+    }
+
+    #[test]
+    fn variable_problems_are_reported() {
+        let game_yaml = example_game_yaml_with_variables("  bells_rung: 0\n  weather: \"rain\"\n");
+        let (_, problems) = load_example_game_with(&[("game.yaml", &game_yaml), ("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "Hello."
+   options:
+      - dialog: "Everything"
+        conditions:
+          - variable: { id: "bells_rung", value: ">= 3" }
+          - variable: { id: "weather", value: "> sun" }
+          - variable: { id: "season", value: "winter" }
+          - flag: "weather"
+        effects:
+          - set_variable: { id: "bells_rung", value: "+= 1" }
+          - set_variable: { id: "bells_rung", value: "+= lots" }
+          - set_variable: { id: "weather", value: 3 }
+          - clear_flag: "bells_rung"
+        next: "exit"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   options:
+      - dialog: "Bye."
+        next: "exit"
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec!["[0].options[0].effects[1].set_variable.value: Expected an integer to add or subtract"]);
+
+        let (_, problems) = load_example_game_with(&[("game.yaml", &game_yaml), ("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "Hello."
+   options:
+      - dialog: "Everything"
+        conditions:
+          - variable: { id: "bells_rung", value: ">= 3" }
+          - variable: { id: "weather", value: "> sun" }
+          - variable: { id: "season", value: "winter" }
+          - flag: "weather"
+        effects:
+          - set_variable: { id: "bells_rung", value: "+= 1" }
+          - set_variable: { id: "weather", value: 3 }
+          - clear_flag: "bells_rung"
+        next: "exit"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   options:
+      - dialog: "Bye."
+        next: "exit"
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        let option = "[0].options[0]";
+        assert_eq!(problems, vec![
+            format!("{}.conditions[1].variable: The global variable 'weather' has string values, so it can only be \
+                     compared with == or !=", option),
+            format!("{}.conditions[2].variable: No global variable named 'season' is declared in game.yaml", option),
+            format!("{}.conditions[3].flag: The global variable 'weather' has string values, so it can't be used as \
+                     a flag", option),
+            format!("{}.effects[1].set_variable: The global variable 'weather' has string values, but is set to the \
+                     integer 3", option),
+            format!("{}.effects[2].clear_flag: The global variable 'bells_rung' has integer values, so it can't be \
+                     used as a flag", option),
+        ]);
+    }
+}
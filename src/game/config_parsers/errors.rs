@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use serde::Serialize;
+use yaml_rust::{Yaml, YamlLoader};
+use yaml_rust::yaml::Hash;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+// How serious a problem is. Errors stop the game from starting, while warnings are only reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// A single problem found in the configuration files. Line and column are 1-based and only present
+// when the problem can be traced to a spot in the file, and field is the path to the offending
+// value within the file (e.g. "interactions.attacks[0].base_damage").
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigError {
+    pub severity: Severity,
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl ConfigError {
+    // Creates an error that applies to a file (or folder) as a whole
+    pub fn new(path: &Path, message: impl Into<String>) -> ConfigError {
+        ConfigError {
+            severity: Severity::Error,
+            path: path.to_path_buf(),
+            line: None,
+            column: None,
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    // Converts a Serde parsing error into a ConfigError. Serde includes the path to the field
+    //    at the start of the message (e.g. "traits[0]: missing field `id` at line 2 column 5"),
+    //    so that is split out into the field name.
+    pub fn from_serde(path: &Path, err: &serde_yaml::Error) -> ConfigError {
+        let mut message = err.to_string();
+        let (mut line, mut column) = (None, None);
+        if let Some(location) = err.location() {
+            line = Some(location.line());
+            column = Some(location.column());
+            let suffix = format!(" at line {} column {}", location.line(), location.column());
+            if let Some(stripped) = message.strip_suffix(&suffix) {
+                message = stripped.to_string();
+            }
+        }
+
+        let mut field = None;
+        if let Some((prefix, rest)) = message.split_once(": ") {
+            if !prefix.contains(' ') {
+                field = Some(prefix.trim_start_matches('.').to_string());
+                message = rest.to_string();
+            }
+        }
+        if let Some(missing) = message.strip_prefix("missing field `").and_then(|m| m.strip_suffix('`')) {
+            field = match field {
+                Some(parent) => { Some(format!("{}.{}", parent, missing)) }
+                None => { Some(missing.to_string()) }
+            };
+        }
+
+        ConfigError { severity: Severity::Error, path: path.to_path_buf(), line, column, field, message }
+    }
+
+    // Turns this error into a warning
+    pub fn warning(mut self) -> ConfigError {
+        self.severity = Severity::Warning;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.severity == Severity::Warning {
+            write!(f, "warning: ")?;
+        }
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        if let Some(field) = &self.field {
+            write!(f, ": '{}'", field)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+// Where each piece of content was loaded from, so that problems found once everything is loaded
+// can still be reported against the right spot in the right file. Dialogs share a file, so each
+// dialog also keeps its index within that file.
+#[derive(Debug, Default)]
+pub struct ConfigSources {
+    pub game: Option<YamlFile>,
+    pub maps: HashMap<String, YamlFile>,
+    pub objects: HashMap<String, YamlFile>,
+    pub characters: HashMap<String, YamlFile>,
+    pub dialogs: HashMap<String, (Rc<YamlFile>, usize)>,
+    pub quests: HashMap<String, YamlFile>,
+}
+
+// Records the file that content with the given id came from, adding an error if another file
+//    already used the same id. Returns true if the id was not already used.
+pub fn add_source(sources: &mut HashMap<String, YamlFile>, kind: &str, id: &str, file: YamlFile,
+                  errors: &mut Vec<ConfigError>) -> bool {
+    if let Some(existing) = sources.get(id) {
+        errors.push(file.error("id", format!("The {} id '{}' is already used in {}", kind, id,
+                                             existing.path.display())));
+        return false;
+    }
+    sources.insert(id.to_string(), file);
+    true
+}
+
+// A YAML file loaded with yaml_rust, along with the position of every field in it so that
+// errors found while processing the values can point back to the right spot in the file.
+#[derive(Debug)]
+pub struct YamlFile {
+    pub path: PathBuf,
+    positions: HashMap<String, Marker>,
+}
+
+impl YamlFile {
+    // Records the position of every field in the file contents. Contents that can't be parsed
+    //    just end up with no positions.
+    pub fn new(config_path: &Path, file_contents: &str) -> YamlFile {
+        let mut recorder = PositionRecorder { stack: vec![], positions: HashMap::new() };
+        let _ = Parser::new(file_contents.chars()).load(&mut recorder, false);
+        YamlFile { path: config_path.to_path_buf(), positions: recorder.positions }
+    }
+
+    // Parses the file contents, returning the first document in it. Problems parsing the file are
+    //    added to the errors list.
+    pub fn load(config_path: &Path, file_contents: &str, errors: &mut Vec<ConfigError>) -> Option<(Yaml, YamlFile)> {
+        let mut docs = match YamlLoader::load_from_str(file_contents) {
+            Ok(docs) => { docs }
+            Err(err) => {
+                let mut error = ConfigError::new(config_path, err.to_string());
+                error.line = Some(err.marker().line());
+                error.column = Some(err.marker().col() + 1);
+                errors.push(error);
+                return None;
+            }
+        };
+        if docs.is_empty() {
+            errors.push(ConfigError::new(config_path, "File is empty"));
+            return None;
+        }
+
+        Some((docs.swap_remove(0), YamlFile::new(config_path, file_contents)))
+    }
+
+    // Creates an error for the specified field. If the field itself isn't in the file (such as
+    //    when it is missing), the position of the closest parent is used instead.
+    pub fn error(&self, field: &str, message: impl Into<String>) -> ConfigError {
+        let mut error = ConfigError::new(&self.path, message);
+        error.field = Some(field.to_string());
+
+        let mut lookup = field;
+        loop {
+            if let Some(marker) = self.positions.get(lookup) {
+                error.line = Some(marker.line());
+                error.column = Some(marker.col() + 1);
+                break;
+            }
+            if lookup.is_empty() {
+                break;
+            }
+            match lookup.rfind(['.', '[']) {
+                Some(i) => { lookup = &lookup[..i]; }
+                None => { lookup = ""; }
+            }
+        }
+
+        error
+    }
+
+    // The functions below convert a YAML value into the expected type, adding an error for the
+    //    field if the value is a different type.
+
+    pub fn get_str(&self, field: &str, val: &Yaml, errors: &mut Vec<ConfigError>) -> Option<String> {
+        match val.as_str() {
+            Some(s) => { Some(s.to_string()) }
+            None => {
+                errors.push(self.error(field, "Expected a string"));
+                None
+            }
+        }
+    }
+
+    pub fn get_i64(&self, field: &str, val: &Yaml, errors: &mut Vec<ConfigError>) -> Option<i64> {
+        match val.as_i64() {
+            Some(i) => { Some(i) }
+            None => {
+                errors.push(self.error(field, "Expected an integer"));
+                None
+            }
+        }
+    }
+
+    pub fn get_bool(&self, field: &str, val: &Yaml, errors: &mut Vec<ConfigError>) -> Option<bool> {
+        match val.as_bool() {
+            Some(b) => { Some(b) }
+            None => {
+                errors.push(self.error(field, "Expected a boolean"));
+                None
+            }
+        }
+    }
+
+    pub fn get_hash<'a>(&self, field: &str, val: &'a Yaml, errors: &mut Vec<ConfigError>) -> Option<&'a Hash> {
+        match val.as_hash() {
+            Some(h) => { Some(h) }
+            None => {
+                errors.push(self.error(field, "Expected a mapping of fields"));
+                None
+            }
+        }
+    }
+
+    pub fn get_vec<'a>(&self, field: &str, val: &'a Yaml, errors: &mut Vec<ConfigError>) -> Option<&'a [Yaml]> {
+        match val.as_vec() {
+            Some(v) => { Some(v) }
+            None => {
+                errors.push(self.error(field, "Expected a list"));
+                None
+            }
+        }
+    }
+
+    // Looks up a required field in a mapping, adding an error if it is missing
+    pub fn get_field<'a>(&self, parent: &str, hash: &'a Hash, field: &str, errors: &mut Vec<ConfigError>) -> Option<&'a Yaml> {
+        match hash.get(&Yaml::String(field.to_string())) {
+            Some(val) => { Some(val) }
+            None => {
+                errors.push(self.error(&field_path(parent, field), "Missing required field"));
+                None
+            }
+        }
+    }
+}
+
+// Joins a field name onto the path of its parent
+pub fn field_path(parent: &str, field: &str) -> String {
+    if parent.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", parent, field)
+    }
+}
+
+// Appends an index onto the path of its parent sequence
+pub fn index_path(parent: &str, index: usize) -> String {
+    format!("{}[{}]", parent, index)
+}
+
+enum Frame {
+    Mapping { key: Option<String>, expecting_key: bool },
+    Sequence { index: usize },
+    ComplexKey { depth: usize },
+}
+
+// Follows the parser events to record the position of every key and sequence item
+struct PositionRecorder {
+    stack: Vec<Frame>,
+    positions: HashMap<String, Marker>,
+}
+
+impl PositionRecorder {
+    fn current_path(&self) -> String {
+        let mut path = String::new();
+        for frame in &self.stack {
+            match frame {
+                Frame::Mapping { key: Some(key), .. } => { path = field_path(&path, key); }
+                Frame::Mapping { key: None, .. } => {}
+                Frame::Sequence { index } => { path = index_path(&path, *index); }
+                Frame::ComplexKey { .. } => {}
+            }
+        }
+        path
+    }
+
+    // Moves the innermost container on to its next key or item
+    fn finish_value(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping { expecting_key, .. }) => { *expecting_key = true; }
+            Some(Frame::Sequence { index }) => { *index += 1; }
+            Some(Frame::ComplexKey { .. }) | None => {}
+        }
+    }
+}
+
+impl MarkedEventReceiver for PositionRecorder {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        // Complex keys are skipped over rather than tracked
+        if let Some(Frame::ComplexKey { depth }) = self.stack.last_mut() {
+            match ev {
+                Event::MappingStart(..) | Event::SequenceStart(..) => { *depth += 1; }
+                Event::MappingEnd | Event::SequenceEnd => {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        self.stack.pop();
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match ev {
+            Event::Scalar(..) | Event::Alias(..) | Event::MappingStart(..) | Event::SequenceStart(..) => {
+                // Keys are recorded at their own position, and then the value that follows belongs to them
+                if let Some(Frame::Mapping { key, expecting_key }) = self.stack.last_mut() {
+                    if *expecting_key {
+                        *expecting_key = false;
+                        *key = match &ev {
+                            Event::Scalar(value, ..) => { Some(value.clone()) }
+                            _ => { None }
+                        };
+                        if key.is_some() {
+                            let path = self.current_path();
+                            self.positions.entry(path).or_insert(mark);
+                        }
+                        if let Event::MappingStart(..) | Event::SequenceStart(..) = ev {
+                            self.stack.push(Frame::ComplexKey { depth: 1 });
+                        }
+                        return;
+                    }
+                }
+
+                if let Some(Frame::Sequence { .. }) | None = self.stack.last() {
+                    let path = self.current_path();
+                    self.positions.entry(path).or_insert(mark);
+                }
+
+                match ev {
+                    Event::MappingStart(..) => {
+                        self.stack.push(Frame::Mapping { key: None, expecting_key: true });
+                    }
+                    Event::SequenceStart(..) => {
+                        self.stack.push(Frame::Sequence { index: 0 });
+                    }
+                    _ => { self.finish_value(); }
+                }
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.finish_value();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const QUEST: &str = r##"id: "lost_hat"
+stages:
+  - description: "Find the hat"
+    conditions:
+      - has_item: "hat"
+      - attribute: { id: "skill_3", value: "> 5" }
+rewards:
+  give:
+    item: "coin"
+"##;
+
+    fn position(file: &YamlFile, field: &str) -> (Option<usize>, Option<usize>) {
+        let error = file.error(field, "Problem");
+        assert_eq!(error.field.as_deref(), Some(field));
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn fields_are_found_in_nested_mappings() {
+        let file = YamlFile::new(Path::new("quest.yaml"), QUEST);
+        assert_eq!(position(&file, "id"), (Some(1), Some(1)));
+        assert_eq!(position(&file, "rewards"), (Some(7), Some(1)));
+        assert_eq!(position(&file, "rewards.give"), (Some(8), Some(3)));
+        assert_eq!(position(&file, "rewards.give.item"), (Some(9), Some(5)));
+    }
+
+    // Mappings that are items of a sequence are placed by yaml_rust at the colon after their first
+    //    key, so only their line is checked
+    #[test]
+    fn fields_are_found_in_sequences() {
+        let file = YamlFile::new(Path::new("quest.yaml"), QUEST);
+        assert_eq!(position(&file, "stages[0]").0, Some(3));
+        assert_eq!(position(&file, "stages[0].conditions"), (Some(4), Some(5)));
+        assert_eq!(position(&file, "stages[0].conditions[0].has_item"), (Some(5), Some(9)));
+        assert_eq!(position(&file, "stages[0].conditions[1]").0, Some(6));
+        assert_eq!(position(&file, "stages[0].conditions[1].attribute.value"), (Some(6), Some(37)));
+    }
+
+    #[test]
+    fn missing_fields_use_the_closest_parent() {
+        let file = YamlFile::new(Path::new("quest.yaml"), QUEST);
+        assert_eq!(position(&file, "stages[0].conditions[1].attribute.amount"), (Some(6), Some(9)));
+        assert_eq!(position(&file, "stages[3].description"), (Some(2), Some(1)));
+        // Fields missing from the top of the file fall back to the document itself
+        assert_eq!(position(&file, "name").0, Some(1));
+        assert_eq!(position(&YamlFile::new(Path::new("empty.yaml"), ""), "id"), (None, None));
+
+        let hash = Hash::new();
+        let mut errors = vec![];
+        assert!(file.get_field("rewards.give", &hash, "amount", &mut errors).is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "quest.yaml:8:3: 'rewards.give.amount': Missing required field");
+    }
+
+    #[test]
+    fn unparseable_files_are_reported_where_they_break() {
+        let mut errors = vec![];
+        assert!(YamlFile::load(Path::new("broken.yaml"), "id: \"broken\"\nname: [\n", &mut errors).is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].path.clone()), (Some(3), PathBuf::from("broken.yaml")));
+
+        assert!(YamlFile::load(Path::new("empty.yaml"), "", &mut errors).is_none());
+        assert_eq!(errors[1].to_string(), "empty.yaml: File is empty");
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Trait {
+        id: String,
+        max_value: u8,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Character {
+        traits: Vec<Trait>,
+    }
+
+    fn serde_error(file_contents: &str) -> ConfigError {
+        let err = serde_yaml::from_str::<Character>(file_contents).unwrap_err();
+        ConfigError::from_serde(Path::new("character.yaml"), &err)
+    }
+
+    #[test]
+    fn serde_messages_are_split_into_location_and_text() {
+        let error = serde_error("traits:\n  - id: \"health\"\n    max_value: \"lots\"\n");
+        assert_eq!(error.field.as_deref(), Some("traits[0].max_value"));
+        assert_eq!((error.line, error.column), (Some(3), Some(16)));
+        assert_eq!(error.message, "invalid type: string \"lots\", expected u8");
+
+        let error = serde_error("traits:\n  - id: \"health\"\n");
+        assert_eq!(error.field.as_deref(), Some("traits[0].max_value"));
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.message, "missing field `max_value`");
+
+        let error = serde_error("traits: 3\n");
+        assert_eq!(error.field.as_deref(), Some("traits"));
+        assert_eq!((error.line, error.column), (Some(1), Some(9)));
+        assert_eq!(error.message, "invalid type: integer `3`, expected a sequence");
+
+        let error = serde_error("{}\n");
+        assert_eq!(error.field.as_deref(), Some("traits"));
+        assert_eq!(error.message, "missing field `traits`");
+        assert_eq!(error.to_string(), format!("character.yaml:{}:{}: 'traits': missing field `traits`",
+                                              error.line.unwrap(), error.column.unwrap()));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::game::config_parsers::errors::ConfigError;
+
+// Where a game's config files are read from. Games are normally loaded from a folder on disk, but
+// can also be loaded from files held in memory, such as the ones bundled into a compiled game.
+pub trait ConfigFiles {
+    // The folder that every config file is in. Used to name the game in messages and to find
+    //    where its save file goes.
+    fn root(&self) -> &Path;
+
+    // Lists the path of every yaml file, adding an error for anything that can't be listed
+    fn yaml_files(&self, errors: &mut Vec<ConfigError>) -> Vec<PathBuf>;
+
+    // Reads the contents of one of the listed files
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+}
+
+// Config files in a folder on disk
+pub struct FolderFiles {
+    root: PathBuf,
+}
+
+impl FolderFiles {
+    pub fn new(root: &Path) -> FolderFiles {
+        FolderFiles { root: root.to_path_buf() }
+    }
+}
+
+impl ConfigFiles for FolderFiles {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn yaml_files(&self, errors: &mut Vec<ConfigError>) -> Vec<PathBuf> {
+        let mut paths = Vec::<PathBuf>::new();
+        // Loop over every file in the folder
+        for entry in WalkDir::new(&self.root).into_iter() { // Iterator used to walk directory
+            let entry = match entry {
+                Ok(entry) => { entry }
+                Err(err) => {
+                    let path = err.path().unwrap_or(&self.root).to_path_buf();
+                    errors.push(ConfigError::new(&path, format!("Unable to read entry: {}", err)));
+                    continue;
+                }
+            };
+
+            // Make sure its a path with an extension, and that the extension is yaml
+            if entry.path().extension() == Some(OsStr::new("yaml")) {
+                paths.push(entry.into_path());
+            }
+        }
+        paths
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+// Config files held in memory, stored by their path relative to the root
+pub struct MemoryFiles {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl MemoryFiles {
+    pub fn new(root: &Path) -> MemoryFiles {
+        MemoryFiles { root: root.to_path_buf(), files: BTreeMap::new() }
+    }
+
+    // Adds a file, replacing any file already at the same path
+    pub fn add_file(&mut self, relative_path: impl AsRef<Path>, contents: impl Into<String>) {
+        self.files.insert(relative_path.as_ref().to_path_buf(), contents.into());
+    }
+
+    // The example game's files, with extra files added to it or taking the place of the files with
+    //    the same paths, for tests that need a whole game with a few changes made to it
+    #[cfg(test)]
+    pub fn example_game_with(extra_files: &[(&str, &str)]) -> MemoryFiles {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+        let mut files = MemoryFiles::new(&config_path);
+        for path in FolderFiles::new(&config_path).yaml_files(&mut vec![]) {
+            files.add_file(path.strip_prefix(&config_path).unwrap(), fs::read_to_string(&path).unwrap());
+        }
+        for (path, contents) in extra_files {
+            files.add_file(path, *contents);
+        }
+        files
+    }
+
+    // Returns the contents of the file at the relative path, if there is one
+    #[cfg(test)]
+    pub fn file(&self, relative_path: &str) -> Option<&str> {
+        self.files.get(Path::new(relative_path)).map(|contents| contents.as_str())
+    }
+}
+
+impl ConfigFiles for MemoryFiles {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn yaml_files(&self, _errors: &mut Vec<ConfigError>) -> Vec<PathBuf> {
+        self.files.keys()
+            .filter(|path| path.extension() == Some(OsStr::new("yaml")))
+            .map(|path| self.root.join(path))
+            .collect()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let contents = path.strip_prefix(&self.root).ok().and_then(|relative| self.files.get(relative));
+        match contents {
+            Some(contents) => { Ok(contents.clone()) }
+            None => { Err(io::Error::new(io::ErrorKind::NotFound, "No such file in the game files")) }
+        }
+    }
+}
@@ -1,67 +1,160 @@
-use std::error::Error;
-use std::fs;
 use std::path::Path;
-use yaml_rust::YamlLoader;
-use crate::game::config_parsers::{GameData};
+use crate::game::config_parsers::GameData;
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile, field_path};
+use crate::game::config_parsers::objects::parse_state_value;
 
-// Reads game config file into the GameData structure
-pub fn process_config(game_data: &mut GameData, config_path: &Path) -> Result<(), Box<dyn Error>> {
+// Fields that every game.yaml must define
+const REQUIRED_FIELDS: [&str; 6] = ["name", "description", "author", "min_screen_size", "starting_map",
+                                    "starting_position"];
 
-    // Load file contents
-    let file_contents = fs::read_to_string(config_path)?;
+// Reads game config file into the GameData structure
+pub fn process_config(game_data: &mut GameData, config_path: &Path, file_contents: &str,
+                      sources: &mut ConfigSources, errors: &mut Vec<ConfigError>) {
 
-    // Convert to YAML
-    let docs = YamlLoader::load_from_str(&*file_contents).unwrap();
+    // Convert the file contents to YAML
+    let (doc, file) = match YamlFile::load(config_path, file_contents, errors) {
+        Some(loaded) => { loaded }
+        None => { return; }
+    };
 
-    // Multi document support, doc is a yaml::Yaml, need to extract the doc
-    let doc = &docs[0];
+    let game_info_hash = match file.get_hash("", &doc, errors) {
+        Some(hash) => { hash }
+        None => { return; }
+    };
 
-    let game_info_hash = doc.as_hash().unwrap();
+    for field in REQUIRED_FIELDS {
+        file.get_field("", game_info_hash, field, errors);
+    }
 
-    for key in game_info_hash.keys() {
-        let val = game_info_hash.get(key).unwrap_or(key);
-        let key_str = key.as_str().unwrap();
+    for (key, val) in game_info_hash {
+        let key_str = match key.as_str() {
+            Some(key_str) => { key_str }
+            None => { continue; }
+        };
         match key_str {
             "name" => {
-                game_data.info.name = val.as_str().unwrap().to_string();
+                if let Some(name) = file.get_str(key_str, val, errors) {
+                    game_data.info.name = name;
+                }
             }
             "description" => {
-                game_data.info.description = val.as_str().unwrap().to_string();
+                if let Some(description) = file.get_str(key_str, val, errors) {
+                    game_data.info.description = description;
+                }
             }
             "author" => {
-                game_data.info.author = val.as_str().unwrap().to_string();
+                if let Some(author) = file.get_str(key_str, val, errors) {
+                    game_data.info.author = author;
+                }
             }
             "min_screen_size" => {
-                let width_key = yaml_rust::Yaml::from_str("width");
-                let width = val.as_hash().unwrap()
-                    .get(&width_key).unwrap_or(key)
-                    .as_i64().unwrap();
-                let height_key = yaml_rust::Yaml::from_str("height");
-                let height = val.as_hash().unwrap()
-                    .get(&height_key).unwrap_or(key)
-                    .as_i64().unwrap();
-
-                game_data.info.min_screen_cols = width as u16;
-                game_data.info.min_screen_rows = height as u16;
+                if let Some((width, height)) = parse_pair(&file, key_str, val, ("width", "height"), errors) {
+                    game_data.info.min_screen_cols = width;
+                    game_data.info.min_screen_rows = height;
+                }
             }
             "starting_map" => {
-                game_data.info.starting_map = val.as_str().unwrap().to_string();
+                if let Some(starting_map) = file.get_str(key_str, val, errors) {
+                    game_data.info.starting_map = starting_map;
+                }
             }
             "starting_position" => {
-                let x_key = yaml_rust::Yaml::from_str("x");
-                let x = val.as_hash().unwrap()
-                    .get(&x_key).unwrap_or(key)
-                    .as_i64().unwrap();
-                let y_key = yaml_rust::Yaml::from_str("y");
-                let y = val.as_hash().unwrap()
-                    .get(&y_key).unwrap_or(key)
-                    .as_i64().unwrap();
-                game_data.info.starting_position_x = x as u16;
-                game_data.info.starting_position_y = y as u16;
+                if let Some((x, y)) = parse_pair(&file, key_str, val, ("x", "y"), errors) {
+                    game_data.info.starting_position_x = x;
+                    game_data.info.starting_position_y = y;
+                }
+            }
+            "tick_rate" => {
+                match file.get_i64(key_str, val, errors) {
+                    Some(tick_rate) if tick_rate > 0 => { game_data.info.tick_rate_ms = tick_rate as u64; }
+                    Some(_) => { errors.push(file.error(key_str, "Expected a positive number of milliseconds")); }
+                    None => {}
+                }
+            }
+            "variables" => {
+                parse_variables(game_data, &file, key_str, val, errors);
             }
             _ => {continue;}
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    sources.game = Some(file);
+}
+
+// Parses the global variables, given as a mapping of each variable's name to the value it starts
+//    with, which also decides its type
+fn parse_variables(game_data: &mut GameData, file: &YamlFile, field: &str, val: &yaml_rust::Yaml,
+                   errors: &mut Vec<ConfigError>) {
+    let hash = match file.get_hash(field, val, errors) {
+        Some(hash) => { hash }
+        None => { return; }
+    };
+    for (key, val) in hash {
+        let name = match key.as_str() {
+            Some(name) => { name }
+            None => { continue; }
+        };
+        if let Some(value) = parse_state_value(file, &field_path(field, name), val, errors) {
+            game_data.info.variables.insert(name.to_string(), value);
+        }
+    }
+}
+
+// Parses a mapping made up of two non-negative integers, such as a width and height
+fn parse_pair(file: &YamlFile, field: &str, val: &yaml_rust::Yaml, names: (&str, &str),
+              errors: &mut Vec<ConfigError>) -> Option<(u16, u16)> {
+    let hash = file.get_hash(field, val, errors)?;
+    let mut values = [0; 2];
+    let mut valid = true;
+    for (i, name) in [names.0, names.1].iter().enumerate() {
+        let name_path = field_path(field, name);
+        let value = file.get_field(field, hash, name, errors)
+            .and_then(|v| file.get_i64(&name_path, v, errors));
+        match value {
+            Some(value) if value >= 0 && value <= u16::MAX as i64 => { values[i] = value as u16; }
+            Some(_) => {
+                errors.push(file.error(&name_path, "Expected a non-negative integer"));
+                valid = false;
+            }
+            None => { valid = false; }
+        }
+    }
+    if valid {
+        Some((values[0], values[1]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::game::config_parsers::tests::{example_game_yaml_with_variables, load_example_game_with};
+    use crate::game::objects::StateValue;
+
+    #[test]
+    fn global_variables_are_declared_in_game_yaml() {
+        let load = |variables: &str| {
+            let game_yaml = example_game_yaml_with_variables(variables);
+            let (game_data, problems) = load_example_game_with(&[("game.yaml", &game_yaml)]);
+            let problems = problems.iter()
+                .filter(|p| p.is_error())
+                .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+                .collect::<Vec<String>>();
+            (game_data, problems)
+        };
+        let variables = "  bells_rung: 0\n  gate_open: false\n  weather: \"rain\"\n";
+
+        let (_, problems) = load(&format!("{}  broken: [1, 2]\n", variables));
+        assert_eq!(problems, vec!["variables.broken: Expected a boolean, an integer, or a string"]);
+
+        let (game_data, problems) = load(variables);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(game_data.info.variables, BTreeMap::from([
+            ("asked_about_key".to_string(), StateValue::Bool(false)),
+            ("bells_rung".to_string(), StateValue::Int(0)),
+            ("gate_open".to_string(), StateValue::Bool(false)),
+            ("weather".to_string(), StateValue::Str("rain".to_string())),
+        ]));
+    }
+}
@@ -1,6 +1,10 @@
-use std::fs;
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
-use crate::game::maps::{Size};
+use yaml_rust::{Yaml, YamlLoader};
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile, add_source};
+use crate::game::config_parsers::objects::parse_object_interactions;
+use crate::game::maps::{Size, Tile, TileKind, walled_tiles};
+use crate::game::objects::{Object, ObjectInteraction, StateValue, default_instance_id};
 use serde::{Serialize,Deserialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -8,8 +12,32 @@ use serde::{Serialize,Deserialize};
 pub struct MapItemData {
     pub id: String,
     pub description: String,
-    pub size: Size,
+    // Can be left out when the map has a layout, which decides its size instead
+    #[serde(default)]
+    pub size: Option<Size>,
+    // Rows of characters, each standing for whatever the legend says it is
+    #[serde(default)]
+    pub layout: Option<String>,
+    #[serde(default)]
+    pub legend: BTreeMap<String, LegendEntry>,
+    #[serde(default)]
     pub objects: Vec<MapObject>,
+    // Named spots that doors can lead to
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+}
+
+// What a character in a map's layout stands for: a tile, an object or character placed on a tile
+//    (floor unless another tile is given), or both. Passability defaults to what the tile is.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEntry {
+    #[serde(default)]
+    pub tile: Option<TileKind>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub passable: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,7 +45,47 @@ pub struct MapItemData {
 pub struct MapObject {
     pub id: String,
     pub position: Position,
+    // Tells this placement of the object apart from any others
+    #[serde(default)]
+    pub instance: Option<String>,
+    // Changes made to the object for just this placement
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub icon: Option<char>,
+    #[serde(default)]
+    pub state: BTreeMap<String, StateValue>,
+    // Read separately, the same way as in object files
+    #[serde(skip)]
+    pub interactions: Option<Vec<ObjectInteraction>>,
+    // Where the door placed here leads
+    #[serde(default)]
+    pub link: Option<Link>,
+}
+
+// Where a door leads: a door or a spawn in the target map, which is the door's own map if none is
+//    given. Links are two-way unless one_way is set, so a door at the other end with no link of its
+//    own leads back.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub door: Option<String>,
+    #[serde(default)]
+    pub spawn: Option<String>,
+    #[serde(default, rename = "one_way")]
+    pub one_way: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Spawn {
+    pub id: String,
+    pub position: Position,
 }
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
@@ -25,17 +93,541 @@ pub struct Position {
     pub y: usize,
 }
 
+impl MapObject {
+    pub fn new(id: String, x: usize, y: usize) -> MapObject {
+        MapObject {
+            id,
+            position: Position { x, y },
+            instance: None,
+            name: None,
+            icon: None,
+            state: BTreeMap::new(),
+            interactions: None,
+            link: None,
+        }
+    }
+
+    // Returns true if this placement changes anything about what is placed
+    pub fn has_overrides(&self) -> bool {
+        self.instance.is_some() || self.name.is_some() || self.icon.is_some() || !self.state.is_empty()
+            || self.interactions.is_some()
+    }
+}
+
+// An object or character placed by a map's layout
+pub struct LayoutPlacement {
+    pub id: String,
+    pub glyph: char,
+    pub x: usize,
+    pub y: usize,
+}
+
 // Reads the map config file into a temporary structure (MapItemData) using Serde
-pub fn process_config_serde(map_item_data: &mut Vec<MapItemData>, config_path: &Path) -> Result<(),serde_yaml::Error>{
-    let file_contents = fs::read_to_string(config_path).unwrap();
-    let doc = serde_yaml::from_str::<MapItemData>(&file_contents);
+pub fn process_config_serde(map_item_data: &mut Vec<MapItemData>, config_path: &Path, file_contents: &str,
+                            sources: &mut ConfigSources, errors: &mut Vec<ConfigError>) {
+    let doc = serde_yaml::from_str::<MapItemData>(file_contents);
     match doc {
-        Ok(parsed) => {
-            map_item_data.push(parsed);
+        Ok(mut parsed) => {
+            let error_count = errors.len();
+            let file = YamlFile::new(config_path, file_contents);
+            parse_interaction_overrides(&mut parsed, file_contents, &file, errors);
+            check_map_item_data(&mut parsed, &file, errors);
+            if errors.len() == error_count && add_source(&mut sources.maps, "map", &parsed.id, file, errors) {
+                map_item_data.push(parsed);
+            }
         }
         Err(err) =>{
-            eprintln!("{}",err);
+            errors.push(ConfigError::from_serde(config_path, &err));
+        }
+    }
+}
+
+// Makes sure the map has room for its walls, that every object and spawn placed in it is within its
+//    size, and that every link says where it leads. Maps with a layout must have rows that are all
+//    the same width and only use characters in the legend, and take their size from the layout.
+fn check_map_item_data(data: &mut MapItemData, file: &YamlFile, errors: &mut Vec<ConfigError>) {
+    for (glyph, entry) in &data.legend {
+        let field = format!("legend.{}", glyph);
+        if glyph.chars().count() != 1 {
+            errors.push(file.error(&field, "Legend keys must be a single character"));
+        }
+        if entry.tile.is_none() && entry.id.is_none() {
+            errors.push(file.error(&field, "Legend entries need a tile, an id, or both"));
+        }
+    }
+
+    match &data.layout {
+        Some(layout) => {
+            let rows = layout_rows(layout);
+            let width = rows.first().map(|row| row.len()).unwrap_or(0);
+            if width == 0 {
+                errors.push(file.error("layout", "The layout must have at least one row"));
+            }
+            let mut unknown = HashSet::<char>::new();
+            for (y, row) in rows.iter().enumerate() {
+                if row.len() != width {
+                    errors.push(file.error("layout", format!(
+                        "Row {} of the layout is {} characters wide, but the first row is {}", y, row.len(), width)));
+                }
+                for (x, glyph) in row.iter().enumerate() {
+                    if !data.legend.contains_key(&glyph.to_string()) && unknown.insert(*glyph) {
+                        errors.push(file.error("layout", format!(
+                            "'{}' at ({}, {}) of the layout is not in the legend", glyph, x, y)));
+                    }
+                }
+            }
+
+            let layout_size = Size { width: width as i64, height: rows.len() as i64 };
+            if let Some(size) = &data.size {
+                if size.width != layout_size.width || size.height != layout_size.height {
+                    errors.push(file.error("size", format!("The map's size is {}x{}, but its layout is {}x{}",
+                                                           size.width, size.height,
+                                                           layout_size.width, layout_size.height)));
+                }
+            }
+            data.size = Some(layout_size);
+        }
+        None => {
+            match &data.size {
+                Some(size) => {
+                    if size.width < 3 {
+                        errors.push(file.error("size.width", "Map width must be greater than 2"));
+                    }
+                    if size.height < 3 {
+                        errors.push(file.error("size.height", "Map height must be greater than 2"));
+                    }
+                }
+                None => {
+                    errors.push(file.error("size", "Maps need either a size or a layout"));
+                }
+            }
         }
     }
-    Ok(())
-}
\ No newline at end of file
+
+    if let Some(size) = &data.size {
+        let mut positions = Vec::<(String, &Position)>::new();
+        for (i, map_object) in data.objects.iter().enumerate() {
+            positions.push((format!("objects[{}].position", i), &map_object.position));
+        }
+        for (i, spawn) in data.spawns.iter().enumerate() {
+            positions.push((format!("spawns[{}].position", i), &spawn.position));
+        }
+        for (field, position) in positions {
+            if position.x as i64 >= size.width || position.y as i64 >= size.height {
+                errors.push(file.error(&field,
+                                       format!("Position ({}, {}) is outside of the map's {}x{} size",
+                                               position.x, position.y, size.width, size.height)));
+            }
+        }
+    }
+
+    let mut spawn_ids = HashSet::<&str>::new();
+    for (i, spawn) in data.spawns.iter().enumerate() {
+        if !spawn_ids.insert(&spawn.id) {
+            errors.push(file.error(&format!("spawns[{}].id", i), format!("The spawn id '{}' is already used", spawn.id)));
+        }
+    }
+    for (i, map_object) in data.objects.iter().enumerate() {
+        if let Some(link) = &map_object.link {
+            if link.door.is_some() == link.spawn.is_some() {
+                errors.push(file.error(&format!("objects[{}].link", i), "Links need either a door or a spawn to lead to"));
+            }
+        }
+    }
+}
+
+// Reads the interactions given to single placements of objects, which are written the same way as
+//    in object files and so can't be read by Serde
+fn parse_interaction_overrides(data: &mut MapItemData, file_contents: &str, file: &YamlFile,
+                               errors: &mut Vec<ConfigError>) {
+    let doc = match YamlLoader::load_from_str(file_contents) {
+        Ok(mut docs) if !docs.is_empty() => { docs.swap_remove(0) }
+        _ => { return; }
+    };
+    let yaml_objects = match doc["objects"].as_vec() {
+        Some(yaml_objects) => { yaml_objects }
+        None => { return; }
+    };
+    for (i, (map_object, yaml_object)) in data.objects.iter_mut().zip(yaml_objects).enumerate() {
+        let mut interactions = Vec::<ObjectInteraction>::new();
+        match &yaml_object["interactions"] {
+            Yaml::BadValue => { continue; }
+            // Placements can take away all of the object's interactions
+            Yaml::Null => {}
+            yaml_interactions => {
+                let field = format!("objects[{}].interactions", i);
+                parse_object_interactions(&mut interactions, file, &field, yaml_interactions, errors);
+            }
+        }
+        map_object.interactions = Some(interactions);
+    }
+}
+
+// Creates the instance of an object for one placement of it in a map, making any changes the
+//    placement gives. States that the object doesn't have, or that are given the wrong type of
+//    value, are reported.
+pub fn place_object(object: &Object, map_id: &str, map_object: MapObject, field: &str, file: Option<&YamlFile>,
+                    errors: &mut Vec<ConfigError>) -> Object {
+    let (x, y) = (map_object.position.x, map_object.position.y);
+    let mut instance = object.clone();
+    instance.instance_id = map_object.instance.unwrap_or_else(|| default_instance_id(&object.id, map_id, x, y));
+    if let Some(name) = map_object.name {
+        instance.name = name;
+    }
+    if let Some(icon) = map_object.icon {
+        instance.icon = icon;
+    }
+    if let Some(interactions) = map_object.interactions {
+        instance.interactions = interactions;
+    }
+    for (state_name, value) in map_object.state {
+        let problem = match object.state_value(&state_name) {
+            Some(default) if default.same_type(&value) => {
+                instance.set_state(state_name.clone(), value);
+                continue;
+            }
+            Some(default) => {
+                format!("The state '{}' of the object '{}' has {} values, not {} values", state_name, object.id,
+                        default.type_name(), value.type_name())
+            }
+            None => { format!("The object '{}' has no state named '{}'", object.id, state_name) }
+        };
+        if let Some(file) = file {
+            errors.push(file.error(&format!("{}.state.{}", field, state_name), problem));
+        }
+    }
+    instance
+}
+
+// Splits a layout into rows of characters
+fn layout_rows(layout: &str) -> Vec<Vec<char>> {
+    layout.lines().map(|line| line.chars().collect()).collect()
+}
+
+// Creates the tiles of a map that has already been checked. Maps without a layout are a plain
+//    room with walls around the edges.
+pub fn map_tiles(data: &MapItemData) -> Vec<Vec<Tile>> {
+    let size = data.size.as_ref().unwrap();
+    let layout = match &data.layout {
+        Some(layout) => { layout }
+        None => { return walled_tiles(size.width as usize, size.height as usize); }
+    };
+
+    let rows = layout_rows(layout);
+    let mut tiles = Vec::<Vec<Tile>>::new();
+    for x in 0..size.width as usize {
+        let mut column = Vec::<Tile>::new();
+        for row in &rows {
+            let glyph = row[x];
+            let entry = &data.legend[&glyph.to_string()];
+            // Tiles under objects and characters are left blank, since something is drawn over them
+            let mut tile = match (entry.tile, &entry.id) {
+                (Some(kind), None) => { Tile::new(kind, glyph) }
+                (Some(kind), Some(_)) => { Tile::new(kind, ' ') }
+                (None, _) => { Tile::new(TileKind::Floor, ' ') }
+            };
+            if let Some(passable) = entry.passable {
+                tile.passable = passable;
+            }
+            column.push(tile);
+        }
+        tiles.push(column);
+    }
+    tiles
+}
+
+// Finds every object and character placed by a map's layout
+pub fn layout_placements(data: &MapItemData) -> Vec<LayoutPlacement> {
+    let mut placements = Vec::<LayoutPlacement>::new();
+    if let Some(layout) = &data.layout {
+        for (y, row) in layout_rows(layout).iter().enumerate() {
+            for (x, glyph) in row.iter().enumerate() {
+                if let Some(id) = data.legend.get(&glyph.to_string()).and_then(|entry| entry.id.as_ref()) {
+                    placements.push(LayoutPlacement { id: id.clone(), glyph: *glyph, x, y });
+                }
+            }
+        }
+    }
+    placements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::config_parsers::GameData;
+    use crate::game::config_parsers::tests::load_example_game_with;
+    use crate::game::maps::MapData;
+
+    // Loads the example game with one extra map, returning the game and the problems found in that map
+    fn load_with_map(map_yaml: &str) -> (GameData, Vec<String>) {
+        load_with_files(map_yaml, &[])
+    }
+
+    // Loads the example game with one extra map and any other extra files, returning the game and the
+    //    problems found in that map
+    fn load_with_files(map_yaml: &str, extra_files: &[(&str, &str)]) -> (GameData, Vec<String>) {
+        let mut files = vec![("maps/test.yaml", map_yaml)];
+        files.extend_from_slice(extra_files);
+        let (game_data, problems) = load_example_game_with(&files);
+        let problems = problems.iter()
+            .filter(|p| p.is_error() && p.path.ends_with("test.yaml"))
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect();
+        (game_data, problems)
+    }
+
+    #[test]
+    fn layout_builds_tiles_and_places_objects() {
+        let (game_data, problems) = load_with_map(r##"
+id: "test"
+description: ""
+layout: |
+  ####
+  #.T#
+  #~~#
+  ####
+legend:
+  "#": { tile: "wall" }
+  ".": { tile: "floor" }
+  "~": { tile: "water", passable: true }
+  "T": { id: "table" }
+"##);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let map = game_data.maps.iter().find(|m| m.info.id == "test").unwrap();
+        assert_eq!((map.info.size.width, map.info.size.height), (4, 4));
+        assert!(!map.tiles[0][1].passable);
+        assert_eq!(map.tiles[0][1].icon, '#');
+        assert!(map.tiles[1][1].passable);
+        assert_eq!(map.tiles[1][2].kind, TileKind::Water);
+        assert!(map.tiles[1][2].passable);
+        assert_eq!(map.tiles[2][1].icon, ' ');
+        assert!(map.grid[2][1].is_some());
+    }
+
+    #[test]
+    fn layout_problems_are_reported() {
+        let (_, problems) = load_with_map(r##"
+id: "test"
+description: ""
+size:
+  width: 5
+  height: 3
+layout: |
+  ####
+  #.?#
+  ###
+legend:
+  "#": { tile: "wall" }
+  ".": { passable: true }
+  "X": { id: "nothing" }
+  "ab": { tile: "floor" }
+"##);
+        assert_eq!(problems, vec![
+            "size: The map's size is 5x3, but its layout is 4x3",
+            "layout: '?' at (2, 1) of the layout is not in the legend",
+            "layout: Row 2 of the layout is 3 characters wide, but the first row is 4",
+            "legend..: Legend entries need a tile, an id, or both",
+            "legend.ab: Legend keys must be a single character",
+        ]);
+    }
+
+    #[test]
+    fn layout_ids_must_exist() {
+        let (_, problems) = load_with_map(r##"
+id: "test"
+description: ""
+layout: |
+  ###
+  #X#
+  ###
+legend:
+  "#": { tile: "wall" }
+  "X": { id: "nothing" }
+"##);
+        assert_eq!(problems, vec!["legend.X.id: No character or object with the id 'nothing' exists"]);
+    }
+
+    #[test]
+    fn links_lead_within_a_map_and_back() {
+        let map_yaml = r##"
+id: "test"
+description: ""
+size:
+  width: 8
+  height: 4
+objects:
+  - id: "teleporter"
+    position: { x: 1, y: 1 }
+    link: { door: "door_2" }
+  - id: "door_2"
+    position: { x: 5, y: 1 }
+  - id: "teleporter"
+    position: { x: 1, y: 2 }
+    link: { spawn: "corner", one_way: true }
+  - id: "teleporter"
+    position: { x: 3, y: 2 }
+    link: { map: "basement", spawn: "behind_wall" }
+spawns:
+  - id: "corner"
+    position: { x: 6, y: 2 }
+"##;
+        let (game_data, problems) = load_with_map(map_yaml);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let m = game_data.maps.iter().position(|m| m.info.id == "test").unwrap();
+        let basement = game_data.maps.iter().position(|m| m.info.id == "basement").unwrap();
+        assert_eq!(game_data.door_destination(m, 1, 1), Some((m, 5, 1)));
+        assert_eq!(game_data.door_destination(m, 5, 1), Some((m, 1, 1)));
+        assert_eq!(game_data.door_destination(m, 1, 2), Some((m, 6, 2)));
+        assert_eq!(game_data.door_destination(m, 3, 2), Some((basement, 17, 5)));
+
+        // One-way links don't lead back
+        let (game_data, _) = load_with_map(&map_yaml.replace(r#"{ door: "door_2" }"#,
+                                                             r#"{ door: "door_2", one_way: true }"#));
+        assert_ne!(game_data.door_destination(m, 5, 1), Some((m, 1, 1)));
+    }
+
+    #[test]
+    fn link_problems_are_reported() {
+        let (_, problems) = load_with_map(r##"
+id: "test"
+description: ""
+size:
+  width: 8
+  height: 4
+objects:
+  - id: "teleporter"
+    position: { x: 1, y: 1 }
+    link: { door: "door_2", spawn: "corner" }
+  - id: "teleporter"
+    position: { x: 2, y: 1 }
+    link: { one_way: true }
+spawns:
+  - id: "corner"
+    position: { x: 6, y: 2 }
+  - id: "corner"
+    position: { x: 8, y: 2 }
+"##);
+        assert_eq!(problems, vec![
+            "objects[0].link: Links need either a door or a spawn to lead to",
+            "objects[1].link: Links need either a door or a spawn to lead to",
+            "spawns[1].id: The spawn id 'corner' is already used",
+            "spawns[1].position: Position (8, 2) is outside of the map's 8x4 size",
+        ]);
+
+        let (_, problems) = load_with_map(r##"
+id: "test"
+description: ""
+size:
+  width: 8
+  height: 4
+objects:
+  - id: "teleporter"
+    position: { x: 1, y: 1 }
+    link: { map: "attic", door: "door_2" }
+  - id: "teleporter"
+    position: { x: 2, y: 1 }
+    link: { spawn: "nowhere" }
+  - id: "teleporter"
+    position: { x: 3, y: 1 }
+    link: { door: "teleporter" }
+  - id: "table"
+    position: { x: 4, y: 1 }
+    link: { map: "basement", door: "door_1" }
+"##);
+        assert_eq!(problems, vec![
+            "objects[0].link.map: No map with the id 'attic' exists",
+            "objects[1].link.spawn: The map 'test' has no spawn with the id 'nowhere'",
+            "objects[2].link.door: The map 'test' has 3 objects with the id 'teleporter', so the link needs the \
+             instance id of one of them",
+            "objects[3].link: Only objects with a travel interaction can have a link, but nothing at (4, 1) has one",
+        ]);
+    }
+
+    const CHEST_YAML: &str = r##"
+id: "chest"
+name: "Chest"
+category: "simple"
+icon: "C"
+state:
+  - id: "open"
+    default: false
+  - id: "locked"
+    default: true
+interactions:
+  object_use:
+    - foreign_objects_id: "key"
+      self_action:
+        - locked: false
+      consume_item: false
+"##;
+
+    #[test]
+    fn placements_are_separate_instances() {
+        let mut map_yaml = String::from("id: \"test\"\ndescription: \"\"\nsize: { width: 12, height: 3 }\nobjects:\n");
+        for x in 0..10 {
+            map_yaml.push_str(&format!("  - id: \"chest\"\n    instance: \"chest_{}\"\n    position: {{ x: {}, y: 1 }}\n",
+                                       x, x + 1));
+        }
+        map_yaml.push_str(r##"  - id: "chest"
+    position: { x: 11, y: 1 }
+    name: "Golden Chest"
+    icon: "G"
+    state: { locked: false }
+    interactions:
+      activate:
+        - category: "travel"
+    link: { map: "basement", spawn: "behind_wall" }
+"##);
+        let (game_data, problems) = load_with_files(&map_yaml, &[("objects/chest.yaml", CHEST_YAML)]);
+        assert!(problems.is_empty(), "{:?}", problems);
+
+        let m = game_data.maps.iter().position(|m| m.info.id == "test").unwrap();
+        for x in 0..10 {
+            let instance_id = format!("chest_{}", x);
+            assert_eq!(game_data.find_instance(&instance_id), Some((m, x + 1, 1)));
+        }
+        let golden = match &game_data.maps[m].grid[11][1] {
+            Some(MapData::Object(object)) => { object }
+            _ => { panic!("The golden chest wasn't placed") }
+        };
+        assert_eq!(golden.id, "chest");
+        assert_eq!(golden.instance_id, "chest@test:11,1");
+        assert_eq!((golden.name.as_str(), golden.icon), ("Golden Chest", 'G'));
+        assert!(!golden.get_state("locked"));
+        assert!(golden.travels());
+        assert!(game_data.door_destination(m, 11, 1).is_some());
+        assert!(game_data.door_destination(m, 1, 1).is_none());
+    }
+
+    #[test]
+    fn placement_problems_are_reported() {
+        let (_, problems) = load_with_files(r##"
+id: "test"
+description: ""
+size: { width: 8, height: 3 }
+objects:
+  - id: "chest"
+    instance: "chest_1"
+    position: { x: 1, y: 1 }
+    state: { broken: true }
+  - id: "chest"
+    instance: "chest_1"
+    position: { x: 2, y: 1 }
+    interactions:
+      activate:
+        - category: "travel"
+          destination: "attic"
+  - id: "angry_antagonist"
+    instance: "antagonist_1"
+    position: { x: 3, y: 1 }
+"##, &[("objects/chest.yaml", CHEST_YAML)]);
+        assert_eq!(problems, vec![
+            "objects: The door 'chest' at (2, 1) leads nowhere: give it a link, or put a door with the same id in \
+             another map",
+            "objects[0].state.broken: The object 'chest' has no state named 'broken'",
+            "objects[1].instance: The instance id 'chest_1' is already used",
+            "objects[1].interactions.activate[0].destination: No map with the id 'attic' exists",
+            "objects[2]: Only objects can be given an instance id or changes",
+        ]);
+    }
+}
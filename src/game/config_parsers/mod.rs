@@ -1,18 +1,26 @@
 use std::ffi::OsStr;
-use walkdir::WalkDir;
-use crate::game::config_parsers::maps::MapItemData;
-use crate::game::maps::{Map, MapInfo, MapData};
+use crate::game::config_parsers::maps::{Link, MapItemData, MapObject};
+use crate::game::maps::{DoorLink, Map, MapInfo, MapData};
 use crate::game::characters::Character;
-use crate::game::objects::Object;
+use crate::game::objects::{Object, StateValue};
 use crate::game::dialogs::Dialog;
-use std::collections::HashMap;
-use std::error::Error;
+use crate::game::quests::Quest;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use errors::{ConfigError, ConfigSources};
+use files::ConfigFiles;
 
+pub mod errors;
+pub mod files;
+pub mod validation;
 mod characters;
 mod game;
 mod maps;
 mod objects;
 mod dialogs;
+mod quests;
+
+// How often the game moves forward when nothing else is configured, in milliseconds
+pub const DEFAULT_TICK_RATE_MS: u64 = 500;
 
 #[derive(Debug)]
 pub struct GameInfo {
@@ -24,24 +32,53 @@ pub struct GameInfo {
     pub starting_map: String,
     pub starting_position_x: u16,
     pub starting_position_y: u16,
+    pub tick_rate_ms: u64,
     pub player: Option<Character>,
+    // The global variables and the values they start with
+    pub variables: BTreeMap<String, StateValue>,
 }
 
 #[derive(Debug)]
 pub struct GameData {
     pub maps: Vec<Map>,
+    // The maps as they were loaded from the config files, which saves only store the changes from
+    pub starting_maps: Vec<Map>,
+    // Every object as defined in its config file, for creating more of them while playing
+    pub objects: HashMap<String, Object>,
     pub dialogs: HashMap<String, Dialog>,
+    pub quests: HashMap<String, Quest>,
     pub info: GameInfo,
 }
 
 impl GameData {
-    // Main function to initialize the game data object and populate it with data from the config files
-    pub fn process_configs(config_path: std::path::PathBuf) -> GameData {
+    // Main function to initialize the game data object and populate it with data from the config files.
+    // Every problem found in the config files is collected and returned together, rather than
+    // stopping at the first one.
+    pub fn process_configs(files: &dyn ConfigFiles) -> Result<GameData, Vec<ConfigError>> {
         println!("Parsing configs");
 
+        let (game_data, problems) = GameData::load_configs(files);
+
+        if problems.iter().any(|e| e.is_error()) {
+            Err(problems)
+        } else {
+            for warning in problems {
+                println!("{}", warning);
+            }
+            Ok(game_data)
+        }
+    }
+
+    // Loads the config files without printing anything, returning the game data along with every
+    // problem (errors and warnings) found, sorted by file and position. The game data is only
+    // usable if none of the problems are errors.
+    pub fn load_configs(files: &dyn ConfigFiles) -> (GameData, Vec<ConfigError>) {
         let mut game_data = GameData{
             maps: Vec::new(),
+            starting_maps: Vec::new(),
+            objects: HashMap::new(),
             dialogs: HashMap::new(),
+            quests: HashMap::new(),
             info: GameInfo{
                 name: "".to_string(),
                 description: "".to_string(),
@@ -51,37 +88,47 @@ impl GameData {
                 starting_map: "".to_string(),
                 starting_position_x: 0,
                 starting_position_y: 0,
-                player: None
+                tick_rate_ms: DEFAULT_TICK_RATE_MS,
+                player: None,
+                variables: BTreeMap::new(),
             }
         };
 
-        game_data.scan_config(config_path).expect("Error scanning configuration files, aborting!");
+        let mut problems = Vec::<ConfigError>::new();
+        game_data.scan_config(files, &mut problems);
+        game_data.starting_maps = game_data.maps.clone();
+        problems.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
 
-        game_data
+        (game_data, problems)
     }
 
-    // Scans the provided configuration path and calls the type-specific configuration parsers on each file
-    fn scan_config(&mut self, config_path: std::path::PathBuf) -> Result<(), Box<dyn Error>> {
+    // Scans the provided configuration files and calls the type-specific configuration parsers on each file
+    fn scan_config(&mut self, files: &dyn ConfigFiles, errors: &mut Vec<ConfigError>) {
+        let config_path = files.root();
 
         // As the configs are read, everything is thrown in these vectors, then after all are read, they get put into the actual map objects
         let mut characters = HashMap::<String, Character>::new();
         let mut objects = HashMap::<String, Object>::new();
         let mut map_item_data = Vec::<MapItemData>::new();
+        let mut sources = ConfigSources::default();
+        let mut found_game_config = false;
 
-        // Loop over every file in the provided folder
-        for entry in WalkDir::new(config_path).into_iter() // Iterator used to walk directory
-            .map(|a| a.expect("Unable to read entry"))// Result -> inner type, panic if failure
-            .filter(|a| a.path() // Make sure its a path
-                .extension() // with an extension
-                .map(|ext| ext == OsStr::new("yaml")) // and that the extension is yaml
-                .unwrap_or(false )// include this path in the processed paths if it passed, else don't
-            ) {
+        // Loop over every yaml file in the game
+        for path in files.yaml_files(errors) {
+            let file_contents = match files.read_to_string(&path) {
+                Ok(contents) => { contents }
+                Err(err) => {
+                    errors.push(ConfigError::new(&path, format!("Unable to read file: {}", err)));
+                    continue;
+                }
+            };
 
-            if entry.path().file_name() == Some(OsStr::new("game.yaml")) {
-                game::process_config(self, entry.path())?;
+            if path.file_name() == Some(OsStr::new("game.yaml")) {
+                found_game_config = true;
+                game::process_config(self, &path, &file_contents, &mut sources, errors);
 
             } else {
-                let parent_opt = entry.path() // Option<> representing the parent's path, starting with current path
+                let parent_opt = path // Option<> representing the parent's path, starting with current path
                     .parent() // Make sure it has a parent
                     .and_then(|a| a.file_name()) // and that parent has a filename, then convert the option to the Option over the filename
                     .and_then(|a| a.to_str()); // If there is a file name AND that filename can be converted to a Rust &str, convert the option to an Option over the &str
@@ -90,64 +137,296 @@ impl GameData {
                 if let Some(parent) = parent_opt {
                     // Then check it against our valid parents
                     match parent {
-                        "maps" => { maps::process_config_serde(&mut map_item_data, entry.path())?; }
-                        "characters" => { characters::process_config_serde(&mut characters, entry.path())?; }
-                        "objects" => { objects::process_config(&mut objects, entry.path())?; }
-                        "dialogs" => { dialogs::process_config_serde(&mut self.dialogs, entry.path())?; }
-                        _ => { println!("Found unknown file '{:?}', ignoring", entry.path()) }
+                        "maps" => { maps::process_config_serde(&mut map_item_data, &path, &file_contents, &mut sources, errors); }
+                        "characters" => { characters::process_config_serde(&mut characters, &path, &file_contents, &mut sources, errors); }
+                        "objects" => { objects::process_config(&mut objects, &path, &file_contents, &mut sources, errors); }
+                        "dialogs" => { dialogs::process_config_serde(&mut self.dialogs, &path, &file_contents, &mut sources, errors); }
+                        "quests" => { quests::process_config_serde(&mut self.quests, &path, &file_contents, &mut sources, errors); }
+                        _ => { errors.push(ConfigError::new(&path, "Unknown file, ignoring").warning()); }
                     }
                 }
             }
         }
-        self.set_map_grid(map_item_data, characters, objects)?;
 
-        Ok(())
+        if !found_game_config {
+            errors.push(ConfigError::new(config_path, "No game.yaml file was found"));
+        }
+
+        // References to content that failed to load would just repeat those problems, so
+        //    references are only reported once every file loaded cleanly
+        let mut reference_errors = Vec::<ConfigError>::new();
+        if !characters.contains_key("player") {
+            reference_errors.push(ConfigError::new(config_path, "No character with the id \"player\" was found"));
+        }
+        self.set_map_grid(map_item_data, characters, &objects, &sources, &mut reference_errors);
+        self.objects = objects;
+        validation::validate(self, &sources, &mut reference_errors);
+        if errors.is_empty() {
+            errors.append(&mut reference_errors);
+        }
     }
 
     // Takes the MapItemData, characters list, and objects list and inserts the characters and objects
-    // in the right spaces in the game map
+    // in the right spaces in the game map. Any ids that don't match a character or object are reported.
+    // Each object placed becomes its own instance, with any changes the map makes to it. Once every
+    // map is in place, the links given to doors are worked out.
     fn set_map_grid(&mut self, map_item_data: Vec<MapItemData>, characters: HashMap<String, Character>,
-                                                                objects: HashMap< String, Object> ) -> Result<(), Box<dyn Error>> {
+                    objects: &HashMap<String, Object>, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+        // Links are given as (map index, x, y, link, field) and can only be followed once every map is loaded
+        let mut links = Vec::<(usize, usize, usize, Link, String)>::new();
+        // Instances given their own interactions, as (map index, x, y, field), which can only be
+        //    checked once every map is loaded
+        let mut changed_interactions = Vec::<(usize, usize, usize, String)>::new();
+        let mut instance_ids = HashSet::<String>::new();
         for map_item in map_item_data {
-            // Apparently using size in the map definition moves size, so let's copy the values out ◔_◔
-            let width = map_item.size.width;
-            let height = map_item.size.height;
+            // Sizes have already been checked (or taken from the layout)
+            let size = map_item.size.clone().unwrap();
             let mut map = Map{
-                info: MapInfo { id: map_item.id, description: map_item.description, size: map_item.size },
-                grid: vec![]
+                tiles: maps::map_tiles(&map_item),
+                info: MapInfo { id: map_item.id.clone(), description: map_item.description.clone(), size: size.clone() },
+                grid: vec![],
+                links: vec![],
+                spawns: map_item.spawns.iter()
+                    .map(|spawn| (spawn.id.clone(), (spawn.position.x, spawn.position.y)))
+                    .collect(),
             };
-            map.grid.resize(width as usize, vec![] );
-            for i in 0..(width) {
-                map.grid[i as usize].resize(height as usize, Option::<MapData>::None);
-            }
-
-            for map_object in map_item.objects {
-                let object_id = map_object.id;
-                let pos_x = map_object.position.x;
-                let pos_y = map_object.position.y;
-                let character_opt = characters.get(&object_id);
-                let object_opt = objects.get(&object_id);
-                if let Some(character) = character_opt {
-                    map.grid[pos_x][pos_y] = Option::<MapData>::Some(MapData::Character(character.to_owned()));
+            map.grid.resize(size.width as usize, vec![] );
+            for i in 0..(size.width) {
+                map.grid[i as usize].resize(size.height as usize, Option::<MapData>::None);
+            }
+
+            // Everything placed by the layout goes in first, then anything in the objects list, which
+            //    takes the place of whatever the layout put in the same spot. Each placement is given
+            //    along with the field it comes from.
+            let mut placements = Vec::<(MapObject, String)>::new();
+            for placement in maps::layout_placements(&map_item) {
+                placements.push((MapObject::new(placement.id, placement.x, placement.y),
+                                 format!("legend.{}", placement.glyph)));
+            }
+            for (i, mut map_object) in map_item.objects.into_iter().enumerate() {
+                if let Some(link) = map_object.link.take() {
+                    links.push((self.maps.len(), map_object.position.x, map_object.position.y, link,
+                                format!("objects[{}].link", i)));
                 }
-                if let Some(object) = object_opt {
-                    map.grid[pos_x][pos_y] = Option::<MapData>::Some(MapData::Object(object.to_owned()));
+                placements.push((map_object, format!("objects[{}]", i)));
+            }
+
+            let file = sources.maps.get(&map.info.id);
+            let mut reported = HashSet::<String>::new();
+            for (map_object, field) in placements {
+                // Positions have already been checked to be within the map
+                let (pos_x, pos_y) = (map_object.position.x, map_object.position.y);
+                if let Some(character) = characters.get(&map_object.id) {
+                    if map_object.has_overrides() {
+                        if let Some(file) = file {
+                            errors.push(file.error(&field, "Only objects can be given an instance id or changes"));
+                        }
+                    }
+                    let mut character = character.to_owned();
+                    character.home = (pos_x, pos_y);
+                    map.grid[pos_x][pos_y] = Option::<MapData>::Some(MapData::Character(character));
+                } else if let Some(object) = objects.get(&map_object.id) {
+                    if map_object.interactions.is_some() {
+                        changed_interactions.push((self.maps.len(), pos_x, pos_y, format!("{}.interactions", field)));
+                    }
+                    let instance = maps::place_object(object, &map.info.id, map_object, &field, file, errors);
+                    if !instance_ids.insert(instance.instance_id.clone()) {
+                        if let Some(file) = file {
+                            errors.push(file.error(&format!("{}.instance", field), format!(
+                                "The instance id '{}' is already used", instance.instance_id)));
+                        }
+                    }
+                    map.grid[pos_x][pos_y] = Option::<MapData>::Some(MapData::Object(instance));
+                } else if reported.insert(field.clone()) {
+                    if let Some(file) = file {
+                        errors.push(file.error(&format!("{}.id", field),
+                                               format!("No character or object with the id '{}' exists", map_object.id)));
+                    }
                 }
             }
 
             self.maps.push(map);
         }
 
+        for (m, x, y, link, field) in links {
+            let file = match sources.maps.get(&self.maps[m].info.id) {
+                Some(file) => { file }
+                None => { continue; }
+            };
+            match self.follow_link(m, &link) {
+                Ok(target) => {
+                    match &self.maps[m].grid[x][y] {
+                        Some(MapData::Object(object)) if object.travels() => {
+                            self.maps[m].links.push(DoorLink { x, y, target, one_way: link.one_way });
+                        }
+                        _ => {
+                            errors.push(file.error(&field, format!(
+                                "Only objects with a travel interaction can have a link, but nothing at ({}, {}) has one",
+                                x, y)));
+                        }
+                    }
+                }
+                Err((link_field, message)) => {
+                    errors.push(file.error(&format!("{}.{}", field, link_field), message));
+                }
+            }
+        }
+
+        for (m, x, y, field) in changed_interactions {
+            if let (Some(file), Some(MapData::Object(object))) = (sources.maps.get(&self.maps[m].info.id),
+                                                                  &self.maps[m].grid[x][y]) {
+                validation::check_interactions(self, sources, file, &field, object, errors);
+            }
+        }
+
         // Find and store the player in the state
         for character in &characters {
             if character.0 == "player" {
-                self.info.player = Option::<Character>::Some(characters.get("player").unwrap().to_owned());
+                self.info.player = Option::<Character>::Some(character.1.to_owned());
+            }
+        }
+
+    }
+
+    // Works out the map index and position that a link from a door in map m leads to. If it can't,
+    //    returns the field of the link that is wrong and what is wrong with it.
+    fn follow_link(&self, m: usize, link: &Link) -> Result<(usize, usize, usize), (&'static str, String)> {
+        let target_map = match &link.map {
+            Some(map_id) => {
+                match self.maps.iter().position(|map| &map.info.id == map_id) {
+                    Some(target_map) => { target_map }
+                    None => { return Err(("map", format!("No map with the id '{}' exists", map_id))); }
+                }
+            }
+            None => { m }
+        };
+        let map = &self.maps[target_map];
+
+        if let Some(spawn_id) = &link.spawn {
+            return match map.spawns.get(spawn_id) {
+                Some((x, y)) => { Ok((target_map, *x, *y)) }
+                None => { Err(("spawn", format!("The map '{}' has no spawn with the id '{}'", map.info.id, spawn_id))) }
+            };
+        }
+
+        // Doors are found by their instance id, or by their object id if there is only one of them
+        let door_id = link.door.as_deref().unwrap_or_default();
+        let mut found = vec![];
+        for (x, column) in map.grid.iter().enumerate() {
+            for (y, space) in column.iter().enumerate() {
+                if let Some(MapData::Object(object)) = space {
+                    if object.instance_id == door_id {
+                        return Ok((target_map, x, y));
+                    }
+                    if object.id == door_id {
+                        found.push((target_map, x, y));
+                    }
+                }
+            }
+        }
+        match found.len() {
+            1 => { Ok(found[0]) }
+            0 => { Err(("door", format!("The map '{}' has no object with the id '{}'", map.info.id, door_id))) }
+            count => {
+                Err(("door", format!("The map '{}' has {} objects with the id '{}', so the link needs the instance \
+                                      id of one of them", map.info.id, count, door_id)))
+            }
+        }
+    }
+
+    // Finds where the door at (x, y) in map m leads, as a map index and position. Doors with a link go
+    //    where it leads, and a door that another door's two-way link leads to goes back to that door.
+    //    Otherwise, doors lead to the door with the same id in the map named by their travel
+    //    destination, or failing that, in the first other map that has one.
+    pub fn door_destination(&self, m: usize, x: usize, y: usize) -> Option<(usize, usize, usize)> {
+        let map = self.maps.get(m)?;
+        if let Some(link) = map.links.iter().find(|link| link.x == x && link.y == y) {
+            return Some(link.target);
+        }
+        for (other_m, other_map) in self.maps.iter().enumerate() {
+            for link in &other_map.links {
+                if !link.one_way && link.target == (m, x, y) {
+                    return Some((other_m, link.x, link.y));
+                }
+            }
+        }
+
+        let door = match map.grid.get(x).and_then(|column| column.get(y)) {
+            Some(Some(MapData::Object(door))) => { door }
+            _ => { return None; }
+        };
+        if let Some(destination) = door.travel_destination() {
+            if let Some(target_map) = self.maps.iter().position(|map| map.info.id == destination) {
+                for (c, column) in self.maps[target_map].grid.iter().enumerate() {
+                    for (r, space) in column.iter().enumerate() {
+                        if let Some(MapData::Object(object)) = space {
+                            if object.id == door.id && (target_map, c, r) != (m, x, y) {
+                                return Some((target_map, c, r));
+                            }
+                        }
+                    }
+                }
             }
         }
+        self.find_door_other_side(m, &door.id)
+    }
 
-        Ok(())
+    // Finds the object with the given instance id, returning its map index and position
+    pub fn find_instance(&self, instance_id: &str) -> Option<(usize, usize, usize)> {
+        for (m, map) in self.maps.iter().enumerate() {
+            for (x, column) in map.grid.iter().enumerate() {
+                for (y, space) in column.iter().enumerate() {
+                    if let Some(MapData::Object(object)) = space {
+                        if object.instance_id == instance_id {
+                            return Some((m, x, y));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
 
+    // Finds the door with door_id that is not in the current map, returning its map index and position.
+    //    Doors lead to the first other map that has a door with the same id.
+    pub fn find_door_other_side(&self, current_map: usize, door_id: &str) -> Option<(usize, usize, usize)> {
+        // Go through each map
+        for (m, map) in self.maps.iter().enumerate() {
+            // Door should not be in the same map as the current map
+            if m == current_map {
+                continue;
+            }
+            // Look at every square in the map
+            for (c, column) in map.grid.iter().enumerate() {
+                // Then by each row
+                for (r, space) in column.iter().enumerate() {
+                    if let Some(MapData::Object(object)) = space {
+                        if object.id == door_id { // If correct door is found
+                            return Some((m, c, r));
+                        }
+                    }
+                }
+            }
+        }
+        None
     }
 
 }
 
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use files::MemoryFiles;
+
+    // Loads the example game with extra files added to it, or taking the place of the files with the
+    //    same paths
+    pub fn load_example_game_with(extra_files: &[(&str, &str)]) -> (GameData, Vec<ConfigError>) {
+        GameData::load_configs(&MemoryFiles::example_game_with(extra_files))
+    }
+
+    // The example game's game.yaml with more global variables declared, given as indented yaml lines
+    pub fn example_game_yaml_with_variables(variables: &str) -> String {
+        MemoryFiles::example_game_with(&[]).file("game.yaml").unwrap()
+            .replace("variables:\n", &format!("variables:\n{}", variables))
+    }
+}
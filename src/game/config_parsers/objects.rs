@@ -1,180 +1,515 @@
-use std::error::Error;
-use std::fs;
 use std::path::Path;
 use std::collections::HashMap;
-use yaml_rust::YamlLoader;
 use yaml_rust::Yaml;
-use crate::game::objects::{Object, ObjectState, ObjectInteraction, ObjectInteractionActivate,
-                           ObjectInteractionObjectUse};
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile, add_source, field_path, index_path};
+use crate::game::objects::{AttributeEffect, AttributeModifier, Comparison, EffectDuration, Equipment, LEVER_STATE, Object, ObjectCategory, ObjectState,
+                           ObjectInteraction, ObjectInteractionActivate, ObjectInteractionObjectUse, StateAction,
+                           StateOperation, StatePrereq, StateValue};
+
+// Fields that every object must define
+const REQUIRED_FIELDS: [&str; 3] = ["id", "category", "icon"];
 
 // Takes an object config file and loads it into an object, then adds that object to the objects list
 // so that it can later be added to the game map.
-pub fn process_config(objects: &mut HashMap<String, Object>, config_path: &Path) -> Result<(), Box<dyn Error>> {
-
-    // Load file contents
-    let file_contents = fs::read_to_string(config_path)?;
+pub fn process_config(objects: &mut HashMap<String, Object>, config_path: &Path, file_contents: &str,
+                      sources: &mut ConfigSources, errors: &mut Vec<ConfigError>) {
 
-    // Convert to YAML
-    let docs = YamlLoader::load_from_str(&*file_contents).unwrap();
-
-    // Multi document support, doc is a yaml::Yaml, need to extract the doc
-    let doc = &docs[0];
+    // Convert the file contents to YAML
+    let (doc, file) = match YamlFile::load(config_path, file_contents, errors) {
+        Some(loaded) => { loaded }
+        None => { return; }
+    };
 
     let mut object = Object{
         id: "".to_string(),
+        instance_id: "".to_string(),
         name: "".to_string(),
-        category: "".to_string(),
+        category: ObjectCategory::Simple,
         icon: ' ',
         state: vec![],
-        interactions: vec![]
+        interactions: vec![],
+        text: "".to_string(),
+        contents: vec![],
+        equip: Option::<Equipment>::None,
+        equipped: false,
+        consume: vec![],
     };
 
-    let object_hash = doc.as_hash().unwrap();
+    let object_hash = match file.get_hash("", &doc, errors) {
+        Some(hash) => { hash }
+        None => { return; }
+    };
+
+    let error_count = errors.len();
+    for field in REQUIRED_FIELDS {
+        file.get_field("", object_hash, field, errors);
+    }
 
-    for key in object_hash.keys() {
-        let val = object_hash.get(key).unwrap_or(key);
-        let key_str = key.as_str().unwrap();
+    for (key, val) in object_hash {
+        let key_str = match key.as_str() {
+            Some(key_str) => { key_str }
+            None => { continue; }
+        };
         if val == &Yaml::Null { // sometimes there are no states/interactions specified
             continue;
         }
         match key_str {
             "id" => {
-                object.id = val.as_str().unwrap().to_string();
+                if let Some(id) = file.get_str(key_str, val, errors) {
+                    object.id = id;
+                }
             }
             "name" => {
-                object.name = val.as_str().unwrap().to_string();
+                if let Some(name) = file.get_str(key_str, val, errors) {
+                    object.name = name;
+                }
             }
             "category" => {
-                object.category = val.as_str().unwrap().to_string();
+                if let Some(category) = file.get_str(key_str, val, errors) {
+                    match ObjectCategory::from_name(&category) {
+                        Some(category) => { object.category = category; }
+                        None => {
+                            let names: Vec<&str> = ObjectCategory::ALL.iter().map(|c| c.name()).collect();
+                            errors.push(file.error(key_str, format!("'{}' is not an object category, expected one of {}",
+                                                                    category, names.join(", "))));
+                        }
+                    }
+                }
+            }
+            "text" => {
+                if let Some(text) = file.get_str(key_str, val, errors) {
+                    object.text = text;
+                }
+            }
+            "contents" => {
+                if let Some(contents) = file.get_vec(key_str, val, errors) {
+                    for (i, content) in contents.iter().enumerate() {
+                        if let Some(content_id) = file.get_str(&index_path(key_str, i), content, errors) {
+                            object.contents.push(content_id);
+                        }
+                    }
+                }
             }
             "icon" => {
-                object.icon = val.as_str().unwrap().chars().take(1).last().unwrap();
+                match file.get_str(key_str, val, errors).and_then(|icon| icon.chars().next()) {
+                    Some(icon) => { object.icon = icon; }
+                    None => { errors.push(file.error(key_str, "Expected a single character")); }
+                }
             }
             "state" => {
-                parse_object_states(&mut object, val.as_vec().unwrap());
+                if let Some(states) = file.get_vec(key_str, val, errors) {
+                    parse_object_states(&mut object, &file, key_str, states, errors);
+                }
             }
             "interactions" => {
-                parse_object_interactions(&mut object, val);
+                parse_object_interactions(&mut object.interactions, &file, key_str, val, errors);
+            }
+            "equip" => {
+                object.equip = parse_equipment(&file, key_str, val, errors);
+            }
+            "consume" => {
+                if let Some(effects) = file.get_vec(key_str, val, errors) {
+                    parse_consume_effects(&mut object.consume, &file, key_str, effects, errors);
+                }
             }
             _ => {continue;}
         }
     }
 
-    objects.insert(object.id.clone(), object);
+    check_category(&mut object, &file, errors);
+
+    // The object as defined here is the prototype for every placement of it, which get their own
+    //    instance ids when they are placed
+    object.instance_id = object.id.clone();
 
-    Ok(())
+    // Only add objects that were fully loaded
+    if errors.len() == error_count && add_source(&mut sources.objects, "object", &object.id, file, errors) {
+        objects.insert(object.id.clone(), object);
+    }
 }
 
 
+// Makes sure the object has what its category needs: signs need text, only containers can hold
+//    other objects, only collectables can be equipped or consumed, and levers have a boolean `on` state, which
+//    starts off if it isn't given.
+fn check_category(object: &mut Object, file: &YamlFile, errors: &mut Vec<ConfigError>) {
+    if object.category == ObjectCategory::Sign && object.text.is_empty() {
+        errors.push(file.error("text", "Signs need some text to show"));
+    }
+    if object.category != ObjectCategory::Container && !object.contents.is_empty() {
+        errors.push(file.error("contents", "Only containers can hold other objects"));
+    }
+    if object.category != ObjectCategory::Collectable && object.equip.is_some() {
+        errors.push(file.error("equip", "Only collectable objects can be equipped"));
+    }
+    if object.category != ObjectCategory::Collectable && !object.consume.is_empty() {
+        errors.push(file.error("consume", "Only collectable objects can be consumed"));
+    }
+    if object.category == ObjectCategory::Lever {
+        match object.state_value(LEVER_STATE) {
+            Some(StateValue::Bool(_)) => {}
+            Some(_) => {
+                errors.push(file.error("state", format!("The '{}' state of a lever must be a boolean", LEVER_STATE)));
+            }
+            None => {
+                object.state.push(ObjectState { name: LEVER_STATE.to_string(), value: StateValue::Bool(false) });
+            }
+        }
+    }
+}
+
 // Parsing functions for individual components of the object are below.
 
-fn parse_object_interactions( object: &mut Object, yaml_interactions: &Yaml) {
-    let interaction_hash = yaml_interactions.as_hash().unwrap();
-    for key in interaction_hash.keys() {
-        let val = interaction_hash.get(key).unwrap_or(key);
-        let key_str = key.as_str().unwrap();
+// Also used for the interactions given to a single placement of an object in a map
+pub fn parse_object_interactions(interactions: &mut Vec<ObjectInteraction>, file: &YamlFile, field: &str,
+                                 yaml_interactions: &Yaml, errors: &mut Vec<ConfigError>) {
+    let interaction_hash = match file.get_hash(field, yaml_interactions, errors) {
+        Some(hash) => { hash }
+        None => { return; }
+    };
+    for (key, val) in interaction_hash {
+        let key_str = match key.as_str() {
+            Some(key_str) => { key_str }
+            None => { continue; }
+        };
         match key_str {
             "activate" => {
-                parse_interaction_activate(object, val);
+                parse_interaction_activate(interactions, file, &field_path(field, key_str), val, errors);
             }
             "object_use" => {
-                parse_interaction_object_use(object, val);
+                parse_interaction_object_use(interactions, file, &field_path(field, key_str), val, errors);
             }
             _ => { continue; }
         }
     }
 }
 
-fn parse_interaction_activate( object: &mut Object, yaml_activate: &Yaml) {
-    for activation in yaml_activate.as_vec().unwrap() {
+fn parse_interaction_activate(interactions: &mut Vec<ObjectInteraction>, file: &YamlFile, field: &str,
+                              yaml_activate: &Yaml, errors: &mut Vec<ConfigError>) {
+    let activations = match file.get_vec(field, yaml_activate, errors) {
+        Some(activations) => { activations }
+        None => { return; }
+    };
+    for (i, activation) in activations.iter().enumerate() {
+        let activation_path = index_path(field, i);
         let mut interaction = ObjectInteractionActivate {
             category: "".to_string(),
             prereqs: vec![],
+            self_action: vec![],
             destination: Option::None
         };
-        let activation_hash = activation.as_hash().unwrap();
-        for key in activation_hash.keys() {
-            let val = activation_hash .get(key).unwrap();
-            let key_str = key.as_str().unwrap();
+        let activation_hash = match file.get_hash(&activation_path, activation, errors) {
+            Some(hash) => { hash }
+            None => { continue; }
+        };
+        file.get_field(&activation_path, activation_hash, "category", errors);
+        for (key, val) in activation_hash {
+            let key_str = match key.as_str() {
+                Some(key_str) => { key_str }
+                None => { continue; }
+            };
+            let key_path = field_path(&activation_path, key_str);
             match key_str {
                 "category" => {
-                    interaction.category = val.as_str().unwrap().to_string();
+                    if let Some(category) = file.get_str(&key_path, val, errors) {
+                        interaction.category = category;
+                    }
                 }
                 "prereqs" => {
-                    parse_state_changes(&mut interaction.prereqs, val);
+                    parse_prereqs(&mut interaction.prereqs, file, &key_path, val, errors);
+                }
+                "self_action" => {
+                    parse_actions(&mut interaction.self_action, file, &key_path, val, errors);
                 }
                 "destination" => {
-                    interaction.destination = Option::Some(val.as_str().unwrap().to_string());
+                    if let Some(destination) = file.get_str(&key_path, val, errors) {
+                        interaction.destination = Option::Some(destination);
+                    }
                 }
                 _ => { continue; }
             }
         }
-        object.interactions.push(ObjectInteraction::ObjectInteractionActivate(interaction));
+        interactions.push(ObjectInteraction::ObjectInteractionActivate(interaction));
     }
 }
 
-fn parse_interaction_object_use( object: &mut Object, yaml_activate: &Yaml) {
-    for activation in yaml_activate.as_vec().unwrap() {
+fn parse_interaction_object_use(interactions: &mut Vec<ObjectInteraction>, file: &YamlFile, field: &str,
+                                yaml_activate: &Yaml, errors: &mut Vec<ConfigError>) {
+    let activations = match file.get_vec(field, yaml_activate, errors) {
+        Some(activations) => { activations }
+        None => { return; }
+    };
+    for (i, activation) in activations.iter().enumerate() {
+        let activation_path = index_path(field, i);
         let mut interaction = ObjectInteractionObjectUse {
             foreign_object_id: "".to_string(),
             self_action: vec![],
             consume_item: false
         };
-        let activation_hash = activation.as_hash().unwrap();
-        for key in activation_hash.keys() {
-            let val = activation_hash .get(key).unwrap();
-            let key_str = key.as_str().unwrap();
+        let activation_hash = match file.get_hash(&activation_path, activation, errors) {
+            Some(hash) => { hash }
+            None => { continue; }
+        };
+        file.get_field(&activation_path, activation_hash, "foreign_objects_id", errors);
+        for (key, val) in activation_hash {
+            let key_str = match key.as_str() {
+                Some(key_str) => { key_str }
+                None => { continue; }
+            };
+            let key_path = field_path(&activation_path, key_str);
             match key_str {
                 "foreign_objects_id" => {
-                    interaction.foreign_object_id = val.as_str().unwrap().to_string();
+                    if let Some(foreign_object_id) = file.get_str(&key_path, val, errors) {
+                        interaction.foreign_object_id = foreign_object_id;
+                    }
                 }
                 "self_action" => {
-                    parse_state_changes(&mut interaction.self_action, val);
+                    parse_actions(&mut interaction.self_action, file, &key_path, val, errors);
                 }
                 "consume_item" => {
-                    interaction.consume_item = val.as_bool().unwrap();
+                    if let Some(consume_item) = file.get_bool(&key_path, val, errors) {
+                        interaction.consume_item = consume_item;
+                    }
                 }
                 _ => { continue; }
             }
         }
-        object.interactions.push(ObjectInteraction::ObjectInteractionObjectUse(interaction));
+        interactions.push(ObjectInteraction::ObjectInteractionObjectUse(interaction));
     }
 }
 
-fn parse_state_changes(state_changes: &mut Vec<ObjectState>, yaml_changes: &Yaml) {
-    for change in yaml_changes.as_vec().unwrap() {
-        let mut state = ObjectState{
-            name: "".to_string(),
-            value: true
+// Parses a list of prereqs like `- unlocked: true` or `- pulls: ">= 3"`. Values starting with one of
+//    the comparison symbols are compared that way, and the rest of the value has its type inferred.
+//    Anything else must be equal to the value as it is written.
+fn parse_prereqs(prereqs: &mut Vec<StatePrereq>, file: &YamlFile, field: &str, yaml_prereqs: &Yaml,
+                 errors: &mut Vec<ConfigError>) {
+    for (name, key_path, val) in state_list(file, field, yaml_prereqs, errors) {
+        let (comparison, value) = match val.as_str().and_then(split_comparison) {
+            Some((comparison, rest)) => { (comparison, infer_state_value(rest)) }
+            None => {
+                match parse_state_value(file, &key_path, val, errors) {
+                    Some(value) => { (Comparison::Equal, value) }
+                    None => { continue; }
+                }
+            }
+        };
+        prereqs.push(StatePrereq { name, comparison, value });
+    }
+}
+
+// Parses a list of actions like `- unlocked: true` or `- pulls: "+= 1"`. Values starting with "+=" or
+//    "-=" add to or subtract from an integer state, and anything else replaces the state.
+fn parse_actions(actions: &mut Vec<StateAction>, file: &YamlFile, field: &str, yaml_actions: &Yaml,
+                 errors: &mut Vec<ConfigError>) {
+    for (name, key_path, val) in state_list(file, field, yaml_actions, errors) {
+        match val.as_str().and_then(split_operation) {
+            Some((operation, amount)) => {
+                match amount.trim().parse::<i64>() {
+                    Ok(amount) => {
+                        actions.push(StateAction { name, operation, value: StateValue::Int(amount) });
+                    }
+                    Err(_) => { errors.push(file.error(&key_path, "Expected an integer to add or subtract")); }
+                }
+            }
+            None => {
+                if let Some(value) = parse_state_value(file, &key_path, val, errors) {
+                    actions.push(StateAction { name, operation: StateOperation::Set, value });
+                }
+            }
+        }
+    }
+}
+
+// Splits "+=" or "-=" off the start of an action's value
+pub fn split_operation(text: &str) -> Option<(StateOperation, &str)> {
+    let text = text.trim_start();
+    if let Some(amount) = text.strip_prefix("+=") {
+        Some((StateOperation::Increment, amount))
+    } else {
+        text.strip_prefix("-=").map(|amount| (StateOperation::Decrement, amount))
+    }
+}
+
+// Goes through a list of single state ids and values, returning the state id, the path of the value,
+//    and the value for each
+fn state_list<'a>(file: &YamlFile, field: &str, yaml_list: &'a Yaml, errors: &mut Vec<ConfigError>)
+                  -> Vec<(String, String, &'a Yaml)> {
+    let mut states = vec![];
+    let list = match file.get_vec(field, yaml_list, errors) {
+        Some(list) => { list }
+        None => { return states; }
+    };
+    for (i, item) in list.iter().enumerate() {
+        let item_path = index_path(field, i);
+        let state_hash = match file.get_hash(&item_path, item, errors) {
+            Some(hash) => { hash }
+            None => { continue; }
+        };
+        for (key, val) in state_hash {
+            if let Some(key_str) = key.as_str() {
+                states.push((key_str.to_string(), field_path(&item_path, key_str), val));
+            }
+        }
+    }
+    states
+}
+
+// Splits a comparison symbol off the start of a prereq's value
+pub fn split_comparison(text: &str) -> Option<(Comparison, &str)> {
+    let text = text.trim_start();
+    Comparison::SYMBOLS.iter()
+        .find(|(symbol, _)| text.starts_with(symbol))
+        .map(|(symbol, comparison)| (*comparison, text[symbol.len()..].trim()))
+}
+
+// Works out the type of a value written as text: true and false are booleans, whole numbers are
+//    integers, and anything else is a string. Quotes can be put around a string to keep it from
+//    being read as anything else, like `"== '3'"`.
+pub fn infer_state_value(text: &str) -> StateValue {
+    for quote in ['\'', '"'] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return StateValue::Str(text[1..text.len() - 1].to_string());
+        }
+    }
+    match text {
+        "true" => { StateValue::Bool(true) }
+        "false" => { StateValue::Bool(false) }
+        _ => {
+            match text.parse::<i64>() {
+                Ok(i) => { StateValue::Int(i) }
+                Err(_) => { StateValue::Str(text.to_string()) }
+            }
+        }
+    }
+}
+
+// Converts a YAML value into a state value, keeping its type
+pub fn parse_state_value(file: &YamlFile, field: &str, val: &Yaml, errors: &mut Vec<ConfigError>) -> Option<StateValue> {
+    match val {
+        Yaml::Boolean(b) => { Some(StateValue::Bool(*b)) }
+        Yaml::Integer(i) => { Some(StateValue::Int(*i)) }
+        Yaml::String(s) => { Some(StateValue::Str(s.clone())) }
+        _ => {
+            errors.push(file.error(field, "Expected a boolean, an integer, or a string"));
+            None
+        }
+    }
+}
+
+// Reads the equipment slot the object goes in and the modifiers it gives to attributes
+fn parse_equipment(file: &YamlFile, field: &str, yaml_equip: &Yaml, errors: &mut Vec<ConfigError>) -> Option<Equipment> {
+    let equip_hash = file.get_hash(field, yaml_equip, errors)?;
+    let mut equipment = Equipment { slot: "".to_string(), modifiers: vec![] };
+    let slot = file.get_field(field, equip_hash, "slot", errors)
+        .and_then(|slot| file.get_str(&field_path(field, "slot"), slot, errors))?;
+    equipment.slot = slot;
+
+    let modifiers_path = field_path(field, "modifiers");
+    let yaml_modifiers = match equip_hash.get(&Yaml::String("modifiers".to_string())) {
+        Some(Yaml::Null) | None => { return Some(equipment); }
+        Some(yaml_modifiers) => { file.get_vec(&modifiers_path, yaml_modifiers, errors)? }
+    };
+    for (i, yaml_modifier) in yaml_modifiers.iter().enumerate() {
+        let modifier_path = index_path(&modifiers_path, i);
+        let modifier_hash = match file.get_hash(&modifier_path, yaml_modifier, errors) {
+            Some(hash) => { hash }
+            None => { continue; }
         };
-        let state_hash = change.as_hash().unwrap();
-        for key in state_hash.keys() {
-            let val = state_hash.get(key).unwrap();
-            let key_str = key.as_str().unwrap();
-            state.name = key_str.to_string();
-            state.value = val.as_bool().unwrap();
+        let attribute_id = file.get_field(&modifier_path, modifier_hash, "attribute_id", errors)
+            .and_then(|id| file.get_str(&field_path(&modifier_path, "attribute_id"), id, errors));
+        let amount = file.get_field(&modifier_path, modifier_hash, "amount", errors)
+            .and_then(|amount| file.get_i64(&field_path(&modifier_path, "amount"), amount, errors));
+        if let (Some(attribute_id), Some(amount)) = (attribute_id, amount) {
+            equipment.modifiers.push(AttributeModifier { attribute_id, amount });
+        }
+    }
+    Some(equipment)
+}
+
+// Reads the effects of using up a consumable object. Each one changes an attribute by an amount,
+//    either for good or, with a duration of some turns or ticks, for a while.
+fn parse_consume_effects(consume: &mut Vec<AttributeEffect>, file: &YamlFile, field: &str, yaml_effects: &[Yaml],
+                         errors: &mut Vec<ConfigError>) {
+    for (i, yaml_effect) in yaml_effects.iter().enumerate() {
+        let effect_path = index_path(field, i);
+        let effect_hash = match file.get_hash(&effect_path, yaml_effect, errors) {
+            Some(hash) => { hash }
+            None => { continue; }
+        };
+        let attribute_id = file.get_field(&effect_path, effect_hash, "attribute_id", errors)
+            .and_then(|id| file.get_str(&field_path(&effect_path, "attribute_id"), id, errors));
+        let amount = file.get_field(&effect_path, effect_hash, "amount", errors)
+            .and_then(|amount| file.get_i64(&field_path(&effect_path, "amount"), amount, errors));
+        let duration = match effect_hash.get(&Yaml::String("duration".to_string())) {
+            Some(Yaml::Null) | None => { Some(Option::<EffectDuration>::None) }
+            Some(yaml_duration) => {
+                parse_effect_duration(file, &field_path(&effect_path, "duration"), yaml_duration, errors).map(Some)
+            }
+        };
+        if let (Some(attribute_id), Some(amount), Some(duration)) = (attribute_id, amount, duration) {
+            consume.push(AttributeEffect { attribute_id, amount, duration });
+        }
+    }
+}
+
+// A duration is either `turns: N` or `ticks: N`, for some N greater than 0
+fn parse_effect_duration(file: &YamlFile, field: &str, yaml_duration: &Yaml,
+                         errors: &mut Vec<ConfigError>) -> Option<EffectDuration> {
+    let duration_hash = file.get_hash(field, yaml_duration, errors)?;
+    if duration_hash.len() != 1 {
+        errors.push(file.error(field, "Expected either 'turns' or 'ticks'"));
+        return None;
+    }
+    let (key, val) = duration_hash.front()?;
+    let key_str = key.as_str().unwrap_or_default();
+    let key_path = field_path(field, key_str);
+    let count = match key_str {
+        "turns" | "ticks" => { file.get_i64(&key_path, val, errors)? }
+        _ => {
+            errors.push(file.error(field, "Expected either 'turns' or 'ticks'"));
+            return None;
         }
-        state_changes.push(state);
+    };
+    if count <= 0 {
+        errors.push(file.error(&key_path, "Expected an integer greater than 0"));
+        return None;
+    }
+    match key_str {
+        "turns" => { Some(EffectDuration::Turns(count as u64)) }
+        _ => { Some(EffectDuration::Ticks(count as u64)) }
     }
 }
 
-fn parse_object_states(object: &mut Object, yaml_states: &[Yaml]) {
-    for yaml_state in yaml_states {
+fn parse_object_states(object: &mut Object, file: &YamlFile, field: &str, yaml_states: &[Yaml],
+                       errors: &mut Vec<ConfigError>) {
+    for (i, yaml_state) in yaml_states.iter().enumerate() {
+        let state_path = index_path(field, i);
         let mut state = ObjectState{
             name: "".to_string(),
-            value: true
+            value: StateValue::Bool(true)
         };
-        let state_hash = yaml_state.as_hash().unwrap();
-        for key in state_hash.keys() {
-            let val = state_hash
-                            .get(key).unwrap();
-            let key_str = key.as_str().unwrap();
+        let state_hash = match file.get_hash(&state_path, yaml_state, errors) {
+            Some(hash) => { hash }
+            None => { continue; }
+        };
+        file.get_field(&state_path, state_hash, "id", errors);
+        file.get_field(&state_path, state_hash, "default", errors);
+        for (key, val) in state_hash {
+            let key_str = match key.as_str() {
+                Some(key_str) => { key_str }
+                None => { continue; }
+            };
+            let key_path = field_path(&state_path, key_str);
             match key_str {
                 "id" => {
-                    state.name = val.as_str().unwrap().to_string();
+                    if let Some(name) = file.get_str(&key_path, val, errors) {
+                        state.name = name;
+                    }
                 }
                 "default" => {
-                    state.value = val.as_bool().unwrap();
+                    if let Some(value) = parse_state_value(file, &key_path, val, errors) {
+                        state.value = value;
+                    }
                 }
                 _ => { continue; }
             }
@@ -182,3 +517,300 @@ fn parse_object_states(object: &mut Object, yaml_states: &[Yaml]) {
         object.state.push(state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::game::config_parsers::tests::load_example_game_with;
+    use crate::game::maps::MapData;
+
+    // Loads the example game with the object placed in a map of its own, returning the placed object
+    //    and the problems found in the object's file
+    fn load_dial(object_yaml: &str) -> (Option<Object>, Vec<String>) {
+        let map_yaml = "id: \"dial_room\"\ndescription: \"\"\nsize: { width: 3, height: 3 }\n\
+                        objects:\n  - id: \"dial\"\n    position: { x: 1, y: 1 }\n";
+        let (game_data, problems) = load_example_game_with(&[("maps/dial_room.yaml", map_yaml),
+                                                             ("objects/dial.yaml", object_yaml)]);
+        let object = game_data.maps.iter()
+            .find(|m| m.info.id == "dial_room")
+            .and_then(|m| match &m.grid[1][1] {
+                Some(MapData::Object(object)) => { Some(object.clone()) }
+                _ => { None }
+            });
+        let problems = problems.iter()
+            .filter(|p| p.is_error() && p.path.ends_with("dial.yaml"))
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect();
+        (object, problems)
+    }
+
+    #[test]
+    fn state_types_are_inferred() {
+        let (dial, problems) = load_dial(r##"
+id: "dial"
+name: "Dial"
+category: "simple"
+icon: "D"
+state:
+  - id: "turns"
+    default: 0
+  - id: "direction"
+    default: "north"
+  - id: "stuck"
+    default: false
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - turns: "< 3"
+        - direction: "!= south"
+        - stuck: false
+      self_action:
+        - turns: "+= 1"
+    - category: "use"
+      prereqs:
+        - turns: ">= 3"
+        - direction: "== '3'"
+      self_action:
+        - direction: "south"
+        - turns: "-= 3"
+"##);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let mut dial = dial.unwrap();
+        assert_eq!(dial.state_value("turns"), Some(&StateValue::Int(0)));
+        assert_eq!(dial.state_value("direction"), Some(&StateValue::Str("north".to_string())));
+        assert_eq!(dial.state_value("stuck"), Some(&StateValue::Bool(false)));
+
+        let (turn, reset) = match (&dial.interactions[0], &dial.interactions[1]) {
+            (ObjectInteraction::ObjectInteractionActivate(turn), ObjectInteraction::ObjectInteractionActivate(reset)) => {
+                (turn.clone(), reset.clone())
+            }
+            _ => { panic!("Expected two activate interactions") }
+        };
+        assert_eq!(reset.prereqs[1].value, StateValue::Str("3".to_string()));
+
+        // Turning the dial three times lets it be reset
+        for _ in 0..3 {
+            assert!(dial.prereqs_met(&turn.prereqs, &BTreeMap::new()));
+            assert!(!dial.prereqs_met(&reset.prereqs, &BTreeMap::new()));
+            dial.apply_action(&turn.self_action[0]);
+        }
+        assert!(!dial.prereqs_met(&turn.prereqs, &BTreeMap::new()));
+        assert_eq!(dial.state_value("turns"), Some(&StateValue::Int(3)));
+        dial.set_state("direction".to_string(), StateValue::Str("3".to_string()));
+        assert!(dial.prereqs_met(&reset.prereqs, &BTreeMap::new()));
+        for action in &reset.self_action {
+            dial.apply_action(action);
+        }
+        assert_eq!(dial.state_value("turns"), Some(&StateValue::Int(0)));
+        assert_eq!(dial.state_value("direction"), Some(&StateValue::Str("south".to_string())));
+    }
+
+    // Object configs, without the id, name, and icon that every one of them has, along with every
+    //    problem found in them
+    const OBJECT_PROBLEMS: &[(&str, &[&str])] = &[
+        (r##"
+category: "simple"
+state:
+  - id: "turns"
+    default: 0
+  - id: "direction"
+    default: "north"
+  - id: "stuck"
+    default: [1, 2]
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - turns: "north"
+        - direction: "> east"
+      self_action:
+        - direction: "+= 1"
+        - turns: "+= lots"
+        - turns: true
+"##, &[
+            "state[2].default: Expected a boolean, an integer, or a string",
+            "interactions.activate[0].self_action[1].turns: Expected an integer to add or subtract",
+        ]),
+        (r##"
+category: "simple"
+state:
+  - id: "turns"
+    default: 0
+  - id: "direction"
+    default: "north"
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - turns: "north"
+        - direction: "> east"
+      self_action:
+        - direction: "+= 1"
+        - turns: true
+"##, &[
+            "interactions.activate[0].prereqs[0].turns: The state 'turns' has integer values, but is compared \
+             to the string 'north'",
+            "interactions.activate[0].prereqs[1].direction: The state 'direction' has string values, so it can only \
+             be compared with == or !=",
+            "interactions.activate[0].self_action[0].direction: The state 'direction' has string values, so it can't \
+             be added to or subtracted from",
+            "interactions.activate[0].self_action[1].turns: The state 'turns' has integer values, but is set to the \
+             boolean true",
+        ]),
+        (r##"
+category: "dial"
+"##, &[
+            "category: 'dial' is not an object category, expected one of simple, collidable, collectable, door, \
+             container, sign, lever",
+        ]),
+        (r##"
+category: "sign"
+contents:
+  - "hat"
+"##, &[
+            "text: Signs need some text to show",
+            "contents: Only containers can hold other objects",
+        ]),
+        (r##"
+category: "container"
+contents:
+  - "hat"
+  - "table"
+  - "crown"
+"##, &[
+            "contents[1]: The object 'table' is collidable, but only collectable objects can be put in containers",
+            "contents[2]: No object with the id 'crown' exists",
+        ]),
+        (r##"
+category: "simple"
+equip:
+  modifiers:
+    - attribute_id: "skill_1"
+"##, &[
+            "equip.slot: Missing required field",
+        ]),
+        (r##"
+category: "simple"
+equip:
+  slot: "head"
+  modifiers:
+    - attribute_id: "skill_1"
+"##, &[
+            "equip: Only collectable objects can be equipped",
+            "equip.modifiers[0].amount: Missing required field",
+        ]),
+        (r##"
+category: "collectable"
+equip:
+  slot: "finger"
+  modifiers:
+    - attribute_id: "luck"
+      amount: 1
+"##, &[
+            "equip.slot: The player has no equipment slot named 'finger'",
+            "equip.modifiers[0].attribute_id: The player has no attribute with the id 'luck'",
+        ]),
+        (r##"
+category: "simple"
+consume:
+  - attribute_id: "health"
+    amount: 10
+  - attribute_id: "health"
+  - attribute_id: "health"
+    amount: 10
+    duration: { turns: 0 }
+  - attribute_id: "health"
+    amount: 10
+    duration: { seconds: 3 }
+"##, &[
+            "consume: Only collectable objects can be consumed",
+            "consume[1].amount: Missing required field",
+            "consume[2].duration.turns: Expected an integer greater than 0",
+            "consume[3].duration: Expected either 'turns' or 'ticks'",
+        ]),
+        (r##"
+category: "collectable"
+consume:
+  - attribute_id: "luck"
+    amount: 1
+"##, &[
+            "consume[0].attribute_id: Neither the player nor any character has an attribute with the id 'luck'",
+        ]),
+    ];
+
+    #[test]
+    fn object_problems_are_reported() {
+        for (object_yaml, expected) in OBJECT_PROBLEMS {
+            let (_, problems) = load_dial(&format!("id: \"dial\"\nname: \"Dial\"\nicon: \"D\"\n{}", object_yaml));
+            assert_eq!(&problems, expected, "Problems found in the object:{}", object_yaml);
+        }
+    }
+
+    #[test]
+    fn categories_are_loaded() {
+        let (dial, problems) = load_dial(r##"
+id: "dial"
+name: "Dial"
+category: "container"
+icon: "D"
+contents:
+  - "hat"
+"##);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let dial = dial.unwrap();
+        assert_eq!(dial.category, ObjectCategory::Container);
+        assert_eq!(dial.contents, vec!["hat"]);
+
+        let (dial, problems) = load_dial(r##"
+id: "dial"
+name: "Dial"
+category: "lever"
+icon: "D"
+state:
+interactions:
+"##);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(dial.unwrap().state_value(LEVER_STATE), Some(&StateValue::Bool(false)));
+    }
+
+    #[test]
+    fn equipment_is_loaded() {
+        let (dial, problems) = load_dial(r##"
+id: "dial"
+name: "Dial"
+category: "collectable"
+icon: "D"
+equip:
+  slot: "head"
+  modifiers:
+    - attribute_id: "skill_1"
+      amount: -2
+"##);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let modifier = AttributeModifier { attribute_id: "skill_1".to_string(), amount: -2 };
+        assert_eq!(dial.unwrap().equip, Some(Equipment { slot: "head".to_string(), modifiers: vec![modifier] }));
+    }
+
+    #[test]
+    fn consume_effects_are_loaded() {
+        let (dial, problems) = load_dial(r##"
+id: "dial"
+name: "Dial"
+category: "collectable"
+icon: "D"
+consume:
+  - attribute_id: "health"
+    amount: 10
+  - attribute_id: "skill_2"
+    amount: -1
+    duration: { ticks: 20 }
+"##);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(dial.unwrap().consume, vec![
+            AttributeEffect { attribute_id: "health".to_string(), amount: 10, duration: None },
+            AttributeEffect { attribute_id: "skill_2".to_string(), amount: -1, duration: Some(EffectDuration::Ticks(20)) },
+        ]);
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::game::config_parsers::dialogs::{split_conditions, split_effects};
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile, add_source};
+use crate::game::quests::Quest;
+
+// Reads a quest config file, which holds a single quest
+pub fn process_config_serde(quests: &mut HashMap<String, Quest>, config_path: &Path, file_contents: &str,
+                            sources: &mut ConfigSources, errors: &mut Vec<ConfigError>) {
+    let doc = serde_yaml::from_str::<Quest>(file_contents);
+    match doc {
+        Ok(mut quest) =>{
+            let file = YamlFile::new(config_path, file_contents);
+            check_stages(&mut quest, &file, errors);
+            if add_source(&mut sources.quests, "quest", &quest.id, file, errors) {
+                quests.insert(quest.id.clone(), quest);
+            }
+        }
+        Err(err) =>{
+            errors.push(ConfigError::from_serde(config_path, &err));
+        }
+    }
+}
+
+// Makes sure the quest has stages to work through, and that every stage has something that finishes
+//    it. Comparisons and additions are split off the values of conditions and rewards, the same way as
+//    for dialog options.
+fn check_stages(quest: &mut Quest, file: &YamlFile, errors: &mut Vec<ConfigError>) {
+    if quest.stages.is_empty() {
+        errors.push(file.error("stages", "Quests need at least one stage"));
+    }
+    split_conditions(&mut quest.start);
+    for (i, stage) in quest.stages.iter_mut().enumerate() {
+        if stage.conditions.is_empty() {
+            errors.push(file.error(&format!("stages[{}]", i), "Stages need at least one condition that finishes them"));
+        }
+        split_conditions(&mut stage.conditions);
+    }
+    split_effects(&mut quest.rewards, file, "rewards", errors);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::config_parsers::tests::{example_game_yaml_with_variables, load_example_game_with};
+    use crate::game::dialogs::{DialogCondition, DialogEffect};
+    use crate::game::objects::{Comparison, StateValue};
+
+    #[test]
+    fn quests_have_stages_and_rewards() {
+        let game_yaml = example_game_yaml_with_variables("  heard_about_hat: false\n");
+        let (game_data, problems) = load_example_game_with(&[("game.yaml", &game_yaml), ("quests/lost_hat.yaml", r##"
+id: "lost_hat"
+name: "The Lost Hat"
+description: "Someone lost their hat."
+start:
+  - flag: "heard_about_hat"
+stages:
+  - description: "Find the hat"
+    conditions:
+      - has_item: "hat"
+  - description: "Give the hat away"
+    conditions:
+      - lacks_item: "hat"
+      - attribute: { id: "skill_3", value: "> 5" }
+rewards:
+  - change_attribute: { id: "skill_1", amount: 2 }
+"##)]);
+        assert!(problems.iter().all(|p| !p.is_error()), "{:?}", problems);
+        let quest = &game_data.quests["lost_hat"];
+        assert_eq!(quest.name, "The Lost Hat");
+        assert!(matches!(&quest.start[..], [DialogCondition::Flag(flag)] if flag == "heard_about_hat"));
+        assert_eq!(quest.stages.len(), 2);
+        assert_eq!(quest.stages[1].description, "Give the hat away");
+        match &quest.stages[1].conditions[1] {
+            DialogCondition::Attribute(attribute) => {
+                assert_eq!(attribute.comparison, Comparison::Greater);
+                assert_eq!(attribute.value, StateValue::Int(5));
+            }
+            condition => { panic!("Expected an attribute condition, found {:?}", condition); }
+        }
+        assert!(matches!(&quest.rewards[..], [DialogEffect::ChangeAttribute { amount: 2, .. }]));
+    }
+
+    #[test]
+    fn quest_problems_are_reported() {
+        let (_, problems) = load_example_game_with(&[("quests/empty.yaml", r##"
+id: "empty"
+name: "Nothing to Do"
+stages: []
+"##), ("quests/lost_hat.yaml", r##"
+id: "lost_hat"
+name: "The Lost Hat"
+stages:
+  - description: "Find the hat"
+rewards:
+  - set_variable: { id: "hats", value: "+= many" }
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "stages: Quests need at least one stage",
+            "stages[0]: Stages need at least one condition that finishes them",
+            "rewards[0].set_variable.value: Expected an integer to add or subtract",
+        ]);
+
+        let (_, problems) = load_example_game_with(&[("quests/lost_hat.yaml", r##"
+id: "lost_hat"
+name: "The Lost Hat"
+start:
+  - seen_dialog: "AA lost hat"
+stages:
+  - description: "Find the hat"
+    conditions:
+      - has_item: "cap"
+      - state: { object: "door_1@Main Room:10,9", state: "unlocked", value: 1 }
+rewards:
+  - give_item: "crown"
+  - set_dialog: "AA give hat"
+  - fight
+"##)]);
+        let problems = problems.iter()
+            .map(|p| format!("{}: {}", p.field.clone().unwrap_or_default(), p.message))
+            .collect::<Vec<String>>();
+        assert_eq!(problems, vec![
+            "start[0].seen_dialog: No dialog with the id 'AA lost hat' exists",
+            "stages[0].conditions[0].has_item: No object with the id 'cap' exists",
+            "stages[0].conditions[1].state: The state 'unlocked' has boolean values, but is compared to the integer 1",
+            "rewards[0].give_item: No object with the id 'crown' exists",
+            "rewards[1]: Only dialogs can use the set_dialog effect, since it needs an NPC being talked to",
+            "rewards[2]: Only dialogs can use the fight effect, since it needs an NPC being talked to",
+        ]);
+    }
+}
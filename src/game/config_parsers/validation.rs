@@ -0,0 +1,810 @@
+use std::collections::{HashSet, VecDeque};
+use crate::game::characters::Character;
+use crate::game::characters::behavior::Behavior;
+use crate::game::config_parsers::GameData;
+use crate::game::config_parsers::errors::{ConfigError, ConfigSources, YamlFile};
+use crate::game::dialogs::{DialogCondition, DialogEffect};
+use crate::game::maps::MapData;
+use crate::game::objects::{global_variable, GLOBAL_PREFIX, Object, ObjectCategory, ObjectInteraction, StateAction,
+                           StateOperation, StatePrereq, StateValue};
+
+// Keywords that can be used as a dialog option's `next` value instead of a dialog id
+pub const DIALOG_KEYWORDS: [&str; 3] = ["exit", "inventory", "fight"];
+
+// Checks that everything in the loaded game refers to things that actually exist: maps, dialogs,
+// objects, and object states. Also checks that the player starts somewhere they can stand, and
+// warns about maps that the player has no way of getting to.
+pub fn validate(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    check_starting_position(game_data, sources, errors);
+
+    let mut checked_objects = HashSet::<String>::new();
+    let mut checked_characters = HashSet::<String>::new();
+    let mut checked_patrols = HashSet::<(String, usize)>::new();
+    for (m, map) in game_data.maps.iter().enumerate() {
+        for (x, column) in map.grid.iter().enumerate() {
+            for (y, space) in column.iter().enumerate() {
+                match space {
+                    Some(MapData::Object(object)) => {
+                        if checked_objects.insert(object.id.clone()) {
+                            check_object(game_data, sources, object, errors);
+                        }
+                        if object.travels() && game_data.door_destination(m, x, y).is_none() {
+                            if let Some(file) = sources.maps.get(&map.info.id) {
+                                errors.push(file.error("objects", format!(
+                                    "The door '{}' at ({}, {}) leads nowhere: give it a link, or put a door with \
+                                     the same id in another map", object.id, x, y)));
+                            }
+                        }
+                    }
+                    Some(MapData::Character(character)) => {
+                        if checked_characters.insert(character.id.clone()) {
+                            check_character(game_data, sources, character, errors);
+                        }
+                        if checked_patrols.insert((character.id.clone(), m)) {
+                            check_waypoints(game_data, sources, m, character, errors);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    if let Some(player) = &game_data.info.player {
+        if checked_characters.insert(player.id.clone()) {
+            check_character(game_data, sources, player, errors);
+        }
+    }
+
+    check_equipment(game_data, sources, errors);
+    check_consumables(game_data, sources, errors);
+    check_dialogs(game_data, sources, errors);
+    check_quests(game_data, sources, errors);
+    check_reachable_maps(game_data, sources, errors);
+}
+
+// Makes sure the starting map exists, and that the starting position is somewhere the player can
+//    stand: inside the map, on a tile that can be walked on, and not blocked by a collidable object
+fn check_starting_position(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    let file = match &sources.game {
+        Some(file) => { file }
+        None => { return; }
+    };
+    let info = &game_data.info;
+    let map = match game_data.maps.iter().find(|m| m.info.id == info.starting_map) {
+        Some(map) => { map }
+        None => {
+            errors.push(file.error("starting_map", format!("No map with the id '{}' exists", info.starting_map)));
+            return;
+        }
+    };
+
+    let x = info.starting_position_x as usize;
+    let y = info.starting_position_y as usize;
+    if x >= map.grid.len() || y >= map.grid[0].len() {
+        errors.push(file.error("starting_position", format!(
+            "Starting position ({}, {}) is outside of map '{}', which is {}x{}",
+            x, y, map.info.id, map.info.size.width, map.info.size.height)));
+    } else if !map.tiles[x][y].passable {
+        errors.push(file.error("starting_position", format!(
+            "Starting position ({}, {}) is on a {} tile in map '{}' that can't be walked on",
+            x, y, map.tiles[x][y].kind.name(), map.info.id)));
+    } else if let Some(MapData::Object(object)) = &map.grid[x][y] {
+        if !object.category.passable(true) {
+            errors.push(file.error("starting_position", format!(
+                "Starting position ({}, {}) is blocked by the object '{}'", x, y, object.id)));
+        }
+    }
+}
+
+// Checks the references in an object's interactions, and that anything in a container can be
+//    picked up
+fn check_object(game_data: &GameData, sources: &ConfigSources, object: &Object, errors: &mut Vec<ConfigError>) {
+    let file = match sources.objects.get(&object.id) {
+        Some(file) => { file }
+        None => { return; }
+    };
+    check_interactions(game_data, sources, file, "interactions", object, errors);
+    for (i, content_id) in object.contents.iter().enumerate() {
+        let field = format!("contents[{}]", i);
+        match game_data.objects.get(content_id) {
+            Some(content) if content.category != ObjectCategory::Collectable => {
+                errors.push(file.error(&field, format!(
+                    "The object '{}' is {}, but only collectable objects can be put in containers",
+                    content_id, content.category.name())));
+            }
+            Some(_) => {}
+            None => {
+                errors.push(file.error(&field, format!("No object with the id '{}' exists", content_id)));
+            }
+        }
+    }
+}
+
+// Checks the references in the interactions of an object, which are found in the given field of the
+//    file. Placements of objects in maps can give them interactions of their own.
+pub fn check_interactions(game_data: &GameData, sources: &ConfigSources, file: &YamlFile, field: &str,
+                          object: &Object, errors: &mut Vec<ConfigError>) {
+    let (mut activate_index, mut object_use_index) = (0, 0);
+    for interaction in &object.interactions {
+        match interaction {
+            ObjectInteraction::ObjectInteractionActivate(activate) => {
+                let field = format!("{}.activate[{}]", field, activate_index);
+                if let Some(destination) = &activate.destination {
+                    if !game_data.maps.iter().any(|m| &m.info.id == destination) {
+                        errors.push(file.error(&format!("{}.destination", field),
+                                               format!("No map with the id '{}' exists", destination)));
+                    }
+                }
+                check_prereqs(game_data, file, object, &activate.prereqs, &format!("{}.prereqs", field), errors);
+                check_actions(game_data, file, object, &activate.self_action, &format!("{}.self_action", field), errors);
+                activate_index += 1;
+            }
+            ObjectInteraction::ObjectInteractionObjectUse(object_use) => {
+                let field = format!("{}.object_use[{}]", field, object_use_index);
+                if !sources.objects.contains_key(&object_use.foreign_object_id) {
+                    errors.push(file.error(&format!("{}.foreign_objects_id", field),
+                                           format!("No object with the id '{}' exists", object_use.foreign_object_id)));
+                }
+                check_actions(game_data, file, object, &object_use.self_action, &format!("{}.self_action", field), errors);
+                object_use_index += 1;
+            }
+        }
+    }
+}
+
+// Makes sure every state that a prereq compares is one of the object's states or a declared global
+//    variable, compared to a value of the same type. Only integer states can be ordered.
+fn check_prereqs(game_data: &GameData, file: &YamlFile, object: &Object, prereqs: &[StatePrereq], field: &str,
+                 errors: &mut Vec<ConfigError>) {
+    for (i, prereq) in prereqs.iter().enumerate() {
+        check_prereq(game_data, file, Option::<&Object>::Some(object), prereq,
+                     &format!("{}[{}].{}", field, i, prereq.name), errors);
+    }
+}
+
+fn check_prereq(game_data: &GameData, file: &YamlFile, object: Option<&Object>, prereq: &StatePrereq, field: &str,
+                errors: &mut Vec<ConfigError>) {
+    let state = match find_state(game_data, file, object, &prereq.name, field, errors) {
+        Some(state) => { state }
+        None => { return; }
+    };
+    if !state.same_type(&prereq.value) {
+        errors.push(file.error(field, format!("{} has {} values, but is compared to the {} {}",
+                                              state_description(&prereq.name), state.type_name(),
+                                              prereq.value.type_name(), prereq.value)));
+    } else if prereq.comparison.is_ordering() && !matches!(state, StateValue::Int(_)) {
+        errors.push(file.error(field, format!("{} has {} values, so it can only be compared with == or !=",
+                                              state_description(&prereq.name), state.type_name())));
+    }
+}
+
+// Makes sure every state that an action changes is one of the object's states or a declared global
+//    variable, and is given a value of the same type. Only integer states can be added to or
+//    subtracted from.
+fn check_actions(game_data: &GameData, file: &YamlFile, object: &Object, actions: &[StateAction], field: &str,
+                 errors: &mut Vec<ConfigError>) {
+    for (i, action) in actions.iter().enumerate() {
+        check_action(game_data, file, Option::<&Object>::Some(object), action,
+                     &format!("{}[{}].{}", field, i, action.name), errors);
+    }
+}
+
+fn check_action(game_data: &GameData, file: &YamlFile, object: Option<&Object>, action: &StateAction, field: &str,
+                errors: &mut Vec<ConfigError>) {
+    let state = match find_state(game_data, file, object, &action.name, field, errors) {
+        Some(state) => { state }
+        None => { return; }
+    };
+    if action.operation != StateOperation::Set && !matches!(state, StateValue::Int(_)) {
+        errors.push(file.error(field, format!("{} has {} values, so it can't be added to or subtracted from",
+                                              state_description(&action.name), state.type_name())));
+    } else if !state.same_type(&action.value) {
+        errors.push(file.error(field, format!("{} has {} values, but is set to the {} {}",
+                                              state_description(&action.name), state.type_name(),
+                                              action.value.type_name(), action.value)));
+    }
+}
+
+// How a state or global variable is named in problems found with it
+fn state_description(name: &str) -> String {
+    match global_variable(name) {
+        Some(variable) => { format!("The global variable '{}'", variable) }
+        None => { format!("The state '{}'", name) }
+    }
+}
+
+// Finds the default value of one of the object's states, or of a global variable declared in
+//    game.yaml, reporting it if there is no such state or variable
+fn find_state<'a>(game_data: &'a GameData, file: &YamlFile, object: Option<&'a Object>, name: &str, field: &str,
+                  errors: &mut Vec<ConfigError>) -> Option<&'a StateValue> {
+    if let Some(variable) = global_variable(name) {
+        let state = game_data.info.variables.get(variable);
+        if state.is_none() {
+            errors.push(file.error(field, format!("No global variable named '{}' is declared in game.yaml", variable)));
+        }
+        return state;
+    }
+    let object = object?;
+    let state = object.state_value(name);
+    if state.is_none() {
+        errors.push(file.error(field, format!("The object '{}' has no state named '{}'", object.id, name)));
+    }
+    state
+}
+
+// Checks the dialogs and objects that a character refers to, including what it leaves behind when defeated
+fn check_character(game_data: &GameData, sources: &ConfigSources, character: &Character, errors: &mut Vec<ConfigError>) {
+    let file = match sources.characters.get(&character.id) {
+        Some(file) => { file }
+        None => { return; }
+    };
+
+    if !character.dialog_id.is_empty() && !game_data.dialogs.contains_key(&character.dialog_id) {
+        errors.push(file.error("dialog_id", format!("No dialog with the id '{}' exists", character.dialog_id)));
+    }
+    for (i, object_use) in character.interactions.object_use.iter().enumerate() {
+        let field = format!("interactions.object_use[{}]", i);
+        if !sources.objects.contains_key(&object_use.object_id) {
+            errors.push(file.error(&format!("{}.object_id", field),
+                                   format!("No object with the id '{}' exists", object_use.object_id)));
+        }
+        if !object_use.set_dialog.is_empty() && !game_data.dialogs.contains_key(&object_use.set_dialog) {
+            errors.push(file.error(&format!("{}.set_dialog", field),
+                                   format!("No dialog with the id '{}' exists", object_use.set_dialog)));
+        }
+    }
+    for (i, object_id) in character.on_defeat.loot.iter().enumerate() {
+        if !sources.objects.contains_key(object_id) {
+            errors.push(file.error(&format!("on_defeat.loot[{}]", i),
+                                   format!("No object with the id '{}' exists", object_id)));
+        }
+    }
+    if !character.on_defeat.set_dialog.is_empty() && !game_data.dialogs.contains_key(&character.on_defeat.set_dialog) {
+        errors.push(file.error("on_defeat.set_dialog",
+                               format!("No dialog with the id '{}' exists", character.on_defeat.set_dialog)));
+    }
+}
+
+// Makes sure a patrolling character's waypoints are spots it can walk to on the map it is placed in
+fn check_waypoints(game_data: &GameData, sources: &ConfigSources, m: usize, character: &Character,
+                   errors: &mut Vec<ConfigError>) {
+    let waypoints = match &character.behavior {
+        Behavior::Patrol { waypoints } => { waypoints }
+        _ => { return; }
+    };
+    let file = match sources.characters.get(&character.id) {
+        Some(file) => { file }
+        None => { return; }
+    };
+    let map = &game_data.maps[m];
+    for (i, waypoint) in waypoints.iter().enumerate() {
+        let field = format!("behavior.patrol.waypoints[{}]", i);
+        if waypoint.x >= map.grid.len() || waypoint.y >= map.grid[0].len() {
+            errors.push(file.error(&field, format!("The waypoint ({}, {}) is outside of map '{}'",
+                                                   waypoint.x, waypoint.y, map.info.id)));
+        } else if !map.tiles[waypoint.x][waypoint.y].passable {
+            errors.push(file.error(&field, format!("The waypoint ({}, {}) in map '{}' is on a tile that can't be walked on",
+                                                   waypoint.x, waypoint.y, map.info.id)));
+        }
+    }
+}
+
+// Makes sure that every object that can be equipped goes in one of the player's equipment slots, and
+//    only changes attributes the player has
+fn check_equipment(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    let player = match &game_data.info.player {
+        Some(player) => { player }
+        None => { return; }
+    };
+    for object in game_data.objects.values() {
+        let (file, equipment) = match (sources.objects.get(&object.id), &object.equip) {
+            (Some(file), Some(equipment)) => { (file, equipment) }
+            _ => { continue; }
+        };
+        if !player.equipment_slots.contains(&equipment.slot) {
+            errors.push(file.error("equip.slot", format!("The player has no equipment slot named '{}'", equipment.slot)));
+        }
+        for (i, modifier) in equipment.modifiers.iter().enumerate() {
+            check_player_attribute(game_data, file, &format!("equip.modifiers[{}].attribute_id", i),
+                                   &modifier.attribute_id, errors);
+        }
+    }
+}
+
+// Makes sure the effects of every consumable object change attributes that someone it could be used
+//    on has, either the player or one of the characters placed on a map
+fn check_consumables(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    let mut characters: Vec<&Character> = game_data.info.player.iter().collect();
+    for map in &game_data.maps {
+        for space in map.grid.iter().flatten() {
+            if let Some(MapData::Character(character)) = space {
+                characters.push(character);
+            }
+        }
+    }
+    for object in game_data.objects.values() {
+        let file = match sources.objects.get(&object.id) {
+            Some(file) => { file }
+            None => { continue; }
+        };
+        for (i, effect) in object.consume.iter().enumerate() {
+            if characters.iter().all(|character| character.get_attribute(&effect.attribute_id).is_none()) {
+                errors.push(file.error(&format!("consume[{}].attribute_id", i), format!(
+                    "Neither the player nor any character has an attribute with the id '{}'", effect.attribute_id)));
+            }
+        }
+    }
+}
+
+// Makes sure every dialog option leads to another dialog or uses one of the dialog keywords
+fn check_dialogs(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    for dialog in game_data.dialogs.values() {
+        let (file, index) = match sources.dialogs.get(&dialog.id) {
+            Some((file, index)) => { (file, *index) }
+            None => { continue; }
+        };
+        for (i, option) in dialog.options.iter().enumerate() {
+            let field = format!("[{}].{}", index, dialog.option_field(i));
+            if !DIALOG_KEYWORDS.contains(&option.next.as_str()) && !game_data.dialogs.contains_key(&option.next) {
+                errors.push(file.error(&format!("{}.next", field), format!(
+                    "'{}' is not a dialog id or one of the keywords {}", option.next, DIALOG_KEYWORDS.join(", "))));
+            }
+            for (j, condition) in option.conditions.iter().enumerate() {
+                check_dialog_condition(game_data, file, &format!("{}.conditions[{}]", field, j), condition, errors);
+            }
+            for (j, effect) in option.effects.iter().enumerate() {
+                check_dialog_effect(game_data, file, &format!("{}.effects[{}]", field, j), effect, errors);
+            }
+        }
+    }
+}
+
+// Checks that the items, attributes, and object states a dialog option's condition looks at exist,
+//    and are compared to values of the right type
+fn check_dialog_condition(game_data: &GameData, file: &YamlFile, field: &str, condition: &DialogCondition,
+                          errors: &mut Vec<ConfigError>) {
+    match condition {
+        DialogCondition::HasItem(object_id) => {
+            check_item(game_data, file, &format!("{}.has_item", field), object_id, errors);
+        }
+        DialogCondition::LacksItem(object_id) => {
+            check_item(game_data, file, &format!("{}.lacks_item", field), object_id, errors);
+        }
+        DialogCondition::Flag(flag) => {
+            check_flag(game_data, file, &format!("{}.flag", field), flag, errors);
+        }
+        DialogCondition::NotFlag(flag) => {
+            check_flag(game_data, file, &format!("{}.not_flag", field), flag, errors);
+        }
+        DialogCondition::Variable(variable) => {
+            let prereq = StatePrereq {
+                name: format!("{}{}", GLOBAL_PREFIX, variable.id),
+                comparison: variable.comparison,
+                value: variable.value.clone(),
+            };
+            check_prereq(game_data, file, Option::<&Object>::None, &prereq, &format!("{}.variable", field), errors);
+        }
+        DialogCondition::SeenDialog(dialog_id) => {
+            if !game_data.dialogs.contains_key(dialog_id) {
+                errors.push(file.error(&format!("{}.seen_dialog", field),
+                                       format!("No dialog with the id '{}' exists", dialog_id)));
+            }
+        }
+        DialogCondition::Attribute(attribute) => {
+            let field = format!("{}.attribute", field);
+            check_player_attribute(game_data, file, &format!("{}.id", field), &attribute.id, errors);
+            if !matches!(attribute.value, StateValue::Int(_)) {
+                errors.push(file.error(&format!("{}.value", field), format!(
+                    "Attributes have integer values, but '{}' is compared to the {} {}", attribute.id,
+                    attribute.value.type_name(), attribute.value)));
+            }
+        }
+        DialogCondition::State(condition) => {
+            let field = format!("{}.state", field);
+            let prereq = StatePrereq {
+                name: condition.state.clone(),
+                comparison: condition.comparison,
+                value: condition.value.clone(),
+            };
+            if let Some(object) = find_instance_object(game_data, file, &format!("{}.object", field), &condition.object, errors) {
+                check_prereq(game_data, file, Option::<&Object>::Some(object), &prereq, &field, errors);
+            }
+        }
+    }
+}
+
+// Checks that whatever a dialog option's effect changes exists, and that object states are set to
+//    values of the right type
+fn check_dialog_effect(game_data: &GameData, file: &YamlFile, field: &str, effect: &DialogEffect,
+                       errors: &mut Vec<ConfigError>) {
+    match effect {
+        DialogEffect::GiveItem(object_id) => {
+            check_item(game_data, file, &format!("{}.give_item", field), object_id, errors);
+        }
+        DialogEffect::TakeItem(object_id) => {
+            check_item(game_data, file, &format!("{}.take_item", field), object_id, errors);
+        }
+        DialogEffect::SetFlag(flag) => {
+            check_flag(game_data, file, &format!("{}.set_flag", field), flag, errors);
+        }
+        DialogEffect::ClearFlag(flag) => {
+            check_flag(game_data, file, &format!("{}.clear_flag", field), flag, errors);
+        }
+        DialogEffect::Fight => {}
+        DialogEffect::SetVariable(change) => {
+            let mut action = change.action();
+            action.name = format!("{}{}", GLOBAL_PREFIX, change.id);
+            check_action(game_data, file, Option::<&Object>::None, &action, &format!("{}.set_variable", field), errors);
+        }
+        DialogEffect::SetState { object, state, value } => {
+            let field = format!("{}.set_state", field);
+            let action = StateAction { name: state.clone(), operation: StateOperation::Set, value: value.clone() };
+            if let Some(object) = find_instance_object(game_data, file, &format!("{}.object", field), object, errors) {
+                check_action(game_data, file, Option::<&Object>::Some(object), &action, &field, errors);
+            }
+        }
+        DialogEffect::ChangeAttribute { id, .. } => {
+            check_player_attribute(game_data, file, &format!("{}.change_attribute.id", field), id, errors);
+        }
+        DialogEffect::SetDialog(dialog_id) => {
+            if !game_data.dialogs.contains_key(dialog_id) {
+                errors.push(file.error(&format!("{}.set_dialog", field),
+                                       format!("No dialog with the id '{}' exists", dialog_id)));
+            }
+        }
+        DialogEffect::Travel { map, spawn } => {
+            let field = format!("{}.travel", field);
+            match game_data.maps.iter().find(|m| &m.info.id == map) {
+                Some(found) if !found.spawns.contains_key(spawn) => {
+                    errors.push(file.error(&format!("{}.spawn", field),
+                                           format!("The map '{}' has no spawn with the id '{}'", map, spawn)));
+                }
+                Some(_) => {}
+                None => {
+                    errors.push(file.error(&format!("{}.map", field), format!("No map with the id '{}' exists", map)));
+                }
+            }
+        }
+    }
+}
+
+// Checks the conditions that start and finish each quest, and its rewards. Rewards can use the
+//    effects of dialog options, except for the ones that need an NPC being talked to.
+fn check_quests(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    for quest in game_data.quests.values() {
+        let file = match sources.quests.get(&quest.id) {
+            Some(file) => { file }
+            None => { continue; }
+        };
+        for (i, condition) in quest.start.iter().enumerate() {
+            check_dialog_condition(game_data, file, &format!("start[{}]", i), condition, errors);
+        }
+        for (i, stage) in quest.stages.iter().enumerate() {
+            for (j, condition) in stage.conditions.iter().enumerate() {
+                check_dialog_condition(game_data, file, &format!("stages[{}].conditions[{}]", i, j), condition, errors);
+            }
+        }
+        for (i, effect) in quest.rewards.iter().enumerate() {
+            let field = format!("rewards[{}]", i);
+            let npc_effect = match effect {
+                DialogEffect::SetDialog(_) => { Some("set_dialog") }
+                DialogEffect::Fight => { Some("fight") }
+                _ => { None }
+            };
+            match npc_effect {
+                Some(name) => {
+                    errors.push(file.error(&field, format!(
+                        "Only dialogs can use the {} effect, since it needs an NPC being talked to", name)));
+                }
+                None => { check_dialog_effect(game_data, file, &field, effect, errors); }
+            }
+        }
+    }
+}
+
+// Flags are global variables, so they must be declared in game.yaml as booleans
+fn check_flag(game_data: &GameData, file: &YamlFile, field: &str, flag: &str, errors: &mut Vec<ConfigError>) {
+    match game_data.info.variables.get(flag) {
+        Some(StateValue::Bool(_)) => {}
+        Some(value) => {
+            errors.push(file.error(field, format!("The global variable '{}' has {} values, so it can't be used as a flag",
+                                                  flag, value.type_name())));
+        }
+        None => {
+            errors.push(file.error(field, format!("No global variable named '{}' is declared in game.yaml", flag)));
+        }
+    }
+}
+
+fn check_item(game_data: &GameData, file: &YamlFile, field: &str, object_id: &str, errors: &mut Vec<ConfigError>) {
+    if !game_data.objects.contains_key(object_id) {
+        errors.push(file.error(field, format!("No object with the id '{}' exists", object_id)));
+    }
+}
+
+fn check_player_attribute(game_data: &GameData, file: &YamlFile, field: &str, attribute_id: &str,
+                          errors: &mut Vec<ConfigError>) {
+    if let Some(player) = &game_data.info.player {
+        if player.get_attribute(attribute_id).is_none() {
+            errors.push(file.error(field, format!("The player has no attribute with the id '{}'", attribute_id)));
+        }
+    }
+}
+
+// Finds the object placed in a map with the instance id, reporting it if there is none
+fn find_instance_object<'a>(game_data: &'a GameData, file: &YamlFile, field: &str, instance_id: &str,
+                            errors: &mut Vec<ConfigError>) -> Option<&'a Object> {
+    match game_data.find_instance(instance_id) {
+        Some((m, x, y)) => {
+            match &game_data.maps[m].grid[x][y] {
+                Some(MapData::Object(object)) => { Some(object) }
+                _ => { None }
+            }
+        }
+        None => {
+            errors.push(file.error(field, format!("No object placed in a map has the instance id '{}'", instance_id)));
+            None
+        }
+    }
+}
+
+// Warns about any maps that can't be reached from the starting map by going through doors
+fn check_reachable_maps(game_data: &GameData, sources: &ConfigSources, errors: &mut Vec<ConfigError>) {
+    let start = match game_data.maps.iter().position(|m| m.info.id == game_data.info.starting_map) {
+        Some(start) => { start }
+        None => { return; }
+    };
+
+    let mut reached = vec![false; game_data.maps.len()];
+    reached[start] = true;
+    let mut to_visit = VecDeque::from([start]);
+    while let Some(m) = to_visit.pop_front() {
+        for (x, column) in game_data.maps[m].grid.iter().enumerate() {
+            for (y, space) in column.iter().enumerate() {
+                if let Some(MapData::Object(object)) = space {
+                    if !object.travels() {
+                        continue;
+                    }
+                    if let Some((other_map, _, _)) = game_data.door_destination(m, x, y) {
+                        if !reached[other_map] {
+                            reached[other_map] = true;
+                            to_visit.push_back(other_map);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for (m, map) in game_data.maps.iter().enumerate() {
+        if reached[m] {
+            continue;
+        }
+        if let Some(file) = sources.maps.get(&map.info.id) {
+            errors.push(file.error("id", format!(
+                "The map '{}' can't be reached from the starting map '{}'", map.info.id,
+                game_data.info.starting_map)).warning());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::game::config_parsers::GameData;
+    use crate::game::config_parsers::errors::{ConfigError, Severity};
+    use crate::game::config_parsers::files::MemoryFiles;
+
+    // Starts the game in the given map and position
+    fn game_yaml(starting_map: &str, x: u16, y: u16) -> String {
+        format!("name: \"Small Game\"\ndescription: \"\"\nauthor: \"\"\nmin_screen_size: {{ width: 80, height: 20 }}\n\
+                 starting_map: \"{}\"\nstarting_position: {{ x: {}, y: {} }}\n", starting_map, x, y)
+    }
+
+    // A game with two maps joined by a door, a rock, and a guide to talk to. The files given are added
+    //    to it, or take the place of the files with the same paths.
+    fn load_small_game(files: &[(&str, &str)]) -> Vec<ConfigError> {
+        let mut game = MemoryFiles::new(Path::new("small_game"));
+        game.add_file("game.yaml", game_yaml("field", 1, 1));
+        game.add_file("characters/player.yaml", r##"
+id: "player"
+name: "You"
+icon: "*"
+inventory_size: { width: 1, height: 1 }
+traits: []
+interactions: { attacks: [], object_use: [] }
+dialog_id: ""
+"##);
+        game.add_file("characters/guide.yaml", r##"
+id: "guide"
+name: "Guide"
+icon: "G"
+inventory_size: { width: 0, height: 0 }
+traits: []
+interactions: { attacks: [], object_use: [] }
+dialog_id: "hello"
+"##);
+        game.add_file("dialogs/guide.yaml", r##"
+- id: "hello"
+  npc_dialog: "Hello!"
+  options:
+    - dialog: "Bye."
+      next: "exit"
+"##);
+        game.add_file("objects/gate.yaml", r##"
+id: "gate"
+name: "Gate"
+category: "door"
+icon: "#"
+interactions:
+  activate:
+    - category: "travel"
+      destination: "forest"
+"##);
+        game.add_file("objects/rock.yaml", r##"
+id: "rock"
+name: "Rock"
+category: "collidable"
+icon: "o"
+"##);
+        game.add_file("maps/field.yaml", r##"
+id: "field"
+description: "A field."
+size: { width: 6, height: 4 }
+objects:
+  - { id: "gate", position: { x: 5, y: 1 } }
+  - { id: "rock", position: { x: 2, y: 2 } }
+  - { id: "guide", position: { x: 2, y: 0 } }
+"##);
+        game.add_file("maps/forest.yaml", r##"
+id: "forest"
+description: "A forest."
+size: { width: 3, height: 3 }
+objects:
+  - { id: "gate", position: { x: 0, y: 1 } }
+"##);
+        for (path, contents) in files {
+            game.add_file(path, *contents);
+        }
+        GameData::load_configs(&game).1
+    }
+
+    fn describe(problems: &[ConfigError]) -> Vec<String> {
+        problems.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn small_game_has_no_problems() {
+        let problems = load_small_game(&[]);
+        assert!(problems.is_empty(), "{:?}", describe(&problems));
+    }
+
+    #[test]
+    fn missing_ids_are_reported() {
+        let problems = load_small_game(&[("objects/gate.yaml", r##"
+id: "gate"
+name: "Gate"
+category: "door"
+icon: "#"
+interactions:
+  activate:
+    - category: "travel"
+      destination: "desert"
+  object_use:
+    - foreign_objects_id: "key"
+      self_action: []
+      consume_item: true
+"##), ("dialogs/guide.yaml", r##"
+- id: "hi"
+  npc_dialog: "Hello!"
+  options:
+    - dialog: "Tell me more."
+      next: "more"
+"##)]);
+        assert_eq!(describe(&problems), vec![
+            "small_game/characters/guide.yaml:8:1: 'dialog_id': No dialog with the id 'hello' exists",
+            "small_game/dialogs/guide.yaml:6:7: '[0].options[0].next': 'more' is not a dialog id or one of the keywords exit, inventory, fight",
+            "small_game/objects/gate.yaml:9:7: 'interactions.activate[0].destination': No map with the id 'desert' exists",
+            "small_game/objects/gate.yaml:11:7: 'interactions.object_use[0].foreign_objects_id': No object with the id 'key' exists",
+        ]);
+    }
+
+    #[test]
+    fn starting_position_must_be_open() {
+        let outside = load_small_game(&[("game.yaml", &game_yaml("field", 6, 1))]);
+        assert_eq!(describe(&outside), vec![
+            "small_game/game.yaml:6:1: 'starting_position': Starting position (6, 1) is outside of map 'field', which is 6x4",
+        ]);
+
+        let blocked = load_small_game(&[("game.yaml", &game_yaml("field", 2, 2))]);
+        assert_eq!(describe(&blocked), vec![
+            "small_game/game.yaml:6:1: 'starting_position': Starting position (2, 2) is blocked by the object 'rock'",
+        ]);
+
+        let walled = load_small_game(&[("game.yaml", &game_yaml("field", 0, 1))]);
+        assert_eq!(describe(&walled), vec![
+            "small_game/game.yaml:6:1: 'starting_position': Starting position (0, 1) is on a wall tile in map 'field' that can't be walked on",
+        ]);
+
+        let missing = load_small_game(&[("game.yaml", &game_yaml("meadow", 1, 1))]);
+        assert_eq!(describe(&missing), vec![
+            "small_game/game.yaml:5:1: 'starting_map': No map with the id 'meadow' exists",
+        ]);
+    }
+
+    #[test]
+    fn unreachable_maps_are_warned_about() {
+        let problems = load_small_game(&[("maps/cave.yaml", r##"
+description: "A cave that no door leads to."
+id: "cave"
+size: { width: 3, height: 3 }
+objects: []
+"##)]);
+        assert_eq!(problems.len(), 1, "{:?}", describe(&problems));
+        let warning = &problems[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.path, Path::new("small_game/maps/cave.yaml"));
+        assert_eq!((warning.line, warning.column), (Some(3), Some(1)));
+        assert_eq!(warning.field.as_deref(), Some("id"));
+        assert_eq!(warning.message, "The map 'cave' can't be reached from the starting map 'field'");
+    }
+
+    #[test]
+    fn global_variables_must_be_declared_and_used_by_type() {
+        let game_yaml = format!("{}variables:\n  bells_rung: 0\n  gate_open: false\n  weather: \"rain\"\n",
+                                game_yaml("field", 1, 1));
+        let problems = load_small_game(&[("game.yaml", &game_yaml), ("objects/rock.yaml", r##"
+id: "rock"
+name: "Rock"
+category: "collidable"
+icon: "o"
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - global.bells_rung: ">= 3"
+        - global.weather: "> sun"
+        - global.season: "winter"
+      self_action:
+        - global.gate_open: true
+        - global.weather: "+= 1"
+        - global.bells_rung: "sun"
+"##)]);
+        let rock = "small_game/objects/rock.yaml";
+        assert_eq!(describe(&problems), vec![
+            format!("{}:11:11: 'interactions.activate[0].prereqs[1].global.weather': The global variable 'weather' has \
+                     string values, so it can only be compared with == or !=", rock),
+            format!("{}:12:11: 'interactions.activate[0].prereqs[2].global.season': No global variable named 'season' \
+                     is declared in game.yaml", rock),
+            format!("{}:15:11: 'interactions.activate[0].self_action[1].global.weather': The global variable 'weather' \
+                     has string values, so it can't be added to or subtracted from", rock),
+            format!("{}:16:11: 'interactions.activate[0].self_action[2].global.bells_rung': The global variable \
+                     'bells_rung' has integer values, but is set to the string 'sun'", rock),
+        ]);
+    }
+
+    #[test]
+    fn flags_must_be_declared_boolean_variables() {
+        let game_yaml = format!("{}variables:\n  asked: false\n  bells_rung: 0\n", game_yaml("field", 1, 1));
+        let problems = load_small_game(&[("game.yaml", &game_yaml), ("dialogs/guide.yaml", r##"
+- id: "hello"
+  npc_dialog: "Hello!"
+  options:
+    - dialog: "Can I ask something?"
+      conditions:
+        - not_flag: "asked"
+        - flag: "bells_rung"
+      effects:
+        - set_flag: "asked"
+        - clear_flag: "waved"
+      next: "exit"
+"##)]);
+        let guide = "small_game/dialogs/guide.yaml";
+        assert_eq!(describe(&problems), vec![
+            format!("{}:8:11: '[0].options[0].conditions[1].flag': The global variable 'bells_rung' has integer \
+                     values, so it can't be used as a flag", guide),
+            format!("{}:11:11: '[0].options[0].effects[1].clear_flag': No global variable named 'waved' is declared \
+                     in game.yaml", guide),
+        ]);
+    }
+}
@@ -1,18 +1,148 @@
 use serde::Deserialize;
+use crate::game::objects::{Comparison, StateAction, StateOperation, StateValue};
 
 // Dialog data structure. Contains a single dialog object consisting of
-// some NPC dialog and two dialog options for the player.
+// some NPC dialog and a list of dialog options for the player.
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Dialog {
     pub id: String,
     pub npc_dialog: String,
-    pub option_0: DialogOption,
-    pub option_1: DialogOption,
+    #[serde(default)]
+    pub options: Vec<DialogOption>,
+    // Older dialogs give exactly two options this way instead. They are added to `options` when
+    //    the dialog is loaded.
+    #[serde(default)]
+    pub option_0: Option<DialogOption>,
+    #[serde(default)]
+    pub option_1: Option<DialogOption>,
+}
+
+impl Dialog {
+    // Returns true if the dialog gives its options as option_0 and option_1
+    pub fn has_legacy_options(&self) -> bool {
+        self.option_0.is_some() || self.option_1.is_some()
+    }
+
+    // The config field that the option at the given index came from
+    pub fn option_field(&self, index: usize) -> String {
+        if self.has_legacy_options() {
+            format!("option_{}", index)
+        } else {
+            format!("options[{}]", index)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DialogOption {
     pub dialog: String,
     pub next: String,
-}
\ No newline at end of file
+    // Everything that has to be true for the option to be chosen
+    #[serde(default)]
+    pub conditions: Vec<DialogCondition>,
+    #[serde(default)]
+    pub when_unmet: WhenUnmet,
+    // What happens when the option is chosen, before going on to `next`
+    #[serde(default)]
+    pub effects: Vec<DialogEffect>,
+}
+
+// How an option is shown while its conditions aren't met
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhenUnmet {
+    #[default]
+    Hide,
+    // Shown, but can't be chosen
+    Grey,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogCondition {
+    // The player has an object with this id in their inventory
+    HasItem(String),
+    // The player has no object with this id in their inventory
+    LacksItem(String),
+    // The global flag, a boolean global variable, is set
+    Flag(String),
+    // The global flag isn't set
+    NotFlag(String),
+    // A global variable compares to a value
+    Variable(ValueCondition),
+    // One of the player's attributes compares to a value
+    Attribute(ValueCondition),
+    // A state of an object placed in a map compares to a value
+    State(StateCondition),
+    // The player has talked to an NPC long enough to be shown the dialog with this id
+    SeenDialog(String),
+}
+
+// Values can start with a comparison like `">= 3"`, the same way as object prereqs. It is split off
+//    into `comparison` when the dialog is loaded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValueCondition {
+    pub id: String,
+    #[serde(skip)]
+    pub comparison: Comparison,
+    pub value: StateValue,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateCondition {
+    // The instance id of the object
+    pub object: String,
+    pub state: String,
+    #[serde(skip)]
+    pub comparison: Comparison,
+    pub value: StateValue,
+}
+
+// Values can start with "+=" or "-=", which is split off into `operation` when the dialog is loaded
+#[derive(Debug, Clone, Deserialize)]
+pub struct VariableChange {
+    pub id: String,
+    #[serde(skip)]
+    pub operation: StateOperation,
+    pub value: StateValue,
+}
+
+impl VariableChange {
+    // The change as an action on the variable
+    pub fn action(&self) -> StateAction {
+        StateAction { name: self.id.clone(), operation: self.operation, value: self.value.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogEffect {
+    // Puts a new object with this id in the player's inventory
+    GiveItem(String),
+    // Removes an object with this id from the player's inventory
+    TakeItem(String),
+    SetFlag(String),
+    ClearFlag(String),
+    // Changes a global variable. Values can start with "+=" or "-=" to add to or subtract from an
+    //    integer variable, the same way as object actions.
+    SetVariable(VariableChange),
+    // Sets a state of an object placed in a map, found by its instance id
+    SetState { object: String, state: String, value: StateValue },
+    // Adds to one of the player's attributes, or subtracts when negative, keeping it within its
+    //    minimum and maximum
+    ChangeAttribute { id: String, amount: i64 },
+    // Changes which dialog the NPC being talked to starts with from now on
+    SetDialog(String),
+    // Ends the conversation with a fight
+    Fight,
+    // Ends the conversation by moving the player to a spawn
+    Travel { map: String, spawn: String },
+}
+
+impl DialogEffect {
+    // Returns true for effects that end the conversation, so the option's `next` isn't followed
+    pub fn ends_conversation(&self) -> bool {
+        matches!(self, DialogEffect::Fight | DialogEffect::Travel { .. })
+    }
+}
@@ -0,0 +1,1378 @@
+use std::collections::{HashSet, VecDeque};
+use crate::game::{GameState, saves};
+use crate::game::characters::{Buff, Character};
+use crate::game::characters::behavior::{Behavior, OnContact};
+use crate::game::config_parsers::GameData;
+use crate::game::dialogs::{DialogCondition, DialogEffect, DialogOption, WhenUnmet};
+use crate::game::maps::{Map, MapData};
+use crate::game::objects::{EffectDuration, LEVER_STATE, ObjectCategory, ObjectInteraction, Object, StateAction, StateValue,
+                           default_instance_id, global_variable};
+use crate::game::quests::{Quest, QuestProgress};
+use crate::game::VisualState;
+
+// The rules of the game. Every change to the game made by the player happens through one of these
+// functions, which only work on the GameData and GameState, so that the game can be played by
+// anything that can call them: the terminal Screen, tests, or other front-ends.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    // How far one step in this direction moves the player, as (x, y)
+    fn delta(&self) -> (i16, i16) {
+        match self {
+            Direction::Up => { (0, -1) }
+            Direction::Down => { (0, 1) }
+            Direction::Left => { (-1, 0) }
+            Direction::Right => { (1, 0) }
+        }
+    }
+}
+
+// Moves the game forward by one tick
+pub fn tick(game_data: &mut GameData, game_state: &mut GameState) {
+    game_state.cursor_blink = !game_state.cursor_blink;
+    game_state.tick_count += 1;
+
+    // A fight ends if there is no longer anyone there to fight
+    if let VisualState::PlayingCharacterFight = game_state.visual_state {
+        let map = &game_data.maps[game_state.current_map];
+        let space = &map.grid[game_state.fight_x][game_state.fight_y];
+        if !matches!(space, Some(MapData::Character(_))) {
+            game_state.visual_state = VisualState::PlayingMap;
+        }
+    }
+
+    // Characters only move around while the player is walking around the map, so nothing changes
+    //    behind a conversation, a fight, or a menu
+    if let VisualState::PlayingMap = game_state.visual_state {
+        move_characters(game_data, game_state);
+        count_down_tick_buffs(game_data, game_state);
+    }
+}
+
+// Counts down the buffs that last a number of ticks, for the player and everyone on the current map
+fn count_down_tick_buffs(game_data: &mut GameData, game_state: &GameState) {
+    if let Some(player) = &mut game_data.info.player {
+        player.count_down_buffs(false);
+    }
+    for space in game_data.maps[game_state.current_map].grid.iter_mut().flatten() {
+        if let Some(MapData::Character(character)) = space {
+            character.count_down_buffs(false);
+        }
+    }
+}
+
+// Leaves the start screen and starts playing
+pub fn start_game(game_state: &mut GameState) {
+    game_state.visual_state = VisualState::PlayingMap;
+}
+
+// Goes back to the start screen
+pub fn show_start_screen(game_state: &mut GameState) {
+    game_state.visual_state = VisualState::StartScreen;
+}
+
+// Opens the player's inventory
+pub fn open_inventory(game_state: &mut GameState) {
+    game_state.visual_state = VisualState::PlayingInventory;
+}
+
+// Opens the quest log, starting from the first quest in it
+pub fn open_quest_log(game_state: &mut GameState) {
+    game_state.quest_selected = 0;
+    game_state.visual_state = VisualState::PlayingQuestLog;
+}
+
+// Leaves the current screen and goes back to the map
+pub fn return_to_map(game_state: &mut GameState) {
+    game_state.visual_state = VisualState::PlayingMap;
+}
+
+// Moves the player one space in the given direction, if nothing is in the way. Walking onto a
+//    hostile character starts a fight with it. Returns true if the player moved.
+pub fn move_player(game_data: &GameData, game_state: &mut GameState, direction: Direction) -> bool {
+    let (delta_x, delta_y) = direction.delta();
+    if !check_move_available(game_data, game_state, delta_x, delta_y) {
+        return false;
+    }
+    game_state.current_player_x = (game_state.current_player_x as i16 + delta_x) as u16;
+    game_state.current_player_y = (game_state.current_player_y as i16 + delta_y) as u16;
+    // Show the player right away after moving
+    game_state.cursor_blink = true;
+
+    let x = game_state.current_player_x as usize;
+    let y = game_state.current_player_y as usize;
+    if let Some(MapData::Character(character)) = &game_data.maps[game_state.current_map].grid[x][y] {
+        if character.hostility().is_some_and(|hostility| hostility.on_contact == OnContact::Fight) {
+            start_fight(game_state, x, y);
+        }
+    }
+    true
+}
+
+// Returns true if there is space on the map for the player to move delta_x spaces right and delta_y spaces down.
+// Returns false if there is an object or a tile like a wall in the way, or the move is out of map bounds.
+fn check_move_available(game_data: &GameData, game_state: &GameState, delta_x: i16, delta_y: i16) -> bool {
+    let target_x = game_state.current_player_x as i16 + delta_x;
+    let target_y = game_state.current_player_y as i16 + delta_y;
+    space_open(&game_data.maps[game_state.current_map], target_x, target_y)
+}
+
+// Returns true if (x, y) is inside the map and is somewhere that can be walked on
+fn space_open(map: &Map, x: i16, y: i16) -> bool {
+    // Check to make sure they can't leave the map
+    if x < 0 || x >= map.grid.len() as i16 ||
+       y < 0 || y >= map.grid[0].len() as i16 {
+        return false;
+    }
+
+    // Walls, water, and anything else that can't be walked on block the way, except for doors
+    let tile = &map.tiles[x as usize][y as usize];
+    match &map.grid[x as usize][y as usize] {
+        // Whether objects can be walked over depends on their category
+        Some(MapData::Object(object)) => { object.category.passable(tile.passable) }
+        // Characters can be walked over (for interacting), as long as they are somewhere the
+        //    player could walk anyway and aren't hostile characters standing in the way
+        Some(MapData::Character(character)) => {
+            tile.passable && !character.hostility().is_some_and(|hostility| hostility.on_contact == OnContact::Block)
+        }
+        // Empty space, they can go there if the tile allows it
+        None => { tile.passable }
+    }
+}
+
+// Lets every character on the current map that is due a step take one, following its behavior.
+//    Hostile characters go after the player instead once the player comes close enough, and those
+//    that fight attack as soon as they are next to the player. The character the player is
+//    standing on stays put so that it can still be talked to.
+fn move_characters(game_data: &mut GameData, game_state: &mut GameState) {
+    let player = (game_state.current_player_x as i16, game_state.current_player_y as i16);
+    let map = &mut game_data.maps[game_state.current_map];
+
+    // Find everyone who moves before moving anyone, so that no one gets two steps by walking into
+    //    a space that hasn't been looked at yet
+    let mut movers = Vec::<(usize, usize)>::new();
+    for (x, column) in map.grid.iter().enumerate() {
+        for (y, space) in column.iter().enumerate() {
+            if let Some(MapData::Character(character)) = space {
+                let moves = character.behavior != Behavior::Stationary || character.hostility().is_some();
+                if moves && (x as i16, y as i16) != player &&
+                   (game_state.tick_count.is_multiple_of(character.move_every) ||
+                    ready_to_fight(character, (x as i16, y as i16), player)) {
+                    movers.push((x, y));
+                }
+            }
+        }
+    }
+
+    for (x, y) in movers {
+        let mut character = match map.grid[x][y].take() {
+            Some(MapData::Character(character)) => { character }
+            space => {
+                map.grid[x][y] = space;
+                continue;
+            }
+        };
+        if ready_to_fight(&character, (x as i16, y as i16), player) {
+            map.grid[x][y] = Option::<MapData>::Some(MapData::Character(character));
+            start_fight(game_state, x, y);
+            return;
+        }
+        let (mut to_x, mut to_y) = (x, y);
+        for direction in character_steps(game_state, &mut character, (x as i16, y as i16), player) {
+            let (delta_x, delta_y) = direction.delta();
+            let (step_x, step_y) = (x as i16 + delta_x, y as i16 + delta_y);
+            // Characters walk where the player could, but only into empty spaces, and never onto the player
+            if space_open(map, step_x, step_y) && map.grid[step_x as usize][step_y as usize].is_none() &&
+               (step_x, step_y) != player {
+                (to_x, to_y) = (step_x as usize, step_y as usize);
+                break;
+            }
+        }
+        map.grid[to_x][to_y] = Option::<MapData>::Some(MapData::Character(character));
+    }
+}
+
+// Returns true if the character at `position` is hostile, fights on contact, and is right next to
+//    the player, close enough to notice them
+fn ready_to_fight(character: &Character, position: (i16, i16), player: (i16, i16)) -> bool {
+    character.hostility().is_some_and(|hostility| {
+        hostility.on_contact == OnContact::Fight && hostility.aggro_radius >= 1 && distance(position, player) == 1
+    })
+}
+
+// How many steps apart two spaces are, counting across and down
+fn distance(from: (i16, i16), to: (i16, i16)) -> i16 {
+    (to.0 - from.0).abs() + (to.1 - from.1).abs()
+}
+
+// The directions a character at `position` would like to step in this tick, best first. The first
+//    one that isn't blocked is taken. Patrolling characters move on to their next waypoint here
+//    once they have reached the current one.
+fn character_steps(game_state: &mut GameState, character: &mut Character, position: (i16, i16),
+                   player: (i16, i16)) -> Vec<Direction> {
+    // Hostile characters stop whatever they were doing to chase the player, stopping next to them
+    if let Some(hostility) = character.hostility() {
+        let distance = distance(position, player);
+        if distance <= hostility.aggro_radius as i16 {
+            return if distance > 1 { steps_towards(position, player) } else { vec![] };
+        }
+    }
+    match &character.behavior {
+        Behavior::Stationary => { vec![] }
+        Behavior::Wander { radius } => {
+            let directions = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+            let direction = directions[(next_random(game_state) % 4) as usize];
+            let (delta_x, delta_y) = direction.delta();
+            let (home_x, home_y) = (character.home.0 as i16, character.home.1 as i16);
+            if (position.0 + delta_x - home_x).abs() <= *radius as i16 &&
+               (position.1 + delta_y - home_y).abs() <= *radius as i16 {
+                vec![direction]
+            } else {
+                vec![]
+            }
+        }
+        Behavior::Patrol { waypoints } => {
+            let waypoint = &waypoints[character.waypoint % waypoints.len()];
+            if (waypoint.x as i16, waypoint.y as i16) == position {
+                character.waypoint = (character.waypoint + 1) % waypoints.len();
+            }
+            let waypoint = &waypoints[character.waypoint % waypoints.len()];
+            steps_towards(position, (waypoint.x as i16, waypoint.y as i16))
+        }
+        Behavior::Follow { range } => {
+            let distance = distance(position, player);
+            if distance > 1 && distance <= *range as i16 {
+                steps_towards(position, player)
+            } else {
+                vec![]
+            }
+        }
+    }
+}
+
+// The directions that get closer to the target, with the direction that has the furthest to go first
+fn steps_towards(from: (i16, i16), to: (i16, i16)) -> Vec<Direction> {
+    let (delta_x, delta_y) = (to.0 - from.0, to.1 - from.1);
+    let mut steps = Vec::<(i16, Direction)>::new();
+    if delta_x != 0 {
+        steps.push((delta_x.abs(), if delta_x > 0 { Direction::Right } else { Direction::Left }));
+    }
+    if delta_y != 0 {
+        steps.push((delta_y.abs(), if delta_y > 0 { Direction::Down } else { Direction::Up }));
+    }
+    steps.sort_by_key(|(distance, _)| std::cmp::Reverse(*distance));
+    steps.into_iter().map(|(_, direction)| direction).collect()
+}
+
+// The next number from the game's random number generator, a simple xorshift that is good enough
+//    for characters wandering around
+fn next_random(game_state: &mut GameState) -> u64 {
+    let mut x = game_state.rng_state;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    game_state.rng_state = x;
+    x
+}
+
+// Changes to the next map, wrapping around to the first map after the last one
+pub fn next_map(game_data: &GameData, game_state: &mut GameState) {
+    if game_state.current_map + 1 <  game_data.maps.len() {
+        game_state.current_map += 1;
+    } else {
+        game_state.current_map = 0;
+    }
+}
+
+// Asks the player whether they want to exit the game
+pub fn request_exit(game_state: &mut GameState) {
+    game_state.dialog_message = "Would you like to exit the game?".to_string();
+    game_state.dialog_options = vec![("No".to_string(), game_state.visual_state.clone()),
+                                     ("Yes".to_string(), game_state.visual_state.clone())];
+    game_state.dialog_return_cancel = game_state.visual_state.clone();
+    game_state.visual_state = VisualState::PlayingDialog;
+    game_state.pre_exit = true;
+}
+
+// Interacts with whatever is in the player's spot on the map (when the "interact" key is pressed)
+pub fn interact(game_data: &mut GameData, game_state: &mut GameState) {
+    let map = &game_data.maps[game_state.current_map].clone();
+    let player_x = game_state.current_player_x as usize;
+    let player_y = game_state.current_player_y as usize;
+    if map.grid[player_x][player_y].is_some() {
+        match map.grid[player_x][player_y].as_ref().unwrap()  {
+            MapData::Character(character) => {
+                character_interact(game_state, character);
+            }
+            MapData::Object(object) => {
+                activate_object(game_data, game_state, object);
+            }
+        }
+        update_quests(game_data, game_state);
+    }
+}
+
+// Starts any interaction that happens when an npc conversation is started
+//    with the interact key
+fn character_interact(game_state: &mut GameState, character: &Character) {
+    game_state.visual_state = VisualState::PlayingCharacterInteraction;
+    game_state.npc_dialog_id = character.dialog_id.clone();
+    game_state.seen_dialogs.insert(character.dialog_id.clone());
+}
+
+// Starts any interaction that happens when an object is activated with the interact key. What
+//    happens depends on the object's category, along with any activate interactions it has.
+fn activate_object(game_data: &mut GameData, game_state: &mut GameState, object: &Object) {
+    let x = game_state.current_player_x as usize;
+    let y = game_state.current_player_y as usize;
+    let m = game_state.current_map;
+    match object.category {
+        ObjectCategory::Collectable => {
+            // Remove item from map and add to inventory
+            if !collect_object(game_data, game_state, object) {
+                show_message(game_state, format!("You found the {}, but your inventory is full!", object.name));
+                return;
+            }
+            // display "found item" dialog
+            game_state.dialog_message = format!("You've found the {}!\n\nNow, what will you do with it?"
+                                                , object.name);
+            game_state.dialog_options = vec![("Open inventory".to_string(), VisualState::PlayingInventory),
+                                             ("Close".to_string(), game_state.visual_state.clone())];
+            game_state.dialog_return_cancel = game_state.visual_state.clone();
+            game_state.pre_exit = false;
+            game_state.visual_state = VisualState::PlayingDialog;
+            return;
+        }
+        ObjectCategory::Sign => {
+            show_message(game_state, object.text.clone());
+            return;
+        }
+        _ => {}
+    }
+
+    // Pulling a lever flips it before anything else happens, so its interactions see it as it is
+    //    after being pulled
+    let mut object = object.clone();
+    if object.category == ObjectCategory::Lever {
+        if let Some(MapData::Object(lever)) = &mut game_data.maps[m].grid[x][y] {
+            let on = lever.get_state(LEVER_STATE);
+            lever.set_state(LEVER_STATE.to_string(), StateValue::Bool(!on));
+            object = lever.clone();
+        }
+    }
+
+    let mut locked = false;
+    for interaction in &object.interactions {
+        match interaction {
+            ObjectInteraction::ObjectInteractionActivate(activate) => {
+                if !object.prereqs_met(&activate.prereqs, &game_state.variables) {
+                    locked = true;
+                    continue;
+                }
+                // Activating the object can change its own states
+                if let Some(MapData::Object(instance)) = &mut game_data.maps[m].grid[x][y] {
+                    for action in &activate.self_action {
+                        instance.apply_action(action);
+                    }
+                }
+                apply_global_actions(game_state, &activate.self_action);
+                if activate.category == "travel" {
+                    travel_through_door(game_data, game_state);
+                }
+            }
+            ObjectInteraction::ObjectInteractionObjectUse(_object_use) => {
+            }
+        }
+    }
+
+    match object.category {
+        // TODO: Add a more personalized dialog box here.
+        //   Specify in config file?
+        ObjectCategory::Door if locked => {
+            // Display dialog for door being locked
+            game_state.dialog_message = "The door is locked! Try to find a key.".to_string();
+            game_state.dialog_options = vec![("Open inventory".to_string(), VisualState::PlayingInventory),
+                                             ("Close".to_string(), game_state.visual_state.clone())];
+            game_state.dialog_return_cancel = game_state.visual_state.clone();
+            game_state.pre_exit = false;
+            game_state.visual_state = VisualState::PlayingDialog;
+        }
+        ObjectCategory::Container if locked => {
+            show_message(game_state, format!("The {} is locked!", object.name));
+        }
+        ObjectCategory::Container => {
+            open_container(game_data, game_state);
+        }
+        _ => {}
+    }
+}
+
+// Takes everything out of the container in the player's spot and puts it in the player's inventory,
+//    leaving anything there is no room for inside, then says what was found
+fn open_container(game_data: &mut GameData, game_state: &mut GameState) {
+    let x = game_state.current_player_x as usize;
+    let y = game_state.current_player_y as usize;
+    let container = match &mut game_data.maps[game_state.current_map].grid[x][y] {
+        Some(MapData::Object(container)) => { container }
+        _ => { return; }
+    };
+    let mut player = match &game_data.info.player {
+        Some(player) => { player.clone() }
+        None => { return; }
+    };
+
+    let mut found = Vec::<String>::new();
+    let mut left = Vec::<String>::new();
+    for content_id in container.contents.drain(..) {
+        match game_data.objects.get(&content_id) {
+            Some(content) if left.is_empty() && player.collect_object(content) => {
+                found.push(content.name.clone());
+            }
+            _ => { left.push(content_id); }
+        }
+    }
+    container.contents = left;
+    let message = match (found.is_empty(), container.contents.is_empty()) {
+        (true, true) => { format!("The {} is empty.", container.name) }
+        (true, false) => { format!("There is something in the {}, but your inventory is full!", container.name) }
+        (false, true) => { format!("You found the {} in the {}!", found.join(", the "), container.name) }
+        (false, false) => {
+            format!("You found the {} in the {}!\n\nYour inventory is full, so the rest was left inside.",
+                    found.join(", the "), container.name)
+        }
+    };
+    game_data.info.player = Option::<Character>::Some(player);
+    show_message(game_state, message);
+}
+
+// Uses the object in the given inventory slot on whatever is in the player's spot on the map.
+pub fn use_item(game_data: &mut GameData, game_state: &mut GameState, slot_x: usize, slot_y: usize) {
+    let x = game_state.current_player_x as usize;
+    let y = game_state.current_player_y as usize;
+
+    // Check that there is an item selected
+    let inventory = game_data.info.player.as_ref().unwrap().inventory.clone();
+    if inventory[slot_x][slot_y].is_none() {
+        return; // Nothing in inventory slot.
+    }
+
+    // Get the selected inventory object
+    let inventory_object = inventory[slot_x][slot_y].as_ref().unwrap();
+    let consumable = !inventory_object.consume.is_empty();
+
+    // Get the object on the map to use the item on
+    let map_object;
+    let map = game_data.maps[game_state.current_map].clone();
+    if map.grid[x][y].is_some() {
+        match map.grid[x][y].as_ref().unwrap()  {
+            MapData::Character(character) => {
+                use_item_on_character(game_data, game_state, character, slot_x, slot_y);
+                return;
+            }
+            MapData::Object(object) => { map_object = object; }
+        }
+    } else if consumable {
+        // Consumables with nothing else to be used on are used on the player
+        consume_item(game_data, game_state, slot_x, slot_y, Option::None);
+        return;
+    } else {
+        // Nothing to use the object on. Display dialog and do nothing.
+        show_message(game_state, "You can't use this item here!".to_string());
+        return;
+    }
+
+    // Objects that have a use for a consumable get it, otherwise the player consumes it themself
+    let has_use = map_object.interactions.iter().any(|interaction| matches!(interaction,
+        ObjectInteraction::ObjectInteractionObjectUse(object_use) if object_use.foreign_object_id == inventory_object.id));
+    if consumable && !has_use {
+        consume_item(game_data, game_state, slot_x, slot_y, Option::None);
+        return;
+    }
+
+    let mut object_used = false;
+    // find object use interaction
+    for interaction in &map_object.interactions {
+        match interaction {
+            ObjectInteraction::ObjectInteractionActivate(_activate) => {}
+            ObjectInteraction::ObjectInteractionObjectUse(object_use) => {
+                if object_use.foreign_object_id != inventory_object.id {
+                    continue;
+                }
+                // The item was used. Display message.
+                show_message(game_state, format!("You used the {} on the {}!",
+                                                 inventory_object.name, map_object.name));
+                // Perform all self-actions on this instance of the object
+                if let Some(MapData::Object(instance)) = &mut game_data.maps[game_state.current_map].grid[x][y] {
+                    for action in &object_use.self_action {
+                        object_used = true;
+                        instance.apply_action(action);
+                    }
+                }
+                apply_global_actions(game_state, &object_use.self_action);
+                // If the item is a door, perform all the actions on the door on the other side,
+                //    unless it leads somewhere that isn't a door, like a spawn point
+                if map_object.category == ObjectCategory::Door {
+                    if let Some((door_map, door_x, door_y)) = game_data.door_destination(game_state.current_map, x, y) {
+                        if let Some(MapData::Object(other_door)) = &mut game_data.maps[door_map].grid[door_x][door_y] {
+                            if other_door.category == ObjectCategory::Door {
+                                for action in &object_use.self_action {
+                                    other_door.apply_action(action);
+                                }
+                            }
+                        }
+                    }
+                }
+                // If the item is consumed, remove it from the inventory
+                if object_use.consume_item {
+                    let mut new_player = game_data.info.player.as_ref().unwrap().clone();
+                    new_player.inventory[slot_x][slot_y] = Option::None;
+                    game_data.info.player = Option::<Character>::Some(new_player);
+                }
+            }
+        }
+    }
+    if !object_used {
+        let message = format!("You tried to use the {} on the {}, but it didn't work!",
+                                            inventory_object.name, map_object.name);
+        show_message(game_state, message);
+    }
+    update_quests(game_data, game_state);
+}
+
+// Uses an object from the player's inventory on a character in the
+//    player's spot on the map
+fn use_item_on_character(game_data: &mut GameData, game_state: &mut GameState, character: &Character,
+                         slot_x: usize, slot_y: usize) {
+    // Get the selected inventory object
+    let inventory = game_data.info.player.as_ref().unwrap().inventory.clone();
+    if inventory[slot_x][slot_y].is_none() {
+        return; // Nothing in inventory slot.
+    }
+    let inventory_object = inventory[slot_x][slot_y].as_ref().unwrap();
+
+    // Find the right interaction for the item
+    let mut object_used = false;
+    for object_use in &character.interactions.object_use {
+        if object_use.object_id == inventory_object.id {
+            object_used = true;
+
+            // Update character dialog if specified by the interaction
+            if !object_use.set_dialog.is_empty() {
+                let mut new_character = character.to_owned();
+                new_character.dialog_id = object_use.set_dialog.to_owned();
+                game_data.maps[game_state.current_map]
+                        .grid[game_state.current_player_x as usize][game_state.current_player_y as usize]
+                        = Option::<MapData>::Some(MapData::Character(new_character));
+            }
+
+            // If the item is consumed, remove it from the inventory
+            if object_use.consume_item {
+                let mut new_player = game_data.info.player.as_ref().unwrap().clone();
+                new_player.inventory[slot_x][slot_y] = Option::None;
+                game_data.info.player = Option::<Character>::Some(new_player);
+            }
+        }
+    }
+    if !object_used {
+        if !inventory_object.consume.is_empty() {
+            consume_item(game_data, game_state, slot_x, slot_y, Option::<(usize, usize)>::Some(
+                (game_state.current_player_x as usize, game_state.current_player_y as usize)));
+            return;
+        }
+        show_message(game_state, "You can't use this item here!".to_string());
+    }
+    update_quests(game_data, game_state);
+}
+
+// Uses up the consumable item in the player's inventory slot on the player, or on the NPC at `npc`
+//    on the current map. Permanent changes are kept within the attribute's limits, effects on
+//    attributes the target doesn't have are skipped, and the item is only used up if it changes
+//    something.
+fn consume_item(game_data: &mut GameData, game_state: &mut GameState, slot_x: usize, slot_y: usize,
+                npc: Option<(usize, usize)>) {
+    let mut player = game_data.info.player.as_ref().unwrap().clone();
+    let item = match &player.inventory[slot_x][slot_y] {
+        Some(item) => { item.clone() }
+        None => { return; }
+    };
+    let mut target = match npc {
+        Some((x, y)) => {
+            match &game_data.maps[game_state.current_map].grid[x][y] {
+                Some(MapData::Character(character)) => { character.clone() }
+                _ => { return; }
+            }
+        }
+        None => { player.clone() }
+    };
+
+    let mut changes = Vec::<String>::new();
+    for effect in &item.consume {
+        let attribute = match target.get_attribute(&effect.attribute_id) {
+            Some(attribute) => { attribute.clone() }
+            None => { continue; }
+        };
+        match effect.duration {
+            Some(duration) => {
+                target.buffs.push(Buff {
+                    attribute_id: effect.attribute_id.clone(),
+                    amount: effect.amount,
+                    remaining: duration,
+                });
+                changes.push(format!("{} {:+} for {}", attribute.display_name, effect.amount,
+                                     describe_duration(game_data, duration)));
+            }
+            None => {
+                target.change_attribute(&effect.attribute_id, effect.amount);
+                let changed = target.get_attribute(&effect.attribute_id)
+                    .map_or(0, |after| after.current_val as i64 - attribute.current_val as i64);
+                if changed != 0 {
+                    changes.push(format!("{} {:+}", attribute.display_name, changed));
+                }
+            }
+        }
+    }
+
+    let on = match npc {
+        Some(_) => { format!("the {}", target.name) }
+        None => { "you".to_string() }
+    };
+    if changes.is_empty() {
+        show_message(game_state, format!("The {} wouldn't do anything to {} right now.", item.name, on));
+        return;
+    }
+    let message = match npc {
+        Some((x, y)) => {
+            let message = format!("You used the {} on {}. {}.", item.name, on, changes.join(", "));
+            game_data.maps[game_state.current_map].grid[x][y] = Option::<MapData>::Some(MapData::Character(target));
+            player.inventory[slot_x][slot_y] = Option::None;
+            message
+        }
+        None => {
+            player = target;
+            player.inventory[slot_x][slot_y] = Option::None;
+            format!("You used the {}. {}.", item.name, changes.join(", "))
+        }
+    };
+    game_data.info.player = Option::<Character>::Some(player);
+    show_message(game_state, message);
+    update_quests(game_data, game_state);
+}
+
+// How long an effect lasts, in words. Ticks are shown as the seconds they take, rounded up.
+fn describe_duration(game_data: &GameData, duration: EffectDuration) -> String {
+    let (count, unit) = match duration {
+        EffectDuration::Turns(turns) => { (turns, "turn") }
+        EffectDuration::Ticks(ticks) => { ((ticks * game_data.info.tick_rate_ms).div_ceil(1000), "second") }
+    };
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+// Equips the item in the player's inventory slot, or unequips it if it is already equipped.
+//    Whatever else was equipped in the same equipment slot is unequipped to make room for it.
+pub fn toggle_equipped(game_data: &mut GameData, game_state: &mut GameState, slot_x: usize, slot_y: usize) {
+    let mut player = match &game_data.info.player {
+        Some(player) => { player.clone() }
+        None => { return; }
+    };
+    let item = match player.inventory.get(slot_x).and_then(|column| column.get(slot_y)) {
+        Some(Some(item)) => { item.clone() }
+        _ => { return; }
+    };
+    let equipment = match &item.equip {
+        Some(equipment) => { equipment }
+        None => {
+            show_message(game_state, format!("The {} can't be equipped.", item.name));
+            return;
+        }
+    };
+
+    let message =
+        if item.equipped {
+            format!("You unequipped the {}.", item.name)
+        } else if !player.equipment_slots.contains(&equipment.slot) {
+            show_message(game_state, format!("You have nowhere to equip the {}.", item.name));
+            return;
+        } else {
+            let mut replaced = Vec::<String>::new();
+            for other in player.inventory.iter_mut().flatten().flatten() {
+                if other.equipped && other.equip.as_ref().is_some_and(|other_equipment| other_equipment.slot == equipment.slot) {
+                    other.equipped = false;
+                    replaced.push(other.name.clone());
+                }
+            }
+            if replaced.is_empty() {
+                format!("You equipped the {}.", item.name)
+            } else {
+                format!("You equipped the {}, and unequipped the {}.", item.name, replaced.join(", the "))
+            }
+        };
+    if let Some(slot) = &mut player.inventory[slot_x][slot_y] {
+        slot.equipped = !item.equipped;
+    }
+    game_data.info.player = Option::<Character>::Some(player);
+    show_message(game_state, message);
+    update_quests(game_data, game_state);
+}
+
+// Changes the global variables used by any of the actions. Actions on the object's own states are
+//    left to the object.
+fn apply_global_actions(game_state: &mut GameState, actions: &[StateAction]) {
+    for action in actions {
+        let current = match global_variable(&action.name).and_then(|name| game_state.variables.get_mut(name)) {
+            Some(current) => { current }
+            None => { continue; }
+        };
+        if let Some(value) = action.apply_to(current) {
+            *current = value;
+        }
+    }
+}
+
+// Removes the object from the player's spot on the map and places it in the
+//     player inventory. Returns false, leaving the object where it is, if there is no room for it.
+fn collect_object(game_data: &mut GameData, game_state: &GameState, object: &Object) -> bool {
+    // If inventory size is not exceeded, add item to player inventory
+    if game_data.info.player.is_none() {
+        return false;
+    }
+    let mut player = game_data.info.player.as_ref().unwrap().clone();
+    if !player.collect_object(object) {
+        return false;
+    }
+    game_data.info.player = Option::<Character>::Some(player);
+    // Remove item from map
+    game_data.maps[game_state.current_map]
+             .grid[game_state.current_player_x as usize][game_state.current_player_y as usize]
+             = Option::None;
+    true
+}
+
+// Moves character to wherever the door in the player's spot leads, which can be another map or
+//    somewhere else in the same map. Assume prereqs are already checked.
+fn travel_through_door(game_data: &GameData, game_state: &mut GameState) {
+    let x = game_state.current_player_x as usize;
+    let y = game_state.current_player_y as usize;
+    // Doors that lead nowhere do nothing
+    if let Some((m, x, y)) = game_data.door_destination(game_state.current_map, x, y) {
+        game_state.current_map = m;
+        game_state.current_player_x = x as u16;
+        game_state.current_player_y = y as u16;
+    }
+}
+
+// The options of the current NPC dialog that can be seen, each with whether it can be chosen.
+//    Options whose conditions aren't met are left out, unless they are greyed out instead.
+pub fn visible_dialog_options<'a>(game_data: &'a GameData, game_state: &GameState) -> Vec<(&'a DialogOption, bool)> {
+    let dialog = match game_data.dialogs.get(&game_state.npc_dialog_id) {
+        Some(dialog) => { dialog }
+        None => { return vec![]; }
+    };
+    dialog.options.iter()
+        .map(|option| (option, conditions_met(game_data, game_state, &option.conditions)))
+        .filter(|(option, met)| *met || option.when_unmet == WhenUnmet::Grey)
+        .collect()
+}
+
+// Returns true if the condition on a dialog option holds right now
+fn condition_met(game_data: &GameData, game_state: &GameState, condition: &DialogCondition) -> bool {
+    let player = match &game_data.info.player {
+        Some(player) => { player }
+        None => { return false; }
+    };
+    match condition {
+        DialogCondition::HasItem(object_id) => { player.has_item(object_id) }
+        DialogCondition::LacksItem(object_id) => { !player.has_item(object_id) }
+        DialogCondition::Flag(flag) => { flag_set(game_state, flag) }
+        DialogCondition::NotFlag(flag) => { !flag_set(game_state, flag) }
+        DialogCondition::Variable(variable) => {
+            match game_state.variables.get(&variable.id) {
+                Some(value) => { variable.comparison.compare(value, &variable.value) }
+                None => { false }
+            }
+        }
+        DialogCondition::Attribute(attribute) => {
+            // Equipment counts towards the attribute
+            match player.get_attribute(&attribute.id) {
+                Some(_) => {
+                    let value = player.get_attribute_value(&attribute.id);
+                    attribute.comparison.compare(&StateValue::Int(value as i64), &attribute.value)
+                }
+                None => { false }
+            }
+        }
+        DialogCondition::SeenDialog(dialog_id) => { game_state.seen_dialogs.contains(dialog_id) }
+        DialogCondition::State(state) => {
+            match game_data.find_instance(&state.object) {
+                Some((m, x, y)) => {
+                    match &game_data.maps[m].grid[x][y] {
+                        Some(MapData::Object(object)) => {
+                            match object.state_value(&state.state) {
+                                Some(value) => { state.comparison.compare(value, &state.value) }
+                                None => { false }
+                            }
+                        }
+                        _ => { false }
+                    }
+                }
+                None => { false }
+            }
+        }
+    }
+}
+
+// Returns true if every one of the conditions holds right now
+fn conditions_met(game_data: &GameData, game_state: &GameState, conditions: &[DialogCondition]) -> bool {
+    conditions.iter().all(|condition| condition_met(game_data, game_state, condition))
+}
+
+// Flags are boolean global variables, which are set while they are true
+fn flag_set(game_state: &GameState, flag: &str) -> bool {
+    game_state.variables.get(flag) == Some(&StateValue::Bool(true))
+}
+
+// Chooses one of the options that can be seen in the current NPC dialog. Its effects happen
+//    first, then the dialog follows where the option leads, unless an effect already ended the
+//    conversation. Options that are greyed out can't be chosen.
+pub fn choose_dialog_option(game_data: &mut GameData, game_state: &mut GameState, option: usize) {
+    let option = match visible_dialog_options(game_data, game_state).get(option) {
+        Some((option, true)) => { (*option).clone() }
+        _ => { return; }
+    };
+    for effect in &option.effects {
+        apply_dialog_effect(game_data, game_state, effect);
+    }
+    if !option.effects.iter().any(|effect| effect.ends_conversation()) {
+        follow_dialog_option(game_data, game_state, &option.next);
+    }
+    update_quests(game_data, game_state);
+}
+
+// Goes where a chosen dialog option leads: another dialog, or one of the dialog keywords
+fn follow_dialog_option(game_data: &GameData, game_state: &mut GameState, next: &str) {
+    if next == "exit" {
+        // Conversations are always started from the map. Popups shown during the conversation use
+        //    dialog_return_cancel themselves, so it can't be used to remember that.
+        game_state.visual_state = VisualState::PlayingMap;
+
+    } else if next == "inventory" {
+        game_state.visual_state = VisualState::PlayingInventory;
+
+    } else if next == "fight" {
+        start_fight(game_state, game_state.current_player_x as usize, game_state.current_player_y as usize);
+
+    } else if game_data.dialogs.contains_key(next) { // if next is a dialog id
+        game_state.npc_dialog_id = next.to_string();
+        game_state.seen_dialogs.insert(next.to_string());
+
+    }
+}
+
+// Makes a change to the game when a dialog option is chosen. Anything that can't be found, such
+//    as an item to take that the player doesn't have, is left alone.
+fn apply_dialog_effect(game_data: &mut GameData, game_state: &mut GameState, effect: &DialogEffect) {
+    let x = game_state.current_player_x as usize;
+    let y = game_state.current_player_y as usize;
+    match effect {
+        DialogEffect::GiveItem(object_id) => {
+            // Items are only given if there is room for them
+            if let (Some(player), Some(object)) = (&mut game_data.info.player, game_data.objects.get(object_id)) {
+                let mut object = object.clone();
+                object.instance_id = object.id.clone();
+                player.collect_object(&object);
+            }
+        }
+        DialogEffect::TakeItem(object_id) => {
+            if let Some(player) = &mut game_data.info.player {
+                player.take_item(object_id);
+            }
+        }
+        DialogEffect::SetFlag(flag) => {
+            if let Some(StateValue::Bool(value)) = game_state.variables.get_mut(flag) {
+                *value = true;
+            }
+        }
+        DialogEffect::ClearFlag(flag) => {
+            if let Some(StateValue::Bool(value)) = game_state.variables.get_mut(flag) {
+                *value = false;
+            }
+        }
+        DialogEffect::SetVariable(change) => {
+            if let Some(current) = game_state.variables.get_mut(&change.id) {
+                if let Some(value) = change.action().apply_to(current) {
+                    *current = value;
+                }
+            }
+        }
+        DialogEffect::SetState { object, state, value } => {
+            if let Some((m, object_x, object_y)) = game_data.find_instance(object) {
+                if let Some(MapData::Object(instance)) = &mut game_data.maps[m].grid[object_x][object_y] {
+                    instance.set_state(state.clone(), value.clone());
+                }
+            }
+        }
+        DialogEffect::ChangeAttribute { id, amount } => {
+            if let Some(player) = &mut game_data.info.player {
+                player.change_attribute(id, *amount);
+            }
+        }
+        DialogEffect::SetDialog(dialog_id) => {
+            if let Some(MapData::Character(character)) = &mut game_data.maps[game_state.current_map].grid[x][y] {
+                character.dialog_id = dialog_id.clone();
+            }
+        }
+        DialogEffect::Fight => {
+            start_fight(game_state, x, y);
+        }
+        DialogEffect::Travel { map, spawn } => {
+            let target = game_data.maps.iter().position(|m| &m.info.id == map)
+                .and_then(|m| game_data.maps[m].spawns.get(spawn).map(|(x, y)| (m, *x, *y)));
+            if let Some((m, spawn_x, spawn_y)) = target {
+                game_state.current_map = m;
+                game_state.current_player_x = spawn_x as u16;
+                game_state.current_player_y = spawn_y as u16;
+                game_state.visual_state = VisualState::PlayingMap;
+            }
+        }
+    }
+}
+
+// Every quest that has started, with how far along it is. Quests still being worked on come first,
+//    then completed ones, each in order of their names.
+pub fn quest_log<'a>(game_data: &'a GameData, game_state: &'a GameState) -> Vec<(&'a Quest, &'a QuestProgress)> {
+    let mut log = game_state.quests.iter()
+        .filter_map(|(quest_id, progress)| game_data.quests.get(quest_id).map(|quest| (quest, progress)))
+        .collect::<Vec<(&Quest, &QuestProgress)>>();
+    log.sort_by_key(|(quest, progress)| (**progress == QuestProgress::Completed, &quest.name, &quest.id));
+    log
+}
+
+// Checks every quest for progress after the player does something: quests whose start conditions
+//    are met start, and stages whose conditions are met are finished, completing the quest and
+//    giving its rewards after the last one. Rewards can let other quests move along too, so this
+//    keeps going until nothing else changes. The player is told about anything that happened.
+fn update_quests(game_data: &mut GameData, game_state: &mut GameState) {
+    let mut quests = game_data.quests.values().cloned().collect::<Vec<Quest>>();
+    quests.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut notices = Vec::<String>::new();
+    loop {
+        let mut changed = false;
+        for quest in &quests {
+            let mut progress = match game_state.quests.get(&quest.id) {
+                Some(progress) => { progress.clone() }
+                None if conditions_met(game_data, game_state, &quest.start) => {
+                    notices.push(format!("New quest: {}", quest.name));
+                    changed = true;
+                    QuestProgress::Active(0)
+                }
+                None => { continue; }
+            };
+            let before = progress.clone();
+            while let QuestProgress::Active(stage) = progress {
+                if !conditions_met(game_data, game_state, &quest.stages[stage].conditions) {
+                    break;
+                }
+                progress = if stage + 1 < quest.stages.len() {
+                    QuestProgress::Active(stage + 1)
+                } else {
+                    QuestProgress::Completed
+                };
+            }
+            if progress != before {
+                changed = true;
+                if progress == QuestProgress::Completed {
+                    notices.push(format!("Quest complete: {}", quest.name));
+                } else if game_state.quests.contains_key(&quest.id) {
+                    notices.push(format!("Quest updated: {}", quest.name));
+                }
+            }
+            let completed = progress == QuestProgress::Completed && before != QuestProgress::Completed;
+            game_state.quests.insert(quest.id.clone(), progress);
+            if completed {
+                for effect in &quest.rewards {
+                    apply_dialog_effect(game_data, game_state, effect);
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    if notices.is_empty() {
+        return;
+    }
+    // Quest news is added to any message already being shown, rather than hiding it
+    let notice = notices.join("\n");
+    if game_state.visual_state == VisualState::PlayingDialog {
+        game_state.dialog_message = format!("{}\n\n{}", game_state.dialog_message, notice);
+    } else {
+        show_message(game_state, notice);
+    }
+}
+
+// Sets the dialog screen to display a popup with the specified msg string.
+//    The popup closes upon clicking return and returns to the current
+//    screen.
+pub fn show_message(game_state: &mut GameState, msg: String) {
+    game_state.dialog_message = msg;
+    game_state.dialog_options = vec![("Continue".to_string(), game_state.visual_state.clone()),
+                                     ("Close".to_string(), game_state.visual_state.clone())];
+    game_state.dialog_return_cancel = game_state.visual_state.clone();
+    game_state.pre_exit = false;
+    game_state.visual_state = VisualState::PlayingDialog;
+}
+
+// Chooses one of the buttons of the popup dialog box, returning to the screen that button
+//    leads to. Choosing "Yes" when asked about exiting ends the game.
+pub fn choose_message_option(game_state: &mut GameState, option: usize) {
+    let next = match game_state.dialog_options.get(option) {
+        Some((_, next)) => { next.clone() }
+        None => { return; }
+    };
+    // Check if we need to do a full exit or a return to the previous screen
+    if game_state.pre_exit {
+        if option == 1 {
+            game_state.do_exit = true;
+        } else { // If option == 0
+            game_state.pre_exit = false;
+            game_state.visual_state = next;
+        }
+    } else {
+        // Set up the result and return to the previous screen
+        game_state.dialog_result_ready = true;
+        game_state.visual_state = next;
+    }
+}
+
+// Closes the popup dialog box without choosing either button
+pub fn cancel_message(game_state: &mut GameState) {
+    // Set up the (lack of) result and return to the previous screen
+    game_state.dialog_result_ready = false;
+    game_state.pre_exit = false;
+    game_state.visual_state = game_state.dialog_return_cancel.clone();
+}
+
+// Starts a fight with the NPC at (x, y) on the current map, which is either in the player's spot or
+//    right next to it
+fn start_fight(game_state: &mut GameState, x: usize, y: usize) {
+    game_state.fight_x = x;
+    game_state.fight_y = y;
+    game_state.fight_selected = 0;
+    game_state.fight_turn = 0;
+    game_state.fight_message = "The fight begins! Use Up/Down to choose an attack and Enter to use it."
+                                    .to_string();
+    game_state.visual_state = VisualState::PlayingCharacterFight;
+}
+
+// Plays a single round of the fight. The player uses the attack at attack_index on the NPC being
+//    fought, then the NPC (if it is still standing) attacks back. NPCs take turns using each of
+//    their attacks in order.
+pub fn attack(game_data: &mut GameData, game_state: &mut GameState, attack_index: usize) {
+    let x = game_state.fight_x;
+    let y = game_state.fight_y;
+    let m = game_state.current_map;
+    let mut npc = match game_data.maps[m].grid[x][y].as_ref() {
+        Some(MapData::Character(character)) => { character.clone() }
+        _ => { return; }
+    };
+    let mut player = game_data.info.player.as_ref().unwrap().clone();
+
+    // The player can't fight without any attacks, so they run away instead
+    if player.interactions.attacks.is_empty() {
+        game_state.visual_state = VisualState::PlayingMap;
+        show_message(game_state, "You have no way to fight, so you run away!".to_string());
+        return;
+    }
+
+    // Player's turn
+    let attack = match player.interactions.attacks.get(attack_index) {
+        Some(attack) => { attack }
+        None => { return; }
+    };
+    let damage = attack.calculate_damage(&player);
+    npc.take_damage(damage);
+    let mut message = format!("You used {} for {} damage!", attack.display_name, damage);
+
+    // Victory
+    if npc.is_defeated() {
+        player.count_down_buffs(true);
+        npc.count_down_buffs(true);
+        game_data.info.player = Option::<Character>::Some(player);
+        game_state.visual_state = VisualState::PlayingMap;
+        defeat_character(game_data, game_state, npc, x, y);
+        return;
+    }
+
+    // NPC's turn
+    if !npc.interactions.attacks.is_empty() {
+        let npc_attack = &npc.interactions.attacks[game_state.fight_turn % npc.interactions.attacks.len()];
+        let npc_damage = npc_attack.calculate_damage(&npc);
+        player.take_damage(npc_damage);
+        message = format!("{}\n\n{} used {} for {} damage!", message, npc.name,
+                          npc_attack.display_name, npc_damage);
+    }
+    game_state.fight_turn += 1;
+    game_state.fight_message = message;
+    // Buffs that last a number of turns count down once both sides have had theirs
+    player.count_down_buffs(true);
+    npc.count_down_buffs(true);
+
+    // Defeat, the player is healed and sent back to where the game started
+    if player.is_defeated() {
+        player.restore_health();
+        game_state.current_map = GameState::map_from_id(game_data, &game_data.info.starting_map);
+        game_state.current_player_x = game_data.info.starting_position_x;
+        game_state.current_player_y = game_data.info.starting_position_y;
+        game_state.visual_state = VisualState::PlayingMap;
+        show_message(game_state, format!(
+            "You were defeated by the {}!\n\nYou wake up back where your journey began.", npc.name));
+    }
+
+    game_data.maps[m].grid[x][y] = Option::<MapData>::Some(MapData::Character(npc));
+    game_data.info.player = Option::<Character>::Some(player);
+}
+
+// Deals with the NPC at (x, y) on the current map having been beaten: it is either taken off the
+//    map, or healed and left in place with its new dialog. The first time it is defeated, its loot
+//    is dropped on the closest empty spaces the player can walk to.
+fn defeat_character(game_data: &mut GameData, game_state: &mut GameState, mut npc: Character, x: usize, y: usize) {
+    let mut message = format!("You defeated the {}!", npc.name);
+    let first_defeat = !npc.defeated;
+    npc.defeated = true;
+    let map = &mut game_data.maps[game_state.current_map];
+    if npc.on_defeat.remove {
+        map.grid[x][y] = Option::None;
+    } else {
+        npc.restore_health();
+        if !npc.on_defeat.set_dialog.is_empty() {
+            npc.dialog_id = npc.on_defeat.set_dialog.clone();
+        }
+        map.grid[x][y] = Option::<MapData>::Some(MapData::Character(npc.clone()));
+    }
+
+    if first_defeat && !npc.on_defeat.loot.is_empty() {
+        let mut dropped = Vec::<String>::new();
+        let spaces = empty_spaces_near(map, x, y);
+        for (object_id, (drop_x, drop_y)) in npc.on_defeat.loot.iter().zip(spaces) {
+            if let Some(object) = game_data.objects.get(object_id) {
+                let mut object = object.clone();
+                object.instance_id = default_instance_id(&object.id, &map.info.id, drop_x, drop_y);
+                dropped.push(object.name.clone());
+                map.grid[drop_x][drop_y] = Option::<MapData>::Some(MapData::Object(object));
+            }
+        }
+        if !dropped.is_empty() {
+            message = format!("{}\n\nThe {} dropped the {}.", message, npc.name, dropped.join(", the "));
+        }
+    }
+    show_message(game_state, message);
+}
+
+// Every empty space that can be walked to from (x, y), closest first, starting with (x, y) itself
+fn empty_spaces_near(map: &Map, x: usize, y: usize) -> Vec<(usize, usize)> {
+    let mut spaces = Vec::<(usize, usize)>::new();
+    let mut seen = HashSet::<(usize, usize)>::from([(x, y)]);
+    let mut queue = VecDeque::<(usize, usize)>::from([(x, y)]);
+    while let Some((space_x, space_y)) = queue.pop_front() {
+        if map.grid[space_x][space_y].is_none() {
+            spaces.push((space_x, space_y));
+        }
+        for direction in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+            let (delta_x, delta_y) = direction.delta();
+            let (next_x, next_y) = (space_x as i16 + delta_x, space_y as i16 + delta_y);
+            if space_open(map, next_x, next_y) && seen.insert((next_x as usize, next_y as usize)) {
+                queue.push_back((next_x as usize, next_y as usize));
+            }
+        }
+    }
+    spaces
+}
+
+// Saves the game to the game's save file, telling the player whether it worked
+pub fn save_progress(game_data: &GameData, game_state: &mut GameState) {
+    match saves::save_game(&game_state.save_path, game_data, game_state) {
+        Ok(_) => {
+            show_message(game_state, "Your progress has been saved.".to_string());
+        },
+        Err(err) => {
+            show_message(game_state, format!("Unable to save your progress: {}", err));
+        }
+    }
+}
+
+// Continues the game from the game's save file, telling the player if it didn't work
+pub fn continue_progress(game_data: &mut GameData, game_state: &mut GameState) {
+    match saves::load_game(&game_state.save_path.clone(), game_data, game_state) {
+        Ok(_) => {
+            game_state.visual_state = VisualState::PlayingMap;
+        },
+        Err(err) => {
+            show_message(game_state, format!("Unable to load your saved game: {}", err));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use super::Direction::*;
+    use crate::game::config_parsers::files::FolderFiles;
+
+    // Loads the example game and starts playing it with the player at the given spot in the given map
+    fn example_game_at(map_id: &str, x: u16, y: u16) -> (GameData, GameState) {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+        let game_data = GameData::process_configs(&FolderFiles::new(&config_path)).unwrap();
+        let mut game_state = GameState::new(&game_data, saves::default_save_path(&config_path));
+        start_game(&mut game_state);
+        game_state.current_map = GameState::map_from_id(&game_data, map_id);
+        game_state.current_player_x = x;
+        game_state.current_player_y = y;
+        (game_data, game_state)
+    }
+
+    fn position(game_state: &GameState) -> (u16, u16) {
+        (game_state.current_player_x, game_state.current_player_y)
+    }
+
+    fn inventory_ids(game_data: &GameData) -> Vec<String> {
+        game_data.info.player.as_ref().unwrap().inventory.iter().flatten().flatten().map(|o| o.id.clone()).collect()
+    }
+
+    #[test]
+    fn walls_and_collidable_objects_block_moves() {
+        let (game_data, mut game_state) = example_game_at("Main Room", 1, 1);
+        assert!(!move_player(&game_data, &mut game_state, Up));
+        assert!(!move_player(&game_data, &mut game_state, Left));
+        assert_eq!(position(&game_state), (1, 1));
+        assert!(move_player(&game_data, &mut game_state, Right));
+        assert_eq!(position(&game_state), (2, 1));
+
+        // The table is at (13, 6)
+        game_state.current_player_x = 13;
+        game_state.current_player_y = 5;
+        assert!(!move_player(&game_data, &mut game_state, Down));
+        assert_eq!(position(&game_state), (13, 5));
+    }
+
+    #[test]
+    fn doors_lead_to_the_matching_door_in_another_map() {
+        let (mut game_data, mut game_state) = example_game_at("Main Room", 5, 1);
+        assert!(move_player(&game_data, &mut game_state, Up));
+        interact(&mut game_data, &mut game_state);
+        assert_eq!(game_data.maps[game_state.current_map].info.id, "Secondary Room");
+        assert_eq!(position(&game_state), (6, 4));
+    }
+
+    #[test]
+    fn locked_doors_open_once_their_prereqs_are_met() {
+        let (mut game_data, mut game_state) = example_game_at("Main Room", 10, 8);
+        assert!(move_player(&game_data, &mut game_state, Down));
+        interact(&mut game_data, &mut game_state);
+        assert_eq!(game_data.maps[game_state.current_map].info.id, "Main Room");
+        assert!(matches!(game_state.visual_state, VisualState::PlayingDialog));
+        assert_eq!(game_state.dialog_message, "The door is locked! Try to find a key.");
+
+        // Unlock the door with the key from the secondary room
+        let secondary_room = GameState::map_from_id(&game_data, "Secondary Room");
+        let key = match &game_data.maps[secondary_room].grid[1][1] {
+            Some(MapData::Object(key)) => { key.clone() }
+            other => { panic!("Expected the key at (1, 1), found {:?}", other) }
+        };
+        game_data.info.player.as_mut().unwrap().collect_object(&key);
+        return_to_map(&mut game_state);
+        use_item(&mut game_data, &mut game_state, 0, 0);
+        assert_eq!(game_state.dialog_message, "You used the Key on the Door!");
+        assert!(inventory_ids(&game_data).is_empty());
+
+        return_to_map(&mut game_state);
+        interact(&mut game_data, &mut game_state);
+        assert_eq!(game_data.maps[game_state.current_map].info.id, "basement");
+        assert_eq!(position(&game_state), (5, 0));
+    }
+
+    #[test]
+    fn items_are_collected_and_used_on_characters() {
+        let (mut game_data, mut game_state) = example_game_at("Main Room", 12, 5);
+        interact(&mut game_data, &mut game_state);
+        assert_eq!(inventory_ids(&game_data), vec!["hat"]);
+        assert!(game_data.maps[game_state.current_map].grid[12][5].is_none());
+        assert!(game_state.dialog_message.starts_with("You've found the Hat!"));
+
+        // Items can't be used where there's nothing to use them on
+        return_to_map(&mut game_state);
+        use_item(&mut game_data, &mut game_state, 0, 0);
+        assert_eq!(game_state.dialog_message, "You can't use this item here!");
+        assert_eq!(inventory_ids(&game_data), vec!["hat"]);
+
+        // The angry antagonist is at (11, 4), and is much happier once given the hat
+        return_to_map(&mut game_state);
+        assert!(move_player(&game_data, &mut game_state, Up));
+        assert!(move_player(&game_data, &mut game_state, Left));
+        use_item(&mut game_data, &mut game_state, 0, 0);
+        assert!(inventory_ids(&game_data).is_empty());
+        match &game_data.maps[game_state.current_map].grid[11][4] {
+            Some(MapData::Character(character)) => { assert_eq!(character.dialog_id, "AA give hat"); }
+            other => { panic!("Expected the angry antagonist at (11, 4), found {:?}", other) }
+        }
+    }
+
+    #[test]
+    fn dialog_options_lead_where_they_say() {
+        let (mut game_data, mut game_state) = example_game_at("Main Room", 11, 4);
+        interact(&mut game_data, &mut game_state);
+        assert!(matches!(game_state.visual_state, VisualState::PlayingCharacterInteraction));
+        assert_eq!(game_state.npc_dialog_id, "AA start");
+
+        choose_dialog_option(&mut game_data, &mut game_state, 0);
+        assert_eq!(game_state.npc_dialog_id, "d0");
+        choose_dialog_option(&mut game_data, &mut game_state, 0);
+        assert!(matches!(game_state.visual_state, VisualState::PlayingMap));
+
+        interact(&mut game_data, &mut game_state);
+        choose_dialog_option(&mut game_data, &mut game_state, 1);
+        assert!(matches!(game_state.visual_state, VisualState::PlayingInventory));
+
+        return_to_map(&mut game_state);
+        game_state.npc_dialog_id = "d0".to_string();
+        choose_dialog_option(&mut game_data, &mut game_state, 1);
+        assert!(matches!(game_state.visual_state, VisualState::PlayingCharacterFight));
+    }
+
+    // The example game's "Where did you get it?" option sets a flag, and is hidden once it is set
+    #[test]
+    fn flags_are_set_and_cleared_by_dialog_effects() {
+        let (mut game_data, mut game_state) = example_game_at("Main Room", 11, 4);
+        let option_count = |game_data: &GameData, game_state: &GameState| visible_dialog_options(game_data, game_state).len();
+        interact(&mut game_data, &mut game_state);
+        choose_dialog_option(&mut game_data, &mut game_state, 0);
+        assert_eq!(option_count(&game_data, &game_state), 3);
+        assert!(!flag_set(&game_state, "asked_about_key"));
+
+        choose_dialog_option(&mut game_data, &mut game_state, 2);
+        assert_eq!(game_state.npc_dialog_id, "d1");
+        assert!(flag_set(&game_state, "asked_about_key"));
+        game_state.npc_dialog_id = "d0".to_string();
+        assert_eq!(option_count(&game_data, &game_state), 2);
+
+        apply_dialog_effect(&mut game_data, &mut game_state, &DialogEffect::ClearFlag("asked_about_key".to_string()));
+        assert!(!flag_set(&game_state, "asked_about_key"));
+        assert_eq!(option_count(&game_data, &game_state), 3);
+
+        // Flags that aren't declared in game.yaml are never set
+        apply_dialog_effect(&mut game_data, &mut game_state, &DialogEffect::SetFlag("undeclared".to_string()));
+        assert!(!flag_set(&game_state, "undeclared"));
+        assert!(!game_state.variables.contains_key("undeclared"));
+    }
+}
@@ -1,11 +1,13 @@
+use std::collections::HashMap;
 use serde::{Serialize,Deserialize};
 use crate::game::characters::Character;
 use crate::game::objects::Object;
 
 // Map data structure. Contains a single map with a grid of spaces.
-// Each space can hold either nothing, a character, or an object.
+// Each space can hold either nothing, a character, or an object. Underneath everything is a grid
+// of tiles (walls, floors, water) that decides where the player can walk.
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum MapData {
     Character(Character),
     Object(Object)
@@ -14,7 +16,23 @@ pub enum MapData {
 #[derive(Debug, Clone)]
 pub struct Map {
     pub info: MapInfo,
-    pub grid: Vec<Vec<Option<MapData>>>
+    pub grid: Vec<Vec<Option<MapData>>>,
+    // Stored the same way as the grid, by column and then by row
+    pub tiles: Vec<Vec<Tile>>,
+    // Where the doors in this map that were given a link lead
+    pub links: Vec<DoorLink>,
+    // Named positions that links and dialogs can send the player to, by spawn id
+    pub spawns: HashMap<String, (usize, usize)>,
+}
+
+// A link from the door at (x, y) to a spot on a map, given as (map index, x, y). Unless the link is
+//    one-way, a door at the other end that has no link of its own leads back through it.
+#[derive(Debug, Clone)]
+pub struct DoorLink {
+    pub x: usize,
+    pub y: usize,
+    pub target: (usize, usize, usize),
+    pub one_way: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -31,3 +49,65 @@ pub struct Size {
     pub height: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TileKind {
+    Wall,
+    Floor,
+    Water,
+}
+
+impl TileKind {
+    // The name used for the tile in config files
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileKind::Wall => { "wall" }
+            TileKind::Floor => { "floor" }
+            TileKind::Water => { "water" }
+        }
+    }
+
+    // Only floors can be walked on, unless a map says otherwise
+    pub fn passable_by_default(&self) -> bool {
+        matches!(self, TileKind::Floor)
+    }
+}
+
+// A single tile of a map, drawn with its icon wherever nothing else is on top of it
+#[derive(Debug, Clone)]
+pub struct Tile {
+    pub kind: TileKind,
+    pub icon: char,
+    pub passable: bool,
+}
+
+impl Tile {
+    pub fn new(kind: TileKind, icon: char) -> Tile {
+        Tile { kind, icon, passable: kind.passable_by_default() }
+    }
+}
+
+// Creates the tiles for a plain rectangular room: a floor surrounded by walls
+pub fn walled_tiles(width: usize, height: usize) -> Vec<Vec<Tile>> {
+    let mut tiles = Vec::<Vec<Tile>>::new();
+    for c in 0..width {
+        let mut column = Vec::<Tile>::new();
+        for r in 0..height {
+            let top_or_bottom = r == 0 || r == height-1;
+            let side = c == 0 || c == width-1;
+            let tile =
+                if top_or_bottom && side {
+                    Tile::new(TileKind::Wall, '+')
+                } else if top_or_bottom {
+                    Tile::new(TileKind::Wall, '-')
+                } else if side {
+                    Tile::new(TileKind::Wall, '|')
+                } else {
+                    Tile::new(TileKind::Floor, ' ')
+                };
+            column.push(tile);
+        }
+        tiles.push(column);
+    }
+    tiles
+}
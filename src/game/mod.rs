@@ -1,117 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::process::exit;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use config_parsers::GameData;
-use crossterm::{event::{Event, KeyCode}, Result};
-use crate::game::screen::{Screen, VisualState};
+use config_parsers::files::ConfigFiles;
+use crossterm::Result;
+use crate::game::objects::StateValue;
+use crate::game::quests::QuestProgress;
+use crate::game::screen::Screen;
+use crate::game::screen::renderer::CrosstermRenderer;
 
+pub mod bundle;
 pub mod characters;
+pub mod check;
 pub mod dialogs;
+pub mod engine;
 pub mod maps;
 pub mod objects;
+pub mod playthrough;
+pub mod quests;
+pub mod saves;
 pub mod screen;
 pub mod config_parsers;
 
+// Seed of the random number generator for new game states
+const DEFAULT_RNG_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
 /// The base Game struct that contains all configuration for the game, but not any of its current state
 pub struct Game {
     game_data: GameData,
     game_state: GameState,
-    screen: Screen,
+    screen: Screen<CrosstermRenderer>,
 }
 
 impl Game {
 
-    /// Create an empty GameState from this Game. This is analogous to beginning a new game
-    pub fn initialize(config_path:std::path::PathBuf) -> Game {
+    /// Create an empty GameState from this Game. This is analogous to beginning a new game.
+    /// If a save file is provided, the game continues from that save instead.
+    /// The config files can come from a folder or from a game compiled into the executable.
+    pub fn initialize(files: &dyn ConfigFiles, load_path: Option<std::path::PathBuf>) -> Game {
         println!("Initializing Game");
 
-        let game_data = config_parsers::GameData::process_configs(config_path);
+        let save_path = match &load_path {
+            Some(path) => { path.clone() }
+            None => { saves::default_save_path(files.root()) }
+        };
+
+        let mut game_data = match config_parsers::GameData::process_configs(files) {
+            Ok(game_data) => { game_data }
+            Err(errors) => {
+                println!("ERROR: Found {} problem(s) in the game configuration files:", errors.len());
+                for error in errors {
+                    println!("  {}", error);
+                }
+                exit(1);
+            }
+        };
+
+        let mut game_state = GameState::new(&game_data, save_path);
+        game_state.seed_rng(SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0));
+
+        // Continue from the save file if one was requested
+        if let Some(load_path) = &load_path {
+            match saves::load_game(load_path, &mut game_data, &mut game_state) {
+                Ok(_) => {
+                    game_state.visual_state = VisualState::PlayingMap;
+                },
+                Err(err) => {
+                    println!("ERROR: Unable to load save file {:?}: {}", load_path, err);
+                    exit(1);
+                }
+            }
+        }
 
         // Create a display object
         let screen = Screen::initialize();
 
-        let game_state = GameState {
-            last_character_pressed: Ok(Event::Key(KeyCode::Enter.into())),
-            last_character_processed: true,
-            pre_exit: false,
-            do_exit: false,
-            visual_state: VisualState::StartScreen,
-            cursor_blink: true,
-            current_map: GameState::map_from_id(&game_data, &game_data.info.starting_map),
-            current_player_x: game_data.info.starting_position_x,
-            current_player_y: game_data.info.starting_position_x,
-            inventory_x: 0,
-            inventory_y: 0,
-            npc_dialog_id: "".to_string(),
-            dialog_message: "".to_string(),
-            dialog_option_0: "".to_string(),
-            dialog_option_1: "".to_string(),
-            dialog_selected: 0,
-            dialog_result_ready: false,
-            dialog_return_0: VisualState::StartScreen,
-            dialog_return_1: VisualState::StartScreen,
-            dialog_return_cancel: VisualState::StartScreen,
-        };
-
         Game{game_data, game_state, screen}
     }
 
-    /// Start playing the game by drawing the first screen, then running the main game loop
+    /// Start playing the game by drawing the first screen, then running the main game loop until
+    /// the player exits or something goes wrong with the terminal
     pub fn start(&mut self) {
 
         println!("Starting game");
-        match self.screen.draw(&mut self.game_data, &mut self.game_state) {
-            Ok(_) => {},
-            Err(_) => {
-                println!("ERROR: Problem encountered while drawing screen, exiting!");
-                self.end();
-            }
+        let result = self.run();
+        self.end();
+        if let Err(err) = result {
+            println!("ERROR: Problem encountered while running the game, exiting! ({})", err);
         }
-        self.run();
 
     }
 
     /// Main Game Loop
-    /// This gets called recursively to keep the game time moving forward
-    /// This function primarily handles reading in user input, handling screen refreshes, and
-    /// shutting the game down when requested.
-    fn run(&mut self) {
-
-        // Read input, but timeout after 500ms if no input is received.
-        // This timeout allows the game to continue processing things like screen updates without
-        // needing to rely on user input.
-        if crossterm::event::poll(std::time::Duration::from_millis(500)).expect("Error") {
-            self.game_state.last_character_pressed = crossterm::event::read();
-            self.game_state.last_character_processed = false;
-        }
+    /// Each pass through the loop waits for input until the next tick is due, handles any input
+    /// that arrived, updates the game once per tick, and then redraws the screen if anything
+    /// changed. Time keeps moving forward at the game's tick rate without needing user input.
+    fn run(&mut self) -> Result<()> {
+        let tick_rate = Duration::from_millis(self.game_data.info.tick_rate_ms);
+        let mut next_tick = Instant::now() + tick_rate;
+
+        self.screen.draw(&self.game_data, &self.game_state)?;
+
+        while !self.game_state.do_exit {
+            let mut redraw = false;
 
-        // Redraw the screen
-        match self.screen.draw(&mut self.game_data, &mut self.game_state) {
-            Ok(_) => {},
-            Err(_) => {
-                println!("ERROR: Problem encountered while drawing screen, exiting!");
-                self.end();
+            // Input: wait for a key press, but only until the next tick is due
+            let timeout = next_tick.saturating_duration_since(Instant::now());
+            if crossterm::event::poll(timeout)? {
+                let event = crossterm::event::read()?;
+                if let crossterm::event::Event::Resize(_, _) = event {
+                    // Resizing can mess up what is on the terminal, so show everything again
+                    self.screen.redraw_all();
+                }
+                self.screen.handle_input(&event, &mut self.game_data, &mut self.game_state);
+                redraw = true;
+            }
+
+            // Update: move the game forward once for every tick that has passed
+            if Instant::now() >= next_tick {
+                engine::tick(&mut self.game_data, &mut self.game_state);
+                next_tick += tick_rate;
+                // If the game fell far behind (such as the computer sleeping), don't try to catch up
+                if next_tick < Instant::now() {
+                    next_tick = Instant::now() + tick_rate;
+                }
+                redraw = true;
             }
-        }
 
-        // Check if an exit was requested
-        if self.game_state.do_exit {
-            self.end();
-        } else {
-            self.run();
+            // Render
+            if redraw && !self.game_state.do_exit {
+                self.screen.draw(&self.game_data, &self.game_state)?;
+            }
         }
+
+        Ok(())
     }
 
-    // Shut down the screen properly, and exit the program
+    // Shut down the screen properly so the terminal is usable again
     fn end(&self) {
         let _ = self.screen.end();
-        exit(0);
     }
 
 }
 
+/// Which screen of the game is being shown, and so which inputs are accepted
+#[derive(Clone, Debug, PartialEq)]
+pub enum VisualState {
+    StartScreen,
+    PlayingMap,
+    PlayingDialog,
+    PlayingInventory,
+    PlayingCharacterInteraction,
+    PlayingCharacterFight,
+    PlayingQuestLog,
+}
+
 /// The current state of a Game
 pub struct GameState {
-    pub last_character_pressed: Result<Event>,
-    pub last_character_processed: bool,
     pub pre_exit: bool,
     pub do_exit: bool,
     pub visual_state: VisualState,
@@ -123,16 +168,82 @@ pub struct GameState {
     pub inventory_y: usize,
     pub npc_dialog_id: String,
     pub dialog_message: String,
-    pub dialog_option_0: String,
-    pub dialog_option_1: String,
-    pub dialog_selected: u8,
+    // The buttons of the popup dialog box, each with the screen it returns to
+    pub dialog_options: Vec<(String, VisualState)>,
+    pub dialog_selected: usize,
     pub dialog_result_ready: bool,
-    pub dialog_return_0: VisualState,
-    pub dialog_return_1: VisualState,
-    pub dialog_return_cancel: VisualState
+    pub dialog_return_cancel: VisualState,
+    // Where the NPC being fought is on the current map
+    pub fight_x: usize,
+    pub fight_y: usize,
+    pub fight_selected: usize,
+    pub fight_turn: usize,
+    pub fight_message: String,
+    // The game's global variables, which start out as declared in game.yaml
+    pub variables: BTreeMap<String, StateValue>,
+    // Every quest that has started, by id
+    pub quests: BTreeMap<String, QuestProgress>,
+    // The ids of every NPC dialog the player has been shown
+    pub seen_dialogs: BTreeSet<String>,
+    pub quest_selected: usize,
+    // How many ticks have gone by since the game started, which decides when characters move
+    pub tick_count: u64,
+    // The state of the random number generator used for characters wandering around
+    pub rng_state: u64,
+    pub save_path: std::path::PathBuf,
 }
 
 impl GameState {
+    // Creates the state of a new game, on the start screen with the player at the starting position
+    pub fn new(game_data: &GameData, save_path: std::path::PathBuf) -> GameState {
+        GameState {
+            pre_exit: false,
+            do_exit: false,
+            visual_state: VisualState::StartScreen,
+            cursor_blink: true,
+            current_map: GameState::map_from_id(game_data, &game_data.info.starting_map),
+            current_player_x: game_data.info.starting_position_x,
+            current_player_y: game_data.info.starting_position_y,
+            inventory_x: 0,
+            inventory_y: 0,
+            npc_dialog_id: "".to_string(),
+            dialog_message: "".to_string(),
+            dialog_options: vec![],
+            dialog_selected: 0,
+            dialog_result_ready: false,
+            dialog_return_cancel: VisualState::StartScreen,
+            fight_x: 0,
+            fight_y: 0,
+            fight_selected: 0,
+            fight_turn: 0,
+            fight_message: "".to_string(),
+            variables: game_data.info.variables.clone(),
+            quests: GameState::starting_quests(game_data),
+            seen_dialogs: BTreeSet::new(),
+            quest_selected: 0,
+            tick_count: 0,
+            // The same seed every time, so that tests play out the same way. Games being played are
+            //    given a new seed when they start.
+            rng_state: DEFAULT_RNG_SEED,
+            save_path,
+        }
+    }
+
+    // The quests that are started at the beginning of a new game, which are the ones that don't have
+    //    anything they are waiting on to start
+    pub fn starting_quests(game_data: &GameData) -> BTreeMap<String, QuestProgress> {
+        game_data.quests.values()
+            .filter(|quest| quest.start.is_empty())
+            .map(|quest| (quest.id.clone(), QuestProgress::Active(0)))
+            .collect()
+    }
+
+    // Seeds the random number generator. The generator gets stuck on a seed of 0, so the default is
+    //    used instead.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng_state = if seed == 0 { DEFAULT_RNG_SEED } else { seed };
+    }
+
     // Converts a map ID into an actual map object index
     // TODO: Move to maps module
     pub fn map_from_id(game_data: &GameData, map_id: &str) -> usize {
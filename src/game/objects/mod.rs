@@ -1,84 +1,385 @@
+use std::collections::BTreeMap;
+use std::fmt;
 use serde::{Serialize,Deserialize};
 
 // Object data structure. These are items found in the game maps, or given to the player
 // via an interaction. These make up the majority of the game's interactivity.
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObjectState {
     pub name: String,
-    pub value: bool
+    pub value: StateValue
 }
 
-#[derive(Clone, Debug)]
+// The value of an object's state, which keeps the type it was given in the config file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StateValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl StateValue {
+    // The name of the value's type, as used in config problems
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            StateValue::Bool(_) => { "boolean" }
+            StateValue::Int(_) => { "integer" }
+            StateValue::Str(_) => { "string" }
+        }
+    }
+
+    // Returns true if the values are of the same type
+    pub fn same_type(&self, other: &StateValue) -> bool {
+        self.type_name() == other.type_name()
+    }
+}
+
+impl fmt::Display for StateValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateValue::Bool(b) => { write!(f, "{}", b) }
+            StateValue::Int(i) => { write!(f, "{}", i) }
+            StateValue::Str(s) => { write!(f, "'{}'", s) }
+        }
+    }
+}
+
+// How a prereq compares an object's state to its value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[default]
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    // The comparisons as they are written in config files. Longer symbols come first so that
+    //    "<=" isn't read as "<".
+    pub const SYMBOLS: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    // Returns true for comparisons that only work on integers
+    pub fn is_ordering(&self) -> bool {
+        !matches!(self, Comparison::Equal | Comparison::NotEqual)
+    }
+
+    // Compares the state's value to the prereq's value. Values of different types are never
+    //    equal, and only integers can be ordered.
+    pub fn compare(&self, state: &StateValue, value: &StateValue) -> bool {
+        match (self, state, value) {
+            (Comparison::Equal, _, _) => { state == value }
+            (Comparison::NotEqual, _, _) => { state != value }
+            (Comparison::Less, StateValue::Int(s), StateValue::Int(v)) => { s < v }
+            (Comparison::LessOrEqual, StateValue::Int(s), StateValue::Int(v)) => { s <= v }
+            (Comparison::Greater, StateValue::Int(s), StateValue::Int(v)) => { s > v }
+            (Comparison::GreaterOrEqual, StateValue::Int(s), StateValue::Int(v)) => { s >= v }
+            _ => { false }
+        }
+    }
+}
+
+// A state that must compare to a value for an interaction to happen
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatePrereq {
+    pub name: String,
+    #[serde(default)]
+    pub comparison: Comparison,
+    pub value: StateValue,
+}
+
+// How an action changes an object's state
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum StateOperation {
+    // Replaces the state with the value
+    #[default]
+    Set,
+    // Adds the value to an integer state
+    Increment,
+    // Subtracts the value from an integer state
+    Decrement,
+}
+
+// A change made to one of an object's states by an interaction
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateAction {
+    pub name: String,
+    #[serde(default)]
+    pub operation: StateOperation,
+    pub value: StateValue,
+}
+
+impl StateAction {
+    // The value that the action changes the current value to. Increments and decrements only change
+    //    integers, so they give nothing for any other type.
+    pub fn apply_to(&self, current: &StateValue) -> Option<StateValue> {
+        match (self.operation, current, &self.value) {
+            (StateOperation::Set, _, value) => { Some(value.clone()) }
+            (StateOperation::Increment, StateValue::Int(current), StateValue::Int(amount)) => {
+                Some(StateValue::Int(current.saturating_add(*amount)))
+            }
+            (StateOperation::Decrement, StateValue::Int(current), StateValue::Int(amount)) => {
+                Some(StateValue::Int(current.saturating_sub(*amount)))
+            }
+            _ => { None }
+        }
+    }
+}
+
+// Prereqs and actions can use the game's global variables instead of the object's own states by
+//    putting this in front of the variable's name, like `global.quest_stage`
+pub const GLOBAL_PREFIX: &str = "global.";
+
+// The name of the global variable that a prereq or action uses, if it uses one
+pub fn global_variable(name: &str) -> Option<&str> {
+    name.strip_prefix(GLOBAL_PREFIX)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ObjectInteraction {
     ObjectInteractionActivate(ObjectInteractionActivate),
     ObjectInteractionObjectUse(ObjectInteractionObjectUse)
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObjectInteractionActivate {
     pub category: String,
-    pub prereqs: Vec<ObjectState>,
+    pub prereqs: Vec<StatePrereq>,
+    #[serde(default)]
+    pub self_action: Vec<StateAction>,
     pub destination: Option<String>
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObjectInteractionObjectUse{
     pub foreign_object_id: String,
-    pub self_action: Vec<ObjectState>,
+    pub self_action: Vec<StateAction>,
     pub consume_item: bool
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObjectInteractionWorld {
 
 }
 
-// TODO: Future Enhancement: Object's categories should probably be some sort of type rather than arbitrary strings
-/*pub enum ObjectCategory {
-"simple",
-"collidable"
-"collectable"
-"door"
-}*/
+// What kind of object something is, which decides how it behaves in the game
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectCategory {
+    // Decoration that can be walked over
+    Simple,
+    // Decoration that can't be walked over
+    Collidable,
+    // Picked up into the inventory
+    Collectable,
+    // Takes the player somewhere else, and can always be walked through
+    Door,
+    // Holds other objects, which the player takes when opening it
+    Container,
+    // Shows its text
+    Sign,
+    // Flips its `on` state each time it is pulled
+    Lever,
+}
+
+impl ObjectCategory {
+    pub const ALL: [ObjectCategory; 7] = [
+        ObjectCategory::Simple,
+        ObjectCategory::Collidable,
+        ObjectCategory::Collectable,
+        ObjectCategory::Door,
+        ObjectCategory::Container,
+        ObjectCategory::Sign,
+        ObjectCategory::Lever,
+    ];
 
-#[derive(Clone, Debug)]
+    // The name used for the category in config files
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObjectCategory::Simple => { "simple" }
+            ObjectCategory::Collidable => { "collidable" }
+            ObjectCategory::Collectable => { "collectable" }
+            ObjectCategory::Door => { "door" }
+            ObjectCategory::Container => { "container" }
+            ObjectCategory::Sign => { "sign" }
+            ObjectCategory::Lever => { "lever" }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ObjectCategory> {
+        ObjectCategory::ALL.into_iter().find(|category| category.name() == name)
+    }
+
+    // Returns true if the player can walk onto an object of this category on a tile that is or isn't
+    //    passable. Doors can be walked through even when they are set into a wall.
+    pub fn passable(&self, tile_passable: bool) -> bool {
+        match self {
+            ObjectCategory::Door => { true }
+            ObjectCategory::Collidable => { false }
+            _ => { tile_passable }
+        }
+    }
+
+    // What interacting with the object does, shown while the player stands on it
+    pub fn interact_prompt(&self) -> Option<&'static str> {
+        match self {
+            ObjectCategory::Simple | ObjectCategory::Collidable => { None }
+            ObjectCategory::Collectable => { Some("Pick up") }
+            ObjectCategory::Door => { Some("Go through") }
+            ObjectCategory::Container => { Some("Open") }
+            ObjectCategory::Sign => { Some("Read") }
+            ObjectCategory::Lever => { Some("Pull") }
+        }
+    }
+}
+
+// An object as it is in the game. Each object placed in a map is its own instance of the object
+//    defined in the object's config file (its prototype), which it shares an id with. The instance id
+//    tells apart the different placements of the same object.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Object {
     pub id: String,
+    pub instance_id: String,
     pub name: String,
-    pub category: String,
+    pub category: ObjectCategory,
     pub icon: char,
     pub state: Vec<ObjectState>,
     pub interactions: Vec<ObjectInteraction>,
+    // What signs say
+    #[serde(default)]
+    pub text: String,
+    // The ids of the objects held by containers
+    #[serde(default)]
+    pub contents: Vec<String>,
+    // How the object can be worn or wielded, if it can be
+    #[serde(default)]
+    pub equip: Option<Equipment>,
+    // Whether the character holding the object in its inventory has it equipped
+    #[serde(default)]
+    pub equipped: bool,
+    // What using the object up does to the attributes of whoever it is used on. Objects without
+    //    any effects can't be consumed.
+    #[serde(default)]
+    pub consume: Vec<AttributeEffect>,
+}
+
+// The equipment slot an object goes in when it is equipped, and how it changes the attributes of
+//    the character who has it equipped
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Equipment {
+    pub slot: String,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttributeModifier {
+    pub attribute_id: String,
+    pub amount: i64,
+}
+
+// A change to an attribute made by a consumable object. Without a duration, the change is made to
+//    the attribute's current value for good. With one, it is a buff that only lasts that long.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttributeEffect {
+    pub attribute_id: String,
+    pub amount: i64,
+    pub duration: Option<EffectDuration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectDuration {
+    // Rounds of fighting
+    Turns(u64),
+    // Game ticks that go by while the player is on the map
+    Ticks(u64),
+}
+
+// The state that levers flip each time they are pulled
+pub const LEVER_STATE: &str = "on";
+
+// The instance id given to an object placed in a map without one of its own, such as
+//    "chest@basement:3,4" for a chest placed at (3, 4) in the basement
+pub fn default_instance_id(object_id: &str, map_id: &str, x: usize, y: usize) -> String {
+    format!("{}@{}:{},{}", object_id, map_id, x, y)
 }
 
 impl Object {
-    // Checks a list of state prereqs to see if they are all met in the object.
-    pub fn prereqs_met(&self, prereqs: &[ObjectState]) -> bool {
+    // Checks a list of state prereqs to see if they are all met in the object, or in the global
+    //    variables for prereqs that use them.
+    pub fn prereqs_met(&self, prereqs: &[StatePrereq], variables: &BTreeMap<String, StateValue>) -> bool {
         for prereq in prereqs {
-            if self.get_state(&prereq.name) != prereq.value {
-                return false;
+            let value = match global_variable(&prereq.name) {
+                Some(variable) => { variables.get(variable) }
+                None => { self.state_value(&prereq.name) }
+            };
+            match value {
+                Some(value) if prereq.comparison.compare(value, &prereq.value) => {}
+                _ => { return false; }
             }
         }
         true
     }
 
+    // Returns true if activating the object can take the player somewhere else
+    pub fn travels(&self) -> bool {
+        self.interactions.iter().any(|interaction| match interaction {
+            ObjectInteraction::ObjectInteractionActivate(activate) => { activate.category == "travel" }
+            ObjectInteraction::ObjectInteractionObjectUse(_) => { false }
+        })
+    }
+
+    // The map named by the object's travel interaction, if it names one
+    pub fn travel_destination(&self) -> Option<&str> {
+        self.interactions.iter().find_map(|interaction| match interaction {
+            ObjectInteraction::ObjectInteractionActivate(activate) if activate.category == "travel" => {
+                activate.destination.as_deref()
+            }
+            _ => { None }
+        })
+    }
+
     // Returns state value from state id
+    pub fn state_value(&self, state_name: &str) -> Option<&StateValue> {
+        self.state.iter().find(|s| s.name == state_name).map(|s| &s.value)
+    }
+
+    // Returns true if the state with state_name is a boolean state that is true
     pub fn get_state(&self, state_name: &str) -> bool {
-        for s in &self.state {
-            if s.name.eq(state_name) {
-                return s.value;
-            }
-        }
-        false
+        matches!(self.state_value(state_name), Some(StateValue::Bool(true)))
     }
 
     // Sets state with state_name to state_val.
     //    If state_name does not exist, does nothing.
-    pub fn set_state(&mut self, state_name: String, state_val: bool) {
+    pub fn set_state(&mut self, state_name: String, state_val: StateValue) {
         for s in &mut self.state {
             if s.name.eq(&state_name) {
-                s.value = state_val;
+                s.value = state_val.clone();
+            }
+        }
+    }
+
+    // Changes one of the object's states as the action says. Increments and decrements only change
+    //    integer states. If the state does not exist, such as for actions on global variables, does
+    //    nothing.
+    pub fn apply_action(&mut self, action: &StateAction) {
+        for s in &mut self.state {
+            if s.name != action.name {
+                continue;
+            }
+            if let Some(value) = action.apply_to(&s.value) {
+                s.value = value;
             }
         }
     }
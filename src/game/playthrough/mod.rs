@@ -0,0 +1,961 @@
+use std::path::Path;
+use crate::game::{GameState, VisualState, saves};
+use crate::game::characters::Character;
+use crate::game::config_parsers::GameData;
+use crate::game::config_parsers::files::{ConfigFiles, FolderFiles};
+use crate::game::engine::{self, Direction};
+use crate::game::maps::MapData;
+use crate::game::objects::Object;
+use crate::game::screen::Screen;
+use crate::game::screen::renderer::BufferRenderer;
+
+// Headless playthroughs of a game for tests. A playthrough loads a game, then feeds a scripted
+// sequence of steps into the engine without a terminal, so that tests can check the state of the
+// game afterwards. Each step can only be taken on the screen a player would take it on, so a
+// script that wanders off course fails at the step where it went wrong. The screen is drawn into
+// memory after every step, just like the game redraws after every key press, so tests can also
+// check what the player would see.
+
+#[derive(Clone, Debug)]
+pub enum Step {
+    // Leave the start screen
+    Start,
+    // Move some number of spaces on the map, failing if anything is in the way or a fight starts
+    //    before the last space
+    Move(Direction, usize),
+    // Interact with whatever is in the player's spot on the map
+    Interact,
+    OpenInventory,
+    OpenQuestLog,
+    // Select the inventory slot at (x, y)
+    SelectSlot(usize, usize),
+    // Use the selected inventory item on whatever is in the player's spot on the map
+    UseItem,
+    // Equip the selected inventory item, or unequip it if it is already equipped
+    ToggleEquipped,
+    // Choose one of the options of the NPC dialog being shown
+    ChooseDialogOption(usize),
+    // Choose one of the two buttons of the popup dialog box being shown
+    ChooseMessageOption(usize),
+    // Use one of the player's attacks in a fight
+    Attack(usize),
+    // Leave the inventory, the quest log, or an NPC conversation and go back to the map
+    ReturnToMap,
+    // Let the given number of ticks pass
+    Tick(usize),
+}
+
+pub struct Playthrough {
+    pub game_data: GameData,
+    pub game_state: GameState,
+    screen: Screen<BufferRenderer>,
+}
+
+impl Playthrough {
+    // Loads the game in the config folder and starts a new game, on the start screen
+    pub fn load(config_path: &Path) -> Result<Playthrough, String> {
+        Playthrough::from_files(&FolderFiles::new(config_path))
+    }
+
+    // Loads the game from the config files and starts a new game, on the start screen. Every
+    //    error in the config files is returned together.
+    pub fn from_files(files: &dyn ConfigFiles) -> Result<Playthrough, String> {
+        let (game_data, problems) = GameData::load_configs(files);
+        let errors: Vec<String> = problems.iter().filter(|p| p.is_error()).map(|p| p.to_string()).collect();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+
+        let game_state = GameState::new(&game_data, saves::default_save_path(files.root()));
+        let mut playthrough = Playthrough {
+            game_data,
+            game_state,
+            screen: Screen::new(BufferRenderer::new(80, 20)),
+        };
+        playthrough.draw()?;
+        Ok(playthrough)
+    }
+
+    // Takes every step in order, stopping at the first one that can't be taken
+    pub fn run(&mut self, steps: &[Step]) -> Result<(), String> {
+        for (i, step) in steps.iter().enumerate() {
+            if let Err(err) = self.step(step) {
+                return Err(format!("Step {} ({:?}) failed: {}", i, step, err));
+            }
+        }
+        Ok(())
+    }
+
+    // Takes a single step, then draws the screen
+    pub fn step(&mut self, step: &Step) -> Result<(), String> {
+        self.take_step(step)?;
+        self.draw()
+    }
+
+    fn take_step(&mut self, step: &Step) -> Result<(), String> {
+        let game_data = &mut self.game_data;
+        let game_state = &mut self.game_state;
+        match step {
+            Step::Start => {
+                expect_screen(game_state, &[VisualState::StartScreen])?;
+                engine::start_game(game_state);
+            }
+            Step::Move(direction, spaces) => {
+                for _ in 0..*spaces {
+                    // Walking into a hostile character stops the player, so the rest of the move fails
+                    expect_screen(game_state, &[VisualState::PlayingMap])?;
+                    if !engine::move_player(game_data, game_state, *direction) {
+                        return Err(format!("Unable to move {:?} from ({}, {}) in map '{}'", direction,
+                                           game_state.current_player_x, game_state.current_player_y,
+                                           game_data.maps[game_state.current_map].info.id));
+                    }
+                }
+            }
+            Step::Interact => {
+                expect_screen(game_state, &[VisualState::PlayingMap])?;
+                engine::interact(game_data, game_state);
+            }
+            Step::OpenInventory => {
+                expect_screen(game_state, &[VisualState::PlayingMap])?;
+                engine::open_inventory(game_state);
+            }
+            Step::OpenQuestLog => {
+                expect_screen(game_state, &[VisualState::PlayingMap])?;
+                engine::open_quest_log(game_state);
+            }
+            Step::SelectSlot(x, y) => {
+                expect_screen(game_state, &[VisualState::PlayingInventory])?;
+                let inventory = &game_data.info.player.as_ref().unwrap().inventory;
+                if *x >= inventory.len() || *y >= inventory[*x].len() {
+                    return Err(format!("There is no inventory slot at ({}, {})", x, y));
+                }
+                game_state.inventory_x = *x;
+                game_state.inventory_y = *y;
+            }
+            Step::UseItem => {
+                expect_screen(game_state, &[VisualState::PlayingInventory])?;
+                engine::use_item(game_data, game_state, game_state.inventory_x, game_state.inventory_y);
+            }
+            Step::ToggleEquipped => {
+                expect_screen(game_state, &[VisualState::PlayingInventory])?;
+                engine::toggle_equipped(game_data, game_state, game_state.inventory_x, game_state.inventory_y);
+            }
+            Step::ChooseDialogOption(option) => {
+                expect_screen(game_state, &[VisualState::PlayingCharacterInteraction])?;
+                match engine::visible_dialog_options(game_data, game_state).get(*option) {
+                    Some((_, true)) => {}
+                    Some((_, false)) => {
+                        return Err(format!("The option {} of the dialog '{}' can't be chosen", option,
+                                           game_state.npc_dialog_id));
+                    }
+                    None => {
+                        return Err(format!("The dialog '{}' has no option {}", game_state.npc_dialog_id, option));
+                    }
+                }
+                engine::choose_dialog_option(game_data, game_state, *option);
+            }
+            Step::ChooseMessageOption(option) => {
+                expect_screen(game_state, &[VisualState::PlayingDialog])?;
+                if *option >= game_state.dialog_options.len() {
+                    return Err(format!("The message box has no option {}", option));
+                }
+                engine::choose_message_option(game_state, *option);
+            }
+            Step::Attack(attack) => {
+                expect_screen(game_state, &[VisualState::PlayingCharacterFight])?;
+                engine::attack(game_data, game_state, *attack);
+            }
+            Step::ReturnToMap => {
+                expect_screen(game_state, &[VisualState::PlayingInventory, VisualState::PlayingQuestLog,
+                                            VisualState::PlayingCharacterInteraction])?;
+                engine::return_to_map(game_state);
+            }
+            Step::Tick(ticks) => {
+                for _ in 0..*ticks {
+                    engine::tick(game_data, game_state);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Draws the current screen again, such as after changing the game state directly
+    pub fn draw(&mut self) -> Result<(), String> {
+        match self.screen.draw(&self.game_data, &self.game_state) {
+            Ok(_) => { Ok(()) }
+            Err(err) => { Err(format!("Unable to draw the screen: {}", err)) }
+        }
+    }
+
+    // The last frame drawn, as text
+    pub fn frame(&self) -> String {
+        self.screen.renderer().text()
+    }
+
+    // The id of the map the player is in
+    pub fn current_map_id(&self) -> &str {
+        &self.game_data.maps[self.game_state.current_map].info.id
+    }
+
+    pub fn player_position(&self) -> (u16, u16) {
+        (self.game_state.current_player_x, self.game_state.current_player_y)
+    }
+
+    pub fn player(&self) -> &Character {
+        self.game_data.info.player.as_ref().unwrap()
+    }
+
+    // The ids of every item in the player's inventory, going down each column of slots in turn
+    pub fn inventory_ids(&self) -> Vec<&str> {
+        self.player().inventory.iter()
+            .flatten()
+            .filter_map(|slot| slot.as_ref().map(|object| object.id.as_str()))
+            .collect()
+    }
+
+    // The object at (x, y) in the map with map_id, if there is one
+    pub fn object_at(&self, map_id: &str, x: usize, y: usize) -> Option<&Object> {
+        match self.space(map_id, x, y) {
+            Some(MapData::Object(object)) => { Some(object) }
+            _ => { None }
+        }
+    }
+
+    // The object with the given instance id, wherever it is in the game's maps
+    pub fn object(&self, instance_id: &str) -> Option<&Object> {
+        let (m, x, y) = self.game_data.find_instance(instance_id)?;
+        match &self.game_data.maps[m].grid[x][y] {
+            Some(MapData::Object(object)) => { Some(object) }
+            _ => { None }
+        }
+    }
+
+    // The character at (x, y) in the map with map_id, if there is one
+    pub fn character_at(&self, map_id: &str, x: usize, y: usize) -> Option<&Character> {
+        match self.space(map_id, x, y) {
+            Some(MapData::Character(character)) => { Some(character) }
+            _ => { None }
+        }
+    }
+
+    // Where the character with character_id is in the map with map_id, if it is there
+    pub fn character_position(&self, map_id: &str, character_id: &str) -> Option<(usize, usize)> {
+        let map = self.game_data.maps.iter().find(|m| m.info.id == map_id)?;
+        for (x, column) in map.grid.iter().enumerate() {
+            for (y, space) in column.iter().enumerate() {
+                if matches!(space, Some(MapData::Character(character)) if character.id == character_id) {
+                    return Some((x, y));
+                }
+            }
+        }
+        None
+    }
+
+    fn space(&self, map_id: &str, x: usize, y: usize) -> Option<&MapData> {
+        let map = self.game_data.maps.iter().find(|m| m.info.id == map_id)?;
+        map.grid.get(x)?.get(y)?.as_ref()
+    }
+}
+
+// Makes sure the game is on one of the expected screens before a step is taken
+fn expect_screen(game_state: &GameState, expected: &[VisualState]) -> Result<(), String> {
+    if expected.contains(&game_state.visual_state) {
+        return Ok(());
+    }
+    let mut message = format!("Expected to be on {:?}, but the game is on {:?}", expected, game_state.visual_state);
+    if game_state.visual_state == VisualState::PlayingDialog {
+        message = format!("{} showing \"{}\"", message, game_state.dialog_message);
+    }
+    Err(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use super::*;
+    use super::Step::*;
+    use crate::game::config_parsers::files::MemoryFiles;
+    use crate::game::config_parsers::tests::example_game_yaml_with_variables;
+    use crate::game::characters::Buff;
+    use crate::game::engine::Direction::*;
+    use crate::game::objects::{EffectDuration, LEVER_STATE, StateValue};
+    use crate::game::quests::QuestProgress;
+
+    fn example_game() -> Playthrough {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+        match Playthrough::load(&config_path) {
+            Ok(playthrough) => { playthrough }
+            Err(err) => { panic!("Unable to load the example game:\n{}", err); }
+        }
+    }
+
+    // Plays the example game from start to finish: giving the hat to the antagonist, fetching the
+    //    key from the secondary room, and unlocking the door down to the basement
+    #[test]
+    fn example_game_playthrough() {
+        let mut game = example_game();
+        assert_eq!(game.current_map_id(), "Main Room");
+        assert_eq!(game.player_position(), (1, 1));
+
+        // Pick up the hat
+        game.run(&[Start, Move(Right, 11), Move(Down, 4), Interact, ChooseMessageOption(1)]).unwrap();
+        assert_eq!(game.inventory_ids(), vec!["hat"]);
+        assert!(game.object_at("Main Room", 12, 5).is_none());
+        assert_eq!(game.game_state.quests.get("basement"), Option::<&QuestProgress>::Some(&QuestProgress::Active(1)));
+
+        // Give the hat to the antagonist, which changes what they say
+        game.run(&[Move(Up, 1), Move(Left, 1), Interact, ChooseDialogOption(1), SelectSlot(0, 0), UseItem,
+                   ReturnToMap]).unwrap();
+        assert!(game.inventory_ids().is_empty());
+        assert_eq!(game.character_at("Main Room", 11, 4).unwrap().dialog_id, "AA give hat");
+
+        // The door to the basement starts out locked
+        assert!(!game.object_at("Main Room", 10, 9).unwrap().get_state("unlocked"));
+
+        // Get the key from the secondary room
+        game.run(&[Move(Left, 6), Move(Up, 4), Interact]).unwrap();
+        assert_eq!(game.current_map_id(), "Secondary Room");
+        assert_eq!(game.player_position(), (6, 4));
+        game.run(&[Move(Up, 3), Move(Left, 5), Interact, ChooseMessageOption(1)]).unwrap();
+        assert_eq!(game.inventory_ids(), vec!["key"]);
+
+        // Go back and try the locked door, then unlock it with the key
+        game.run(&[Move(Right, 5), Move(Down, 3), Interact]).unwrap();
+        assert_eq!(game.current_map_id(), "Main Room");
+        game.run(&[Move(Down, 8), Move(Right, 5), Move(Down, 1), Interact]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "The door is locked! Try to find a key.");
+        game.run(&[ChooseMessageOption(0), SelectSlot(0, 0), UseItem]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You used the Key on the Door!\n\nQuest complete: Into the Basement");
+        game.run(&[ChooseMessageOption(0), ReturnToMap]).unwrap();
+        assert_eq!(game.game_state.quests.get("basement"), Option::<&QuestProgress>::Some(&QuestProgress::Completed));
+        assert_eq!(game.player().get_attribute_value("skill_1"), 2);
+        assert!(game.inventory_ids().is_empty());
+        assert!(game.object_at("Main Room", 10, 9).unwrap().get_state("unlocked"));
+        assert!(game.object("door_1@basement:5,0").unwrap().get_state("unlocked"));
+
+        // Go down to the basement
+        game.run(&[Interact]).unwrap();
+        assert_eq!(game.current_map_id(), "basement");
+        assert_eq!(game.player_position(), (5, 0));
+
+        // The basement has a pool of water in the way
+        assert!(game.run(&[Move(Down, 3)]).is_err());
+        assert_eq!(game.player_position(), (5, 2));
+
+        // The teleporter leads behind the wall, but not back
+        assert_eq!(game.object("basement_teleporter").unwrap().name, "Old Teleporter");
+        game.run(&[Move(Left, 4), Move(Down, 6), Interact]).unwrap();
+        assert_eq!(game.current_map_id(), "basement");
+        assert_eq!(game.player_position(), (17, 5));
+
+        // Behind the wall, a sign points out the chest, which has a lantern inside
+        game.run(&[Move(Up, 2), Interact]).unwrap();
+        assert!(game.game_state.dialog_message.starts_with("Whoever finds this chest"));
+        game.run(&[ChooseMessageOption(1), Move(Down, 4), Interact]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You found the Lantern in the Chest!");
+        assert_eq!(game.inventory_ids(), vec!["lantern"]);
+        game.run(&[ChooseMessageOption(1), Interact]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "The Chest is empty.");
+        assert!(game.object_at("basement", 17, 7).unwrap().contents.is_empty());
+    }
+
+    // Fights the antagonist, who goes down just before the player does
+    #[test]
+    fn example_game_fight() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 10), Move(Down, 3), Interact, ChooseDialogOption(0),
+                   ChooseDialogOption(1)]).unwrap();
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingCharacterFight);
+
+        game.run(&vec![Attack(0); 24]).unwrap();
+        assert_eq!(game.player().get_attribute_value("health"), 4);
+        assert_eq!(game.character_at("Main Room", 11, 4).unwrap().get_attribute_value("health"), 4);
+
+        game.run(&[Attack(0)]).unwrap();
+        assert!(game.character_at("Main Room", 11, 4).is_none());
+        assert_eq!(game.game_state.dialog_message, "You defeated the Angry Antagonist!");
+    }
+
+    // Loads the example game with extra files, and with the given objects placed in the main room
+    //    as (id, x, y)
+    fn example_game_with(placements: &[(&str, u16, u16)], extra_files: &[(&str, &str)]) -> Playthrough {
+        let mut files = MemoryFiles::example_game_with(extra_files);
+        let mut main_room = files.file("maps/room_1.yaml").unwrap().to_string();
+        for (id, x, y) in placements {
+            main_room.push_str(&format!("  - id: \"{}\"\n    position:\n      x: {}\n      y: {}\n", id, x, y));
+        }
+        files.add_file("maps/room_1.yaml", main_room);
+        Playthrough::from_files(&files).unwrap()
+    }
+
+    // Turns a crank placed next to where the player starts, which only opens once it has been turned
+    //    three times
+    #[test]
+    fn activating_changes_states() {
+        let mut game = example_game_with(&[("crank", 2, 1)], &[("objects/crank.yaml", r##"
+id: "crank"
+name: "Crank"
+category: "simple"
+icon: "%"
+state:
+  - id: "turns"
+    default: 0
+  - id: "gate"
+    default: "closed"
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - turns: "< 3"
+      self_action:
+        - turns: "+= 1"
+    - category: "use"
+      prereqs:
+        - turns: ">= 3"
+        - gate: "!= open"
+      self_action:
+        - gate: "open"
+"##)]);
+        game.run(&[Start, Move(Right, 1), Interact, Interact, Interact]).unwrap();
+        let crank = game.object_at("Main Room", 2, 1).unwrap();
+        assert_eq!(crank.state_value("turns"), Some(&StateValue::Int(3)));
+        assert_eq!(crank.state_value("gate"), Some(&StateValue::Str("closed".to_string())));
+
+        game.run(&[Interact, Interact]).unwrap();
+        let crank = game.object_at("Main Room", 2, 1).unwrap();
+        assert_eq!(crank.state_value("turns"), Some(&StateValue::Int(3)));
+        assert_eq!(crank.state_value("gate"), Some(&StateValue::Str("open".to_string())));
+    }
+
+    // Talks the antagonist into trading the key for the hat, which unlocks another option that opens
+    //    the basement door and sends the player down there
+    #[test]
+    fn dialog_options_have_conditions_and_effects() {
+        let game_yaml = example_game_yaml_with_variables("  traded: false\n");
+        let mut game = example_game_with(&[], &[("game.yaml", &game_yaml), ("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "What do you want?"
+   options:
+      - dialog: "Want to trade the key for a hat?"
+        conditions:
+          - has_item: "hat"
+        when_unmet: "grey"
+        effects:
+          - take_item: "hat"
+          - give_item: "key"
+          - set_flag: "traded"
+          - set_dialog: "AA give hat"
+        next: "exit"
+      - dialog: "Bye."
+        next: "exit"
+ - id: "AA give hat"
+   npc_dialog: "This is an awesome hat, thanks."
+   options:
+      - dialog: "Could you open the basement for me?"
+        conditions:
+          - flag: "traded"
+          - state: { object: "door_1@Main Room:10,9", state: "unlocked", value: false }
+        effects:
+          - set_state: { object: "door_1@Main Room:10,9", state: "unlocked", value: true }
+          - change_attribute: { id: "skill_3", amount: 1 }
+          - travel: { map: "basement", spawn: "behind_wall" }
+        next: "exit"
+      - dialog: "Look how skilled I am."
+        conditions:
+          - attribute: { id: "skill_3", value: ">= 8" }
+        next: "exit"
+      - dialog: "Bye."
+        next: "exit"
+"##)]);
+        let option_texts = |game: &Playthrough| engine::visible_dialog_options(&game.game_data, &game.game_state)
+            .iter().map(|(option, met)| (option.dialog.clone(), *met)).collect::<Vec<(String, bool)>>();
+
+        // Without the hat, trading is greyed out
+        game.run(&[Start, Move(Right, 10), Move(Down, 3), Interact]).unwrap();
+        assert_eq!(option_texts(&game), vec![("Want to trade the key for a hat?".to_string(), false),
+                                             ("Bye.".to_string(), true)]);
+        assert!(game.run(&[ChooseDialogOption(0)]).is_err());
+
+        game.run(&[ChooseDialogOption(1), Move(Right, 1), Move(Down, 1), Interact, ChooseMessageOption(1),
+                   Move(Up, 1), Move(Left, 1), Interact, ChooseDialogOption(0)]).unwrap();
+        assert_eq!(game.inventory_ids(), vec!["key"]);
+        assert_eq!(game.game_state.variables.get("traded"), Option::<&StateValue>::Some(&StateValue::Bool(true)));
+        assert_eq!(game.character_at("Main Room", 11, 4).unwrap().dialog_id, "AA give hat");
+
+        // Getting the key moves the example game's quest along
+        assert_eq!(game.game_state.dialog_message, "Quest updated: Into the Basement");
+
+        // Once traded, the antagonist can open the basement, but only while it is locked
+        game.run(&[ChooseMessageOption(1), Interact]).unwrap();
+        assert_eq!(option_texts(&game).len(), 2);
+        game.run(&[ChooseDialogOption(0)]).unwrap();
+        assert!(game.object("door_1@Main Room:10,9").unwrap().get_state("unlocked"));
+        assert_eq!(game.player().get_attribute_value("skill_3"), 8);
+        assert_eq!(game.game_state.dialog_message, "Quest complete: Into the Basement");
+        game.run(&[ChooseMessageOption(1)]).unwrap();
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingMap);
+        assert_eq!(game.current_map_id(), "basement");
+        assert_eq!(game.player_position(), (17, 5));
+        assert_eq!(option_texts(&game), vec![("Look how skilled I am.".to_string(), true),
+                                             ("Bye.".to_string(), true)]);
+    }
+
+    // Rings a bell until the antagonist notices, which uses global variables from both the bell and
+    //    the dialog, and checks that the variables are saved
+    #[test]
+    fn global_variables_are_shared() {
+        let game_yaml = example_game_yaml_with_variables("  bells_rung: 0\n  heard_bells: false\n");
+        let files = [("game.yaml", game_yaml.as_str()), ("objects/bell.yaml", r##"
+id: "bell"
+name: "Bell"
+category: "simple"
+icon: "B"
+state:
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - global.heard_bells: false
+      self_action:
+        - global.bells_rung: "+= 1"
+"##), ("dialogs/dialog.yaml", r##"
+ - id: "AA start"
+   npc_dialog: "What do you want?"
+   options:
+      - dialog: "Did you hear the bells?"
+        conditions:
+          - variable: { id: "bells_rung", value: ">= 2" }
+        effects:
+          - set_variable: { id: "bells_rung", value: "-= 2" }
+          - set_flag: "heard_bells"
+        next: "exit"
+      - dialog: "Bye."
+        next: "exit"
+ - id: "AA give hat"
+   npc_dialog: "Thanks."
+   options:
+      - dialog: "Bye."
+        next: "exit"
+"##)];
+        let mut game = example_game_with(&[("bell", 1, 2)], &files);
+        let variable = |game: &Playthrough, id: &str| game.game_state.variables.get(id).cloned();
+
+        game.run(&[Start, Move(Down, 1), Interact, Interact, Interact]).unwrap();
+        assert_eq!(variable(&game, "bells_rung"), Option::<StateValue>::Some(StateValue::Int(3)));
+
+        game.run(&[Move(Up, 1), Move(Right, 10), Move(Down, 3), Interact, ChooseDialogOption(0)]).unwrap();
+        assert_eq!(variable(&game, "bells_rung"), Option::<StateValue>::Some(StateValue::Int(1)));
+        assert_eq!(variable(&game, "heard_bells"), Option::<StateValue>::Some(StateValue::Bool(true)));
+
+        // Once the antagonist has heard the bells, the bell can't be rung anymore
+        game.run(&[Move(Up, 3), Move(Left, 10), Move(Down, 1), Interact]).unwrap();
+        assert_eq!(game.player_position(), (1, 2));
+        assert_eq!(variable(&game, "bells_rung"), Option::<StateValue>::Some(StateValue::Int(1)));
+
+        let save = saves::SaveData::from_game(&game.game_data, &game.game_state).unwrap();
+        let mut loaded = example_game_with(&[("bell", 1, 2)], &files);
+        assert_eq!(variable(&loaded, "heard_bells"), Option::<StateValue>::Some(StateValue::Bool(false)));
+        save.apply(&mut loaded.game_data, &mut loaded.game_state).unwrap();
+        assert_eq!(loaded.game_state.variables, game.game_state.variables);
+    }
+
+    // Starts a quest by hearing about the key, then finishes it through the dialog, which gives the
+    //    player a lantern. Quest progress and the dialogs seen are saved along with everything else.
+    #[test]
+    fn quests_move_along_as_the_player_plays() {
+        let files = [("quests/key.yaml", r##"
+id: "key"
+name: "The Key"
+start:
+  - seen_dialog: "d0"
+stages:
+  - description: "Ask the Angry Antagonist about the key"
+    conditions:
+      - flag: "asked_about_key"
+rewards:
+  - give_item: "lantern"
+"##)];
+        let mut game = example_game_with(&[], &files);
+        let progress = |game: &Playthrough, quest_id: &str| game.game_state.quests.get(quest_id).cloned();
+        assert_eq!(progress(&game, "key"), Option::<QuestProgress>::None);
+        assert_eq!(progress(&game, "basement"), Option::<QuestProgress>::Some(QuestProgress::Active(0)));
+
+        game.run(&[Start, Move(Right, 10), Move(Down, 3), Interact, ChooseDialogOption(0)]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "New quest: The Key");
+        assert_eq!(progress(&game, "key"), Option::<QuestProgress>::Some(QuestProgress::Active(0)));
+
+        game.run(&[ChooseMessageOption(1), ChooseDialogOption(2)]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "Quest complete: The Key");
+        assert_eq!(game.game_state.variables.get("asked_about_key"), Option::<&StateValue>::Some(&StateValue::Bool(true)));
+        assert_eq!(progress(&game, "key"), Option::<QuestProgress>::Some(QuestProgress::Completed));
+        assert_eq!(game.inventory_ids(), vec!["lantern"]);
+
+        game.run(&[ChooseMessageOption(1)]).unwrap();
+        assert_eq!(game.game_state.npc_dialog_id, "d1");
+
+        // Quests being worked on are listed before completed ones
+        game.run(&[ChooseDialogOption(0), OpenQuestLog]).unwrap();
+        let log = engine::quest_log(&game.game_data, &game.game_state).iter()
+            .map(|(quest, _)| quest.name.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(log, vec!["Into the Basement", "The Key"]);
+        game.run(&[ReturnToMap]).unwrap();
+
+        let save = saves::SaveData::from_game(&game.game_data, &game.game_state).unwrap();
+        let mut loaded = example_game_with(&[], &files);
+        save.apply(&mut loaded.game_data, &mut loaded.game_state).unwrap();
+        assert_eq!(loaded.game_state.quests, game.game_state.quests);
+        assert!(loaded.game_state.seen_dialogs.contains("d0"));
+    }
+
+    // A character with 10 health and no attacks, with the rest of its config given as yaml
+    fn npc(id: &str, config: &str) -> String {
+        format!(r##"
+id: "{}"
+name: "{}"
+icon: "M"
+inventory_size:
+  width: 0
+  height: 0
+traits:
+  - id: "health"
+    display_name: "Health"
+    starting_value: 10
+    max_value: 10
+interactions:
+  attacks: []
+  object_use: []
+{}
+"##, id, id, config)
+    }
+
+    // A character that moves in the way given, taking a step every tick
+    fn moving_character(id: &str, behavior: &str) -> String {
+        npc(id, &format!("dialog_id: \"\"\nbehavior:\n  {}\nmove_every: 1", behavior))
+    }
+
+    // Characters walk around the Main Room as the game ticks, but only while the player is on the map
+    #[test]
+    fn characters_move_on_ticks() {
+        let guard = moving_character("guard", "patrol: { waypoints: [ { x: 23, y: 2 }, { x: 23, y: 4 } ] }");
+        let dog = moving_character("dog", "follow: { range: 6 }");
+        let mouse = moving_character("mouse", "wander: { radius: 1 }");
+        let mut game = example_game_with(&[("guard", 20, 2), ("dog", 5, 1), ("mouse", 30, 5)], &[
+            ("characters/guard.yaml", guard.as_str()),
+            ("characters/dog.yaml", dog.as_str()),
+            ("characters/mouse.yaml", mouse.as_str()),
+        ]);
+        let position = |game: &Playthrough, id: &str| game.character_position("Main Room", id).unwrap();
+
+        // Nothing moves on the start screen
+        game.run(&[Tick(3)]).unwrap();
+        assert_eq!(position(&game, "guard"), (20, 2));
+
+        // The guard walks to the first waypoint, and the dog comes over and stops next to the player
+        game.run(&[Start, Tick(3)]).unwrap();
+        assert_eq!(position(&game, "guard"), (23, 2));
+        assert_eq!(position(&game, "dog"), (2, 1));
+        assert_eq!(position(&game, "angry_antagonist"), (11, 4));
+
+        // Then the guard heads to the next waypoint and back again
+        game.run(&[Tick(2)]).unwrap();
+        assert_eq!(position(&game, "guard"), (23, 4));
+        game.run(&[Tick(2)]).unwrap();
+        assert_eq!(position(&game, "guard"), (23, 2));
+
+        // The mouse never strays more than a space from where it was placed
+        let mut visited = HashSet::<(usize, usize)>::new();
+        for _ in 0..20 {
+            game.run(&[Tick(1)]).unwrap();
+            let (x, y) = position(&game, "mouse");
+            assert!((29..=31).contains(&x) && (4..=6).contains(&y), "The mouse wandered to ({}, {})", x, y);
+            visited.insert((x, y));
+        }
+        assert!(visited.len() > 1);
+
+        // The dog won't walk onto the player, and stays put while the player stands on it
+        game.run(&[Move(Right, 1), Tick(2)]).unwrap();
+        assert_eq!(position(&game, "dog"), (2, 1));
+        game.run(&[Move(Down, 1), Move(Right, 3), Tick(3)]).unwrap();
+        assert_eq!(position(&game, "dog"), (5, 1));
+
+        // Characters wait while the player is in a menu
+        game.run(&[Move(Right, 3), OpenInventory, Tick(4)]).unwrap();
+        assert_eq!(position(&game, "dog"), (5, 1));
+        game.run(&[ReturnToMap, Tick(1)]).unwrap();
+        assert_eq!(position(&game, "dog"), (6, 1));
+    }
+
+    // A wolf chases the player down and drops its loot once beaten, a guard stands in the way, and a
+    //    bandit fights when walked into, then stays around to talk
+    #[test]
+    fn hostile_characters_chase_and_fight() {
+        let wolf = npc("wolf", r##"dialog_id: ""
+hostile: { aggro_radius: 4 }
+move_every: 1
+on_defeat:
+  loot: [ "coin", "hat" ]"##);
+        let guard = npc("guard", r##"dialog_id: ""
+hostile: { aggro_radius: 2, on_contact: "block" }
+move_every: 1"##);
+        let bandit = npc("bandit", r##"dialog_id: "AA start"
+hostile: { aggro_radius: 0 }
+on_defeat:
+  remove: false
+  set_dialog: "AA give hat""##);
+        let mut game = example_game_with(&[("wolf", 6, 1), ("guard", 1, 4), ("bandit", 20, 5)], &[
+            ("characters/wolf.yaml", wolf.as_str()),
+            ("characters/guard.yaml", guard.as_str()),
+            ("characters/bandit.yaml", bandit.as_str()),
+        ]);
+        let position = |game: &Playthrough, id: &str| game.character_position("Main Room", id);
+        let fight = |game: &mut Playthrough| {
+            while game.game_state.visual_state == VisualState::PlayingCharacterFight {
+                game.run(&[Attack(0)]).unwrap();
+            }
+        };
+
+        // The guard comes over and gets in the player's way
+        game.run(&[Start, Move(Down, 1), Tick(1)]).unwrap();
+        assert_eq!(position(&game, "guard"), Some((1, 3)));
+        assert_eq!(position(&game, "wolf"), Some((6, 1)));
+        assert!(game.run(&[Move(Down, 1)]).is_err());
+        assert_eq!(game.player_position(), (1, 2));
+
+        // The wolf notices the player, runs over, and attacks once it is next to them
+        game.run(&[Move(Up, 1), Move(Right, 1), Tick(3)]).unwrap();
+        assert_eq!(position(&game, "wolf"), Some((3, 1)));
+        assert_eq!(position(&game, "guard"), Some((1, 3)));
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingMap);
+        game.run(&[Tick(1)]).unwrap();
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingCharacterFight);
+        assert!(game.frame().contains("wolf"));
+
+        fight(&mut game);
+        assert_eq!(game.game_state.dialog_message, "You defeated the wolf!\n\nThe wolf dropped the Gold Coin, the Hat.");
+        assert_eq!(position(&game, "wolf"), None);
+        assert_eq!(game.object_at("Main Room", 3, 1).unwrap().id, "coin");
+        assert_eq!(game.object_at("Main Room", 3, 2).unwrap().instance_id, "hat@Main Room:3,2");
+
+        // The bandit doesn't come after the player, but fights when walked into
+        game.run(&[ChooseMessageOption(1), Move(Right, 18), Move(Down, 3), Tick(2)]).unwrap();
+        assert_eq!(position(&game, "bandit"), Some((20, 5)));
+        game.run(&[Move(Down, 1)]).unwrap();
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingCharacterFight);
+
+        // Once beaten, the bandit is back on its feet with something new to say, and leaves the player be
+        fight(&mut game);
+        assert_eq!(game.game_state.dialog_message, "You defeated the bandit!");
+        let bandit = game.character_at("Main Room", 20, 5).unwrap();
+        assert!(bandit.defeated);
+        assert_eq!(bandit.get_attribute_value("health"), 10);
+        game.run(&[ChooseMessageOption(1), Move(Up, 1), Move(Down, 1), Interact]).unwrap();
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingCharacterInteraction);
+        assert_eq!(game.game_state.npc_dialog_id, "AA give hat");
+    }
+
+    // Picks up a hat, a helmet, a sword, and a coin, then tries them all on. Only one thing fits on
+    //    the player's head at a time, and the sword makes the player's attacks hit harder.
+    #[test]
+    fn equipment_changes_attributes() {
+        let placements = [("helmet", 12, 6), ("sword", 12, 7), ("coin", 12, 8)];
+        let files = [
+            ("objects/helmet.yaml", r##"
+id: "helmet"
+name: "Helmet"
+category: "collectable"
+icon: "n"
+equip:
+  slot: "head"
+  modifiers:
+    - attribute_id: "skill_3"
+      amount: 1
+"##),
+            ("objects/sword.yaml", r##"
+id: "sword"
+name: "Sword"
+category: "collectable"
+icon: "/"
+equip:
+  slot: "weapon"
+  modifiers:
+    - attribute_id: "skill_1"
+      amount: 5
+    - attribute_id: "skill_2"
+      amount: -1
+"##),
+        ];
+        let mut game = example_game_with(&placements, &files);
+        let damage = |game: &Playthrough| game.player().interactions.attacks[0].calculate_damage(game.player());
+        game.run(&[Start, Move(Right, 11), Move(Down, 4), Interact, ChooseMessageOption(1)]).unwrap();
+        for _ in 0..3 {
+            game.run(&[Move(Down, 1), Interact, ChooseMessageOption(1)]).unwrap();
+        }
+        assert_eq!(game.inventory_ids(), vec!["hat", "helmet", "sword", "coin"]);
+        assert_eq!(damage(&game), 4);
+
+        game.run(&[OpenInventory, SelectSlot(0, 0), ToggleEquipped]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You equipped the Hat.");
+        assert_eq!(game.player().get_attribute_value("skill_1"), 3);
+        assert_eq!(game.player().get_attribute("skill_1").unwrap().current_val, 1);
+
+        game.run(&[ChooseMessageOption(1), SelectSlot(0, 1), ToggleEquipped]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You equipped the Helmet, and unequipped the Hat.");
+        assert_eq!(game.player().get_attribute_value("skill_1"), 1);
+        assert_eq!(game.player().get_attribute_value("skill_3"), 8);
+
+        game.run(&[ChooseMessageOption(1), SelectSlot(0, 2), ToggleEquipped]).unwrap();
+        assert_eq!(game.player().get_attribute_value("skill_1"), 6);
+        assert_eq!(game.player().get_attribute_value("skill_2"), 4);
+        assert_eq!(damage(&game), 30);
+        game.run(&[ChooseMessageOption(1)]).unwrap();
+        assert!(game.frame().contains("Skill 1: 1/10 +5"), "{}", game.frame());
+
+        game.run(&[SelectSlot(1, 0), ToggleEquipped]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "The Gold Coin can't be equipped.");
+
+        // Equipment is saved along with the rest of the inventory
+        let save = saves::SaveData::from_game(&game.game_data, &game.game_state).unwrap();
+        let mut loaded = example_game_with(&placements, &files);
+        save.apply(&mut loaded.game_data, &mut loaded.game_state).unwrap();
+        assert_eq!(loaded.player().equipped_items().map(|item| item.id.as_str()).collect::<Vec<&str>>(),
+                   vec!["helmet", "sword"]);
+
+        game.run(&[ChooseMessageOption(1), SelectSlot(0, 2), ToggleEquipped]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You unequipped the Sword.");
+        assert_eq!(damage(&game), 4);
+    }
+
+    // Drinks a potion and an elixir, bandages up a patient, and then fights a training dummy for
+    //    long enough that the potion's buff wears off
+    #[test]
+    fn consumables_change_attributes() {
+        let patient = npc("patient", "dialog_id: \"\"").replace("starting_value: 10", "starting_value: 4");
+        let dummy = npc("dummy", "dialog_id: \"\"\nhostile: { aggro_radius: 0 }").replace("_value: 10", "_value: 50");
+        let mut game = example_game_with(&[("potion", 2, 1), ("elixir", 3, 1), ("bandage", 4, 1), ("bandage", 5, 1),
+                                           ("patient", 7, 1), ("dummy", 9, 1)], &[
+            ("objects/elixir.yaml", r##"
+id: "elixir"
+name: "Elixir"
+category: "collectable"
+icon: "&"
+consume:
+  - attribute_id: "skill_3"
+    amount: 2
+    duration: { ticks: 4 }
+"##),
+            ("objects/bandage.yaml", r##"
+id: "bandage"
+name: "Bandage"
+category: "collectable"
+icon: "+"
+consume:
+  - attribute_id: "health"
+    amount: 5
+"##),
+            ("characters/patient.yaml", patient.as_str()),
+            ("characters/dummy.yaml", dummy.as_str()),
+        ]);
+        let health = |game: &Playthrough| game.player().get_attribute_value("health");
+        game.run(&[Start]).unwrap();
+        for _ in 0..4 {
+            game.run(&[Move(Right, 1), Interact, ChooseMessageOption(1)]).unwrap();
+        }
+        assert_eq!(game.inventory_ids(), vec!["potion", "elixir", "bandage", "bandage"]);
+
+        // Nothing is used up if it wouldn't change anything
+        game.run(&[OpenInventory, SelectSlot(0, 2), UseItem]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "The Bandage wouldn't do anything to you right now.");
+        assert_eq!(game.inventory_ids().len(), 4);
+
+        // Healing stops at the most health the player can have
+        game.game_data.info.player.as_mut().unwrap().change_attribute("health", -20);
+        game.run(&[ChooseMessageOption(1), SelectSlot(0, 0), UseItem]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You used the Potion. Health +20, Skill 1 +3 for 3 turns.");
+        assert_eq!(health(&game), 100);
+        assert_eq!(game.player().get_attribute_value("skill_1"), 4);
+        assert_eq!(game.player().get_attribute("skill_1").unwrap().current_val, 1);
+
+        game.run(&[ChooseMessageOption(1), SelectSlot(0, 1), UseItem]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You used the Elixir. Skill 3 +2 for 2 seconds.");
+        assert_eq!(game.player().get_attribute_value("skill_3"), 9);
+        assert_eq!(game.inventory_ids(), vec!["bandage", "bandage"]);
+
+        // Items can be used on other characters too
+        game.run(&[ChooseMessageOption(1), ReturnToMap, Move(Right, 2), OpenInventory, SelectSlot(0, 2), UseItem])
+            .unwrap();
+        assert_eq!(game.game_state.dialog_message, "You used the Bandage on the patient. Health +5.");
+        game.run(&[ChooseMessageOption(1), SelectSlot(1, 0), UseItem]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "You used the Bandage on the patient. Health +1.");
+        assert_eq!(game.character_at("Main Room", 7, 1).unwrap().get_attribute_value("health"), 10);
+        assert!(game.inventory_ids().is_empty());
+
+        // The elixir wears off after 4 ticks on the map
+        game.run(&[ChooseMessageOption(1), ReturnToMap, Tick(3)]).unwrap();
+        assert_eq!(game.player().get_attribute_value("skill_3"), 9);
+        game.run(&[Tick(1)]).unwrap();
+        assert_eq!(game.player().get_attribute_value("skill_3"), 7);
+
+        // The potion's buff lasts for three rounds of fighting
+        game.run(&[Move(Right, 2)]).unwrap();
+        assert_eq!(game.game_state.visual_state, VisualState::PlayingCharacterFight);
+        for remaining in [2, 1] {
+            game.run(&[Attack(0)]).unwrap();
+            assert!(matches!(game.player().buffs[..], [Buff { remaining: EffectDuration::Turns(turns), .. }] if turns == remaining));
+        }
+        game.run(&[Attack(0)]).unwrap();
+        assert_eq!(game.game_state.fight_message, "You used Throw stick for 14 damage!");
+        assert!(game.player().buffs.is_empty());
+        game.run(&[Attack(0)]).unwrap();
+        assert_eq!(game.game_state.fight_message, "You used Throw stick for 4 damage!");
+    }
+
+    // Pulls a lever back and forth, and tries a strongbox that stays locked
+    #[test]
+    fn levers_and_locked_containers() {
+        let mut game = example_game_with(&[("lever", 2, 1), ("strongbox", 3, 1)], &[
+            ("objects/lever.yaml", r##"
+id: "lever"
+name: "Lever"
+category: "lever"
+icon: "/"
+state:
+interactions:
+"##),
+            ("objects/strongbox.yaml", r##"
+id: "strongbox"
+name: "Strongbox"
+category: "container"
+icon: "B"
+contents:
+  - "hat"
+state:
+  - id: "locked"
+    default: true
+interactions:
+  activate:
+    - category: "use"
+      prereqs:
+        - locked: false
+"##)]);
+        game.run(&[Start, Move(Right, 1), Interact]).unwrap();
+        assert!(game.object_at("Main Room", 2, 1).unwrap().get_state(LEVER_STATE));
+        game.run(&[Interact]).unwrap();
+        assert!(!game.object_at("Main Room", 2, 1).unwrap().get_state(LEVER_STATE));
+
+        game.run(&[Move(Right, 1), Interact]).unwrap();
+        assert_eq!(game.game_state.dialog_message, "The Strongbox is locked!");
+        assert!(game.inventory_ids().is_empty());
+        assert_eq!(game.object_at("Main Room", 3, 1).unwrap().contents, vec!["hat"]);
+    }
+
+    #[test]
+    fn steps_fail_when_they_cannot_be_taken() {
+        let mut game = example_game();
+        assert!(game.run(&[Move(Right, 1)]).is_err());
+
+        let err = game.run(&[Start, Move(Up, 1)]).unwrap_err();
+        assert_eq!(err, "Step 1 (Move(Up, 1)) failed: Unable to move Up from (1, 1) in map 'Main Room'");
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Deserialize};
+use crate::game::dialogs::{DialogCondition, DialogEffect};
+
+// Quest data structure. A quest is a goal broken up into stages that the player works through in
+// order, finishing each one once all of its conditions are met. Conditions are the same ones that
+// dialog options use, and finishing the last stage completes the quest and gives its rewards.
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Quest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Everything that has to be true for the quest to start. Quests without any start right away.
+    #[serde(default)]
+    pub start: Vec<DialogCondition>,
+    pub stages: Vec<QuestStage>,
+    // What happens when the quest is completed, using the same effects as dialog options
+    #[serde(default)]
+    pub rewards: Vec<DialogEffect>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestStage {
+    pub description: String,
+    #[serde(default)]
+    pub conditions: Vec<DialogCondition>,
+}
+
+// How far the player has gotten in a quest that has started
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestProgress {
+    // Working on the stage at this index
+    Active(usize),
+    Completed,
+}
+
+impl QuestProgress {
+    // How many of the quest's stages have been finished
+    pub fn stages_done(&self, quest: &Quest) -> usize {
+        match self {
+            QuestProgress::Active(stage) => { *stage }
+            QuestProgress::Completed => { quest.stages.len() }
+        }
+    }
+}
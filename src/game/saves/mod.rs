@@ -0,0 +1,470 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Serialize,Deserialize};
+use crate::game::GameState;
+use crate::game::characters::{Buff, Character};
+use crate::game::config_parsers::GameData;
+use crate::game::maps::{Map, MapData};
+use crate::game::objects::{Object, StateValue};
+use crate::game::quests::QuestProgress;
+
+// Save game data structure. Contains only what has changed in a game in progress since it was
+// loaded, so that it can be written to disk and later restored on top of the game's configs.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveData {
+    pub game_name: String,
+    pub current_map: String,
+    pub player_x: u16,
+    pub player_y: u16,
+    pub player: CharacterSave,
+    // Only the maps that have changed
+    pub maps: Vec<MapSave>,
+    pub variables: BTreeMap<String, StateValue>,
+    pub quests: BTreeMap<String, QuestProgress>,
+    pub seen_dialogs: BTreeSet<String>,
+}
+
+// The changes made to a single map. Placements from the map's config that are gone, such as
+//    collected items and defeated characters, are listed as removed. Placements that have moved or
+//    changed, and anything added to the map while playing, such as dropped loot, are stored as
+//    spaces.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapSave {
+    pub id: String,
+    pub removed: Vec<PlacementId>,
+    pub spaces: Vec<SpaceSave>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpaceSave {
+    pub x: usize,
+    pub y: usize,
+    pub data: PlacementSave,
+}
+
+// How a placement is told apart from the others in its map: objects by their instance id, and
+//    characters by their id and the spot they were placed at
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlacementId {
+    Object(String),
+    Character { id: String, home: (usize, usize) },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlacementSave {
+    Object(ObjectSave),
+    Character(CharacterSave),
+}
+
+// The parts of an object that can change while playing. Everything else comes from the placement
+//    with the same instance id, or from the object's config file for objects that weren't placed in
+//    any map.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObjectSave {
+    pub id: String,
+    pub instance_id: String,
+    pub state: BTreeMap<String, StateValue>,
+    pub contents: Vec<String>,
+    pub equipped: bool,
+}
+
+// The parts of a character that can change while playing, with attributes stored by id as their
+//    current values
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CharacterSave {
+    pub id: String,
+    pub home: (usize, usize),
+    pub dialog_id: String,
+    pub attributes: BTreeMap<String, u8>,
+    pub inventory: Vec<Vec<Option<ObjectSave>>>,
+    pub buffs: Vec<Buff>,
+    pub waypoint: usize,
+    pub defeated: bool,
+}
+
+impl SaveData {
+    // Captures what has changed in the game since it was loaded
+    pub fn from_game(game_data: &GameData, game_state: &GameState) -> Result<SaveData, Box<dyn Error>> {
+        let player = match &game_data.info.player {
+            Some(player) => { CharacterSave::from_character(player) }
+            None => { return Err("There is no player to save".into()); }
+        };
+
+        let mut maps = Vec::<MapSave>::new();
+        for (map, starting_map) in game_data.maps.iter().zip(&game_data.starting_maps) {
+            let starting_spaces = map_spaces(starting_map);
+            let spaces = map_spaces(map);
+            let removed: Vec<PlacementId> = starting_spaces.iter()
+                .map(|space| space.data.id())
+                .filter(|id| !spaces.iter().any(|space| &space.data.id() == id))
+                .collect();
+            let spaces: Vec<SpaceSave> = spaces.into_iter()
+                .filter(|space| !starting_spaces.contains(space))
+                .collect();
+            if !removed.is_empty() || !spaces.is_empty() {
+                maps.push(MapSave { id: map.info.id.clone(), removed, spaces });
+            }
+        }
+
+        Ok(SaveData {
+            game_name: game_data.info.name.clone(),
+            current_map: game_data.maps[game_state.current_map].info.id.clone(),
+            player_x: game_state.current_player_x,
+            player_y: game_state.current_player_y,
+            player,
+            maps,
+            variables: game_state.variables.clone(),
+            quests: game_state.quests.clone(),
+            seen_dialogs: game_state.seen_dialogs.clone(),
+        })
+    }
+
+    // Restores the saved state on top of a game freshly loaded from its configs, rebuilding
+    //    everything that isn't in the save from the configs. The save is checked against the game
+    //    first, so nothing is changed if the save does not belong to this game.
+    pub fn apply(self, game_data: &mut GameData, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+        if self.game_name != game_data.info.name {
+            return Err(format!("Save file belongs to '{}', not '{}'", self.game_name, game_data.info.name).into());
+        }
+        let current_map = match game_data.maps.iter().position(|m| m.info.id == self.current_map) {
+            Some(m) => { m }
+            None => { return Err(format!("Saved map '{}' does not exist", self.current_map).into()); }
+        };
+        let player = match &game_data.info.player {
+            Some(player) => { self.player.restore(player, game_data)? }
+            None => { return Err("There is no player to load the save into".into()); }
+        };
+
+        // Rebuild every saved map before changing anything
+        let mut grids = Vec::<(usize, Vec<Vec<Option<MapData>>>)>::new();
+        for map_save in &self.maps {
+            let m = match game_data.starting_maps.iter().position(|m| m.info.id == map_save.id) {
+                Some(m) => { m }
+                None => { return Err(format!("Saved map '{}' does not exist", map_save.id).into()); }
+            };
+            grids.push((m, map_save.restore(&game_data.starting_maps[m], game_data)?));
+        }
+
+        for (m, grid) in grids {
+            game_data.maps[m].grid = grid;
+        }
+        game_data.info.player = Option::<Character>::Some(player);
+        game_state.current_map = current_map;
+        game_state.current_player_x = self.player_x;
+        game_state.current_player_y = self.player_y;
+        // Variables added to the game since it was saved keep their defaults
+        game_state.variables = game_data.info.variables.clone();
+        game_state.variables.extend(self.variables);
+        // The same goes for quests that start right away, while quests that are no longer in the
+        //    game are dropped
+        game_state.quests = GameState::starting_quests(game_data);
+        game_state.quests.extend(self.quests.into_iter().filter(|(quest_id, _)| game_data.quests.contains_key(quest_id)));
+        game_state.seen_dialogs = self.seen_dialogs;
+
+        Ok(())
+    }
+}
+
+impl MapSave {
+    // Rebuilds the map's grid from how it was loaded, taking out the placements that were removed or
+    //    saved somewhere else, then putting in the saved ones
+    fn restore(&self, starting_map: &Map, game_data: &GameData) -> Result<Vec<Vec<Option<MapData>>>, Box<dyn Error>> {
+        let mut grid = starting_map.grid.clone();
+        for space in grid.iter_mut().flatten() {
+            if let Some(data) = space {
+                let id = PlacementSave::from_map_data(data).id();
+                if self.removed.contains(&id) || self.spaces.iter().any(|saved| saved.data.id() == id) {
+                    *space = Option::None;
+                }
+            }
+        }
+
+        for space in &self.spaces {
+            if space.x >= grid.len() || space.y >= grid[space.x].len() {
+                return Err(format!("Saved position ({}, {}) is outside of map '{}'", space.x, space.y, self.id).into());
+            }
+            let data = match &space.data {
+                PlacementSave::Object(object) => { MapData::Object(object.restore(game_data)?) }
+                PlacementSave::Character(character) => {
+                    let placed = starting_map.grid.iter().flatten().find_map(|placed| match placed {
+                        Some(MapData::Character(placed)) if placed.id == character.id && placed.home == character.home => {
+                            Some(placed)
+                        }
+                        _ => { None }
+                    });
+                    match placed {
+                        Some(placed) => { MapData::Character(character.restore(placed, game_data)?) }
+                        None => {
+                            return Err(format!("Saved character '{}' was not placed at ({}, {}) in map '{}'",
+                                               character.id, character.home.0, character.home.1, self.id).into());
+                        }
+                    }
+                }
+            };
+            grid[space.x][space.y] = Option::<MapData>::Some(data);
+        }
+
+        Ok(grid)
+    }
+}
+
+impl PlacementSave {
+    fn from_map_data(data: &MapData) -> PlacementSave {
+        match data {
+            MapData::Object(object) => { PlacementSave::Object(ObjectSave::from_object(object)) }
+            MapData::Character(character) => { PlacementSave::Character(CharacterSave::from_character(character)) }
+        }
+    }
+
+    fn id(&self) -> PlacementId {
+        match self {
+            PlacementSave::Object(object) => { PlacementId::Object(object.instance_id.clone()) }
+            PlacementSave::Character(character) => {
+                PlacementId::Character { id: character.id.clone(), home: character.home }
+            }
+        }
+    }
+}
+
+impl ObjectSave {
+    fn from_object(object: &Object) -> ObjectSave {
+        ObjectSave {
+            id: object.id.clone(),
+            instance_id: object.instance_id.clone(),
+            state: object.state.iter().map(|s| (s.name.clone(), s.value.clone())).collect(),
+            contents: object.contents.clone(),
+            equipped: object.equipped,
+        }
+    }
+
+    fn restore(&self, game_data: &GameData) -> Result<Object, Box<dyn Error>> {
+        let placed = game_data.starting_maps.iter()
+            .flat_map(|map| map.grid.iter().flatten())
+            .find_map(|space| match space {
+                Some(MapData::Object(placed)) if placed.instance_id == self.instance_id => { Some(placed) }
+                _ => { None }
+            });
+        let mut object = match (placed, game_data.objects.get(&self.id)) {
+            (Some(placed), _) => { placed.clone() }
+            (None, Some(object)) => { object.clone() }
+            (None, None) => { return Err(format!("Saved object '{}' does not exist", self.id).into()); }
+        };
+        object.instance_id = self.instance_id.clone();
+        for (state_name, value) in &self.state {
+            object.set_state(state_name.clone(), value.clone());
+        }
+        object.contents = self.contents.clone();
+        object.equipped = self.equipped;
+        Ok(object)
+    }
+}
+
+impl CharacterSave {
+    fn from_character(character: &Character) -> CharacterSave {
+        CharacterSave {
+            id: character.id.clone(),
+            home: character.home,
+            dialog_id: character.dialog_id.clone(),
+            attributes: character.attributes.iter().map(|a| (a.id.clone(), a.current_val)).collect(),
+            inventory: character.inventory.iter()
+                .map(|column| column.iter().map(|slot| slot.as_ref().map(ObjectSave::from_object)).collect())
+                .collect(),
+            buffs: character.buffs.clone(),
+            waypoint: character.waypoint,
+            defeated: character.defeated,
+        }
+    }
+
+    // Makes the saved changes to the character as it was loaded
+    fn restore(&self, character: &Character, game_data: &GameData) -> Result<Character, Box<dyn Error>> {
+        let mut character = character.clone();
+        character.dialog_id = self.dialog_id.clone();
+        for attribute in &mut character.attributes {
+            if let Some(value) = self.attributes.get(&attribute.id) {
+                attribute.current_val = *value;
+            }
+        }
+        let mut inventory = Vec::<Vec<Option<Object>>>::new();
+        for column in &self.inventory {
+            let mut slots = Vec::<Option<Object>>::new();
+            for slot in column {
+                slots.push(match slot {
+                    Some(object) => { Some(object.restore(game_data)?) }
+                    None => { None }
+                });
+            }
+            inventory.push(slots);
+        }
+        character.inventory = inventory;
+        character.buffs = self.buffs.clone();
+        character.waypoint = self.waypoint;
+        character.defeated = self.defeated;
+        Ok(character)
+    }
+}
+
+// Every placement in the map, along with where it is
+fn map_spaces(map: &Map) -> Vec<SpaceSave> {
+    let mut spaces = Vec::<SpaceSave>::new();
+    for (x, column) in map.grid.iter().enumerate() {
+        for (y, space) in column.iter().enumerate() {
+            if let Some(data) = space {
+                spaces.push(SpaceSave { x, y, data: PlacementSave::from_map_data(data) });
+            }
+        }
+    }
+    spaces
+}
+
+// Writes the current state of the game to the save file
+pub fn save_game(save_path: &Path, game_data: &GameData, game_state: &GameState) -> Result<(), Box<dyn Error>> {
+    let save_data = SaveData::from_game(game_data, game_state)?;
+    let file_contents = serde_yaml::to_string(&save_data)?;
+    fs::write(save_path, file_contents)?;
+
+    Ok(())
+}
+
+// Reads the save file and restores its state into the game
+pub fn load_game(save_path: &Path, game_data: &mut GameData, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+    let file_contents = fs::read_to_string(save_path)?;
+    let save_data = serde_yaml::from_str::<SaveData>(&file_contents)?;
+    save_data.apply(game_data, game_state)?;
+
+    Ok(())
+}
+
+// Returns the default save file location for a game, which sits next to the game's config folder or
+//    compiled executable (e.g. "example_game" is saved to "example_game.save.yaml")
+pub fn default_save_path(config_path: &Path) -> PathBuf {
+    let config_path = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+    match config_path.file_name() {
+        Some(name) => {
+            let mut file_name = name.to_os_string();
+            file_name.push(".save.yaml");
+            config_path.with_file_name(file_name)
+        }
+        None => { PathBuf::from("radiance.save.yaml") }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::game::config_parsers::files::FolderFiles;
+
+    fn example_game() -> (GameData, GameState) {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+        let game_data = GameData::process_configs(&FolderFiles::new(&config_path)).unwrap();
+        let game_state = GameState::new(&game_data, default_save_path(&config_path));
+        (game_data, game_state)
+    }
+
+    // Picks up the hat, writes a save file, and loads it into a fresh game the same way --load does
+    #[test]
+    fn saves_are_written_and_loaded() {
+        let (mut game_data, mut game_state) = example_game();
+        let hat = match game_data.maps[game_state.current_map].grid[12][5].take() {
+            Some(MapData::Object(hat)) => { hat }
+            other => { panic!("Expected the hat at (12, 5), found {:?}", other) }
+        };
+        game_data.info.player.as_mut().unwrap().collect_object(&hat);
+        game_state.current_player_x = 15;
+        game_state.current_player_y = 5;
+        let save_path = std::env::temp_dir().join(format!("radiance-test-{}.save.yaml", std::process::id()));
+        save_game(&save_path, &game_data, &game_state).unwrap();
+
+        let (mut loaded_data, mut loaded_state) = example_game();
+        let result = load_game(&save_path, &mut loaded_data, &mut loaded_state);
+        let file_contents = fs::read_to_string(&save_path).unwrap();
+        fs::remove_file(&save_path).unwrap();
+        result.unwrap();
+
+        assert_eq!((loaded_state.current_player_x, loaded_state.current_player_y), (15, 5));
+        let player = loaded_data.info.player.as_ref().unwrap();
+        assert_eq!(player.inventory[0][0].as_ref().map(|o| o.id.as_str()), Some("hat"));
+        assert!(loaded_data.maps[loaded_state.current_map].grid[12][5].is_none());
+        // Only the hat is gone from the map, so nothing else in it is saved
+        let save = serde_yaml::from_str::<SaveData>(&file_contents).unwrap();
+        assert_eq!(save.maps.len(), 1);
+        assert_eq!(save.maps[0].removed, vec![PlacementId::Object(hat.instance_id.clone())]);
+        assert!(save.maps[0].spaces.is_empty());
+        // Saving the loaded game again gives back the same file
+        let resaved = SaveData::from_game(&loaded_data, &loaded_state).unwrap();
+        assert_eq!(serde_yaml::to_string(&resaved).unwrap(), file_contents);
+    }
+
+    // Moves a character and changes its dialog, unlocks a door, and empties the chest, then checks
+    //    that only those placements are saved and that they are rebuilt from the configs on loading
+    #[test]
+    fn changed_placements_are_rebuilt_from_the_configs() {
+        let (mut game_data, game_state) = example_game();
+        assert!(SaveData::from_game(&game_data, &game_state).unwrap().maps.is_empty());
+
+        let grid = &mut game_data.maps[game_state.current_map].grid;
+        let mut antagonist = match grid[11][4].take() {
+            Some(MapData::Character(character)) => { character }
+            other => { panic!("Expected the antagonist at (11, 4), found {:?}", other) }
+        };
+        antagonist.dialog_id = "AA give hat".to_string();
+        grid[11][3] = Some(MapData::Character(antagonist));
+        if let Some(MapData::Object(door)) = &mut grid[10][9] {
+            door.set_state("unlocked".to_string(), StateValue::Bool(true));
+        }
+        let basement = game_data.maps.iter().position(|m| m.info.id == "basement").unwrap();
+        if let Some(MapData::Object(chest)) = &mut game_data.maps[basement].grid[17][7] {
+            chest.contents.clear();
+        }
+
+        let save = SaveData::from_game(&game_data, &game_state).unwrap();
+        let saved_spaces = |map_save: &MapSave| -> Vec<(usize, usize)> {
+            map_save.spaces.iter().map(|space| (space.x, space.y)).collect()
+        };
+        assert_eq!(save.maps.iter().map(|m| m.id.as_str()).collect::<Vec<&str>>(), vec!["Main Room", "basement"]);
+        assert!(save.maps.iter().all(|m| m.removed.is_empty()));
+        assert_eq!(saved_spaces(&save.maps[0]), vec![(10, 9), (11, 3)]);
+        assert_eq!(saved_spaces(&save.maps[1]), vec![(17, 7)]);
+
+        let (mut loaded_data, mut loaded_state) = example_game();
+        save.apply(&mut loaded_data, &mut loaded_state).unwrap();
+        let grid = &loaded_data.maps[loaded_state.current_map].grid;
+        assert!(grid[11][4].is_none());
+        match &grid[11][3] {
+            Some(MapData::Character(character)) => {
+                assert_eq!(character.dialog_id, "AA give hat");
+                assert_eq!(character.name, "Angry Antagonist");
+            }
+            other => { panic!("Expected the antagonist at (11, 3), found {:?}", other) }
+        }
+        match &grid[10][9] {
+            Some(MapData::Object(door)) => {
+                assert!(door.get_state("unlocked"));
+                assert!(!door.interactions.is_empty());
+            }
+            other => { panic!("Expected the door at (10, 9), found {:?}", other) }
+        }
+        match &loaded_data.maps[basement].grid[17][7] {
+            Some(MapData::Object(chest)) => { assert!(chest.contents.is_empty()); }
+            other => { panic!("Expected the chest at (17, 7), found {:?}", other) }
+        }
+    }
+
+    #[test]
+    fn saves_for_other_games_are_not_loaded() {
+        let save_path = std::env::temp_dir().join(format!("radiance-test-{}.other.save.yaml", std::process::id()));
+        let (mut game_data, game_state) = example_game();
+        game_data.info.name = "Another Game".to_string();
+        save_game(&save_path, &game_data, &game_state).unwrap();
+
+        let (mut loaded_data, mut loaded_state) = example_game();
+        let result = load_game(&save_path, &mut loaded_data, &mut loaded_state);
+        fs::remove_file(&save_path).unwrap();
+        assert!(result.unwrap_err().to_string().starts_with("Save file belongs to 'Another Game'"));
+    }
+}
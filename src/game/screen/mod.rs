@@ -1,113 +1,63 @@
-use std::fmt::Debug;
-use std::io::{Error, ErrorKind, stdout};
-use std::process::{exit};
 use crate::game::config_parsers::GameData;
-use crate::game::GameState;
+use crate::game::{GameState, VisualState};
+use crate::game::engine::{self, Direction};
 use crate::game::maps::MapData;
-use crate::game::characters::Character;
-use crate::game::characters::attribute;
-use crate::game::objects::{ObjectInteraction, Object};
+use crate::game::characters::{Character, HEALTH_ATTRIBUTE_ID};
+use crate::game::objects::Object;
+use crate::game::quests::QuestProgress;
+use renderer::{CrosstermRenderer, Renderer};
 use crossterm::{
-    execute,
-    style::{Print},
-    ExecutableCommand, Result,
-    terminal::{SetSize, size, disable_raw_mode, enable_raw_mode, Clear, ClearType},
-    cursor::{MoveTo, Hide, Show},
+    Result,
     event::{Event, KeyCode},
 };
 
-#[derive(Clone, Debug)]
-pub enum VisualState {
-    StartScreen,
-    PlayingMap,
-    PlayingDialog,
-    PlayingInventory,
-    PlayingCharacterInteraction,
-    PlayingCharacterFight,
-}
+pub mod renderer;
 
-#[derive(Clone, Debug)]
-pub struct Screen {
-    original_columns: u16,
-    original_rows: u16,
+// Draws the game and turns key presses into changes to the game. Everything is drawn through a
+// Renderer, which is the terminal while playing.
+pub struct Screen<R: Renderer> {
+    renderer: R,
     current_columns: u16,
     current_rows: u16,
 }
 
-impl Screen {
+impl Screen<CrosstermRenderer> {
 
-    // Throw an error if the terminal size is too small to run the game
-    //      (Terminal must be at least 80x20)
-    fn check_screen_size() -> Result<()> {
-        let error_terminal_too_small = Error::new(ErrorKind::Other, "Terminal size is too small, must be at least 80x20");
-        let (cols, rows) = size()?;
-        if cols < 80 || rows < 20 {
-            Err(error_terminal_too_small)
-        } else {
-            Ok(())
-        }
+    // Set up the terminal to display the game
+    pub fn initialize() -> Screen<CrosstermRenderer> {
+        Screen::new(CrosstermRenderer::initialize())
     }
 
-    // Set up the screen to display the game
-    pub fn initialize() -> Screen {
-        // Get the original size
-        let (orig_cols, orig_rows) = size().unwrap();
-
-        // Make sure their terminal is big enough
-        match Screen::check_screen_size() {
-            Err(_) => {
-                println!("ERROR: Screen size ({}x{}) is too small, please use a larger terminal.",
-                         orig_cols, orig_rows);
-                exit(1);
-            },
-            Ok(_) => {
-                println!("Screen size check passed");
-            }
-        }
+    // Cleans up terminal and displays shutdown message
+    pub fn end(&self) -> Result<()> {
 
-        // Store original and desired sizes in a screen struct
-        let screen = Screen {
-            original_columns: orig_cols,
-            original_rows: orig_rows,
-            current_columns: 80,
-            current_rows: 20
-        };
+        self.renderer.reset()?;
+        println!("Shutting down, goodbye!");
 
-        // Turn off the cursor
-        // TODO: Make a match error check like those other ones
-        match stdout().execute(Hide) {
-            Err(_) => {
-                println!("ERROR: Unable to hide the terminal cursor, please try another terminal");
-                exit(1);
-            },
-            _ => {
-                println!("Terminal cursor hidden");
-            }
-        };
+        Ok(())
+    }
 
-        // Turn on raw mode for proper keyboard input access
-        match enable_raw_mode() {
-            Err(_) => {
-                println!("ERROR: Unable to enable raw terminal mode for input, please try another terminal");
-                exit(1);
-            },
-            _ => {
-                println!("Raw terminal mode enabled");
-            }
-        };
+    // Makes the next draw write the whole screen again, such as after the terminal is resized
+    pub fn redraw_all(&mut self) {
+        self.renderer.invalidate();
+    }
+}
 
-        // Set the size
-        match execute!(stdout(), SetSize(screen.current_columns, screen.current_rows)) {
-            Err(_) => {
-                println!("ERROR: Failed to set the screen size");
-                exit(1);
-            },
-            _ => {
-                println!("Screen size set");
-            }
-        };
+impl<R: Renderer> Screen<R> {
 
-        screen
+    // Creates a screen that draws the game through the given renderer. The game is always
+    //    drawn at 80x20.
+    pub fn new(renderer: R) -> Screen<R> {
+        Screen {
+            renderer,
+            current_columns: 80,
+            current_rows: 20,
+        }
+    }
+
+    // The renderer everything is drawn through
+    pub fn renderer(&self) -> &R {
+        &self.renderer
     }
 
     // Function to take in a string and determine what column it should start being printed at
@@ -126,360 +76,21 @@ impl Screen {
         empty_space/2
     }
 
-    // Test function to use for making sure I can blink the cursor like when a character is over
-    // another object
-    fn blink_player_cursor(&self, game_data: &GameData, game_state: &mut GameState, start_x: u16, start_y: u16, x: u16, y: u16) -> Result<()> {
-        stdout().execute(MoveTo(start_x+x, start_y+y))?;
-        if game_state.cursor_blink {
-            // Print character
-            stdout().execute(Print(game_data.info.player.as_ref().unwrap().icon))?;
-        } else {
-            // Print whatever map thing is there
-            if game_data.maps[game_state.current_map].grid[x as usize][y as usize].is_some() {
-                match game_data.maps[game_state.current_map].grid[x as usize][y as usize].as_ref().unwrap()  {
-                    MapData::Character(character) => { stdout().execute(Print(character.icon))?; }
-                    MapData::Object(object) => { stdout().execute(Print(object.icon))?; }
-                }
-            }
-        }
-
-        game_state.cursor_blink = !game_state.cursor_blink;
-
-        Ok(())
-    }
-
-    // Function for handling the exit dialog and action
-    fn handle_exit_key(&self, game_state: &mut GameState) {
-        game_state.dialog_message = "Would you like to exit the game?".to_string();
-        game_state.dialog_option_0 = "No".to_string();
-        game_state.dialog_option_1 = "Yes".to_string();
-        game_state.dialog_return_0 = game_state.visual_state.clone();
-        game_state.dialog_return_cancel = game_state.visual_state.clone();
-        game_state.visual_state = VisualState::PlayingDialog;
-        game_state.pre_exit = true;
-    }
-
-    // Function for handling object interactions (when the "interact" key is pressed)
-    fn handle_interact_key(&self, game_state: &mut GameState, game_data: &mut GameData) {
-        let map = &game_data.maps[game_state.current_map].clone();
-        let player_x = game_state.current_player_x as usize;
-        let player_y = game_state.current_player_y as usize;
-        if map.grid[player_x][player_y].is_some() {
-            match map.grid[player_x][player_y].as_ref().unwrap()  {
-                MapData::Character(character) => {
-                    self.character_interact(game_state, character);
-                }
-                MapData::Object(object) => {
-                    self.activate_object(game_state, game_data, object);
-                }
-            }
-        }
-    }
-
-    // Starts any interaction that happens when an npc conversation is started
-    //    with the interact key
-    fn character_interact(&self, game_state: &mut GameState, character: &Character) {
-        game_state.dialog_return_cancel = game_state.visual_state.clone();
-        game_state.visual_state = VisualState::PlayingCharacterInteraction;
-        game_state.npc_dialog_id = character.dialog_id.clone();
-    }
-
-    // Starts any interaction that happens when an object is activated with the interact key
-    fn activate_object(&self, game_state: &mut GameState, game_data: &mut GameData, object: &Object) {
-        if object.category.as_str() == "collectable" {
-            // Remove item from map and add to inventory
-            self.collect_object(game_state, game_data, object);
-            // display "found item" dialog
-            game_state.dialog_message = format!("You've found the {}!\n\nNow, what will you do with it?"
-                                                , object.name);
-            game_state.dialog_option_0 = "Open inventory".to_string();
-            game_state.dialog_option_1 = "Close".to_string();
-            game_state.dialog_return_0 = VisualState::PlayingInventory;
-            game_state.dialog_return_1 = game_state.visual_state.clone();
-            game_state.dialog_return_cancel = game_state.visual_state.clone();
-            game_state.pre_exit = false;
-            game_state.visual_state = VisualState::PlayingDialog;
-            return;
-        }
-        for interaction in &object.interactions {
-            match interaction {
-                ObjectInteraction::ObjectInteractionActivate(activate) => {
-                    if !object.prereqs_met(&activate.prereqs) {
-                        // TODO: Add a more personalized dialog box here.
-                        //   Specify in config file?
-                        if object.category == "door" {
-                            // Display dialog for door being locked
-                            game_state.dialog_message = "The door is locked! Try to find a key.".to_string();
-                            game_state.dialog_option_0 = "Open inventory".to_string();
-                            game_state.dialog_option_1 = "Close".to_string();
-                            game_state.dialog_return_0 = VisualState::PlayingInventory;
-                            game_state.dialog_return_1 = game_state.visual_state.clone();
-                            game_state.dialog_return_cancel = game_state.visual_state.clone();
-                            game_state.pre_exit = false;
-                            game_state.visual_state = VisualState::PlayingDialog;
-                        }
-                        continue;
-                    }
-                    if activate.category == "travel" {
-                        self.travel_through_door(game_state, game_data, object);
-                    }
-                }
-                ObjectInteraction::ObjectInteractionObjectUse(_object_use) => {
-                }
-            }
-        }
-    }
-
-    // Uses the object currently selected in the inventory on the object currently under the player.
-    fn use_object(&self, game_state: &mut GameState, game_data: &mut GameData) {
+    // Draws the player at (col, row) on the screen, blinking with whatever is under them so that
+    // both can be seen, such as when the player is standing over another object
+    fn blink_player_cursor(&mut self, game_data: &GameData, game_state: &GameState, col: u16, row: u16) -> Result<()> {
         let x = game_state.current_player_x as usize;
         let y = game_state.current_player_y as usize;
-        let i_x = game_state.inventory_x;
-        let i_y = game_state.inventory_y;
-
-        // Check that there is an item selected
-        let inventory = game_data.info.player.as_ref().unwrap().inventory.clone();
-        if inventory[i_x][i_y].is_none() {
-            return; // Nothing in inventory slot.
-        }
-
-        // Get the object on the map to use the item on
-        let map_object;
-        let map = game_data.maps[game_state.current_map].clone();
-        if map.grid[x][y].is_some() {
-            match map.grid[x][y].as_ref().unwrap()  {
-                MapData::Character(character) => {
-                    self.use_object_character(game_state, game_data, character);
-                    return;
-                }
-                MapData::Object(object) => { map_object = object; }
-            }
+        if game_state.cursor_blink {
+            // Print character
+            self.renderer.draw_cell(col, row, game_data.info.player.as_ref().unwrap().icon)?;
         } else {
-            // Nothing to use the object on. Display dialog and do nothing.
-            self.show_game_message(game_state, "You can't use this item here!".to_string());
-            return;
-        }
-
-        // Get the selected inventory object
-        let inventory_object = inventory[i_x][i_y].as_ref().unwrap();
-        let mut object_used = false;
-        // find object use interaction
-        for interaction in &map_object.interactions {
-            match interaction {
-                ObjectInteraction::ObjectInteractionActivate(_activate) => {}
-                ObjectInteraction::ObjectInteractionObjectUse(object_use) => {
-                    if object_use.foreign_object_id != inventory_object.id {
-                        continue;
-                    }
-                    // The item was used. Display message.
-                    self.show_game_message(game_state, format!("You used the {} on the {}!",
-                                                    inventory_object.name, map_object.name));
-                    // Go through each action in the interaction
-                    for action in &object_use.self_action {
-                        // Perform all self-actions
-                        object_used = true;
-                        let mut new_map_object = map_object.clone();
-                        new_map_object.set_state(action.name.clone(), action.value);
-                        game_data.maps[game_state.current_map]
-                                .grid[game_state.current_player_x as usize][game_state.current_player_y as usize]
-                                = Option::<MapData>::Some(MapData::Object(new_map_object));
-                    }
-                    // If the item is a door, perform all the actions on the other side of the door.
-                    if map_object.category == "door" {
-                        // Get position of other door
-                        let (mut door_map, mut door_x, mut door_y) = (0, 0, 0);
-                        self.get_door_other_side(game_state, game_data, map_object.id.clone(),
-                                                 &mut door_map, &mut door_x, &mut door_y);
-                        // Perform actions on other door
-                        for action in &object_use.self_action {
-                            // Perform all self-actions
-                            let mut new_door = map_object.clone();
-                            new_door.set_state(action.name.clone(), action.value);
-                            game_data.maps[door_map]
-                                    .grid[door_x as usize][door_y as usize]
-                                    = Option::<MapData>::Some(MapData::Object(new_door));
-                        }
-                    }
-                    // If the item is consumed, remove it from the inventory
-                    if object_use.consume_item {
-                        let mut new_player = game_data.info.player.as_ref().unwrap().clone();
-                        new_player.inventory[i_x][i_y] = Option::None;
-                        game_data.info.player = Option::<Character>::Some(new_player);
-                    }
-                }
-            }
-        }
-        if !object_used {
-            let message = format!("You tried to use the {} on the {}, but it didn't work!",
-                                                inventory_object.name, map_object.name);
-            self.show_game_message(game_state, message);
-        }
-    }
-
-    // Uses an object from the player's inventory on a character in the
-    //    player's spot on the map
-    fn use_object_character(&self, game_state: &mut GameState, game_data: &mut GameData, character: &Character) {
-        let i_x = game_state.inventory_x;
-        let i_y = game_state.inventory_y;
-
-        // Get the selected inventory object
-        let inventory = game_data.info.player.as_ref().unwrap().inventory.clone();
-        if inventory[i_x][i_y].is_none() {
-            return; // Nothing in inventory slot.
-        }
-        let inventory_object = inventory[i_x][i_y].as_ref().unwrap();
-
-        // Find the right interaction for the item
-        let mut object_used = false;
-        for object_use in &character.interactions.object_use {
-            if object_use.object_id == inventory_object.id {
-                object_used = true;
-
-                // Update character dialog if specified by the interaction
-                if !object_use.set_dialog.is_empty() {
-                    let mut new_character = character.to_owned();
-                    new_character.dialog_id = object_use.set_dialog.to_owned();
-                    game_data.maps[game_state.current_map]
-                            .grid[game_state.current_player_x as usize][game_state.current_player_y as usize]
-                            = Option::<MapData>::Some(MapData::Character(new_character));
-                }
-
-                // If the item is consumed, remove it from the inventory
-                if object_use.consume_item {
-                    let mut new_player = game_data.info.player.as_ref().unwrap().clone();
-                    new_player.inventory[i_x][i_y] = Option::None;
-                    game_data.info.player = Option::<Character>::Some(new_player);
-                }
-            }
-        }
-        if !object_used {
-            self.show_game_message(game_state, "You can't use this item here!".to_string());
-        }
-    }
-
-    // Removes the object from the player's spot on the map and places it in the
-    //     player inventory
-    fn collect_object(&self, game_state: &mut GameState, game_data: &mut GameData, object: &Object) {
-        // If inventory size is not exceeded, add item to player inventory
-        if game_data.info.player.is_none() {
-            return;
-        }
-        let mut player = game_data.info.player.as_ref().unwrap().clone();
-        player.collect_object(object);
-        game_data.info.player = Option::<Character>::Some(player);
-        // Remove item from map
-        game_data.maps[game_state.current_map]
-                 .grid[game_state.current_player_x as usize][game_state.current_player_y as usize]
-                 = Option::None;
-    }
-
-    // Moves character to a different map through the specified door
-    // Assume prereqs are already checked.
-    fn travel_through_door(&self, game_state: &mut GameState, game_data: &GameData, door: &Object) {
-        // find door
-        let (mut m, mut x, mut y) = (0, 0, 0);
-        self.get_door_other_side(game_state, game_data, door.id.clone(),
-                                &mut m, &mut x, &mut y);
-        // Move character to new door
-        game_state.current_map = m;
-        game_state.current_player_x = x as u16;
-        game_state.current_player_y = y as u16;
-    }
-
-    // Sets door_map, x, and y to the position of the door with door_id that is not in current space.
-    fn get_door_other_side(&self, game_state: &GameState, game_data: &GameData, door_id: String,
-                           door_map: &mut usize, x: &mut usize, y: &mut usize) {
-        *door_map = 0;
-        *x = 0;
-        *y = 0;
-        // Go through each map
-        for m in 0..game_data.maps.len() {
-            let map = &game_data.maps[m];
-            // Door should not be in the same map as the current map
-            if m == game_state.current_map {
-                continue;
-            }
-            // Look at every square in the map
-            for c in 0..map.grid.len() {
-                // Then by each row
-                for r in 0..map.grid[c].len() {
-                    if map.grid[c][r].is_some() {
-                        match map.grid[c][r].as_ref().unwrap()  {
-                            MapData::Character(_character) => {
-                            }
-                            MapData::Object(object) => {
-                                if object.id == door_id { // If correct door is found
-                                    // Move character to new door
-                                    *door_map = m;
-                                    *x = c;
-                                    *y = r;
-                                    return;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Sets the dialog screen to display a popup with the specified msg string.
-    //    The popup closes upon clicking return and returns to the current
-    //    screen.
-    fn show_game_message(&self, game_state: &mut GameState, msg: String) {
-            game_state.dialog_message = msg;
-            game_state.dialog_option_0 = "Continue".to_string();
-            game_state.dialog_option_1 = "Close".to_string();
-            game_state.dialog_return_0 = game_state.visual_state.clone();
-            game_state.dialog_return_1 = game_state.visual_state.clone();
-            game_state.dialog_return_cancel = game_state.visual_state.clone();
-            game_state.pre_exit = false;
-            game_state.visual_state = VisualState::PlayingDialog;
-    }
-
-    // Draws a rectangular border with given start coordinates and width/heights
-    fn draw_border(&self, start_col: u16, start_row: u16, cols: u16, rows: u16) -> Result<()> {
-        // Loop over each row
-        for r in start_row..start_row+rows {
-            // Loop over each column
-            stdout().execute(MoveTo(start_col, r))?;
-            for c in start_col..start_col+cols {
-                if r == start_row || r == start_row+rows-1 {
-                    if c == start_col || c == start_col+cols-1 {
-                        stdout().execute(Print("+"))?;
-                    } else {
-                        stdout().execute(Print("-"))?;
-                    }
-                } else if c == start_col || c == start_col+cols-1 {
-                        stdout().execute(Print("|"))?;
-                } else {
-                    stdout().execute(Print(" "))?;
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    // Draws a rectangular border with given start coordinates and width/heights.
-    // Adds a highlight ('=' signs around the inside of the border) to indicate the box is selected.
-    fn draw_highlight_border(&self, start_col: u16, start_row: u16, cols: u16, rows: u16) -> Result<()> {
-        self.draw_border(start_col, start_row, cols, rows)?;
-        // Loop over each row
-        let h_start_col = start_col+1;
-        let h_start_row = start_row+1;
-        let h_cols = cols-2;
-        let h_rows = rows-2;
-        for r in h_start_row..h_start_row+h_rows {
-            // Loop over each column
-            stdout().execute(MoveTo(h_start_col, r))?;
-            for c in h_start_col..h_start_col+h_cols {
-                if r == h_start_row || r == h_start_row+h_rows-1 ||
-                   c == h_start_col || c == h_start_col+h_cols-1 {
-                    stdout().execute(Print("="))?;
-                } else {
-                    stdout().execute(Print(" "))?;
-                }
+            // Print whatever map thing or tile is there
+            let map = &game_data.maps[game_state.current_map];
+            match &map.grid[x][y] {
+                Some(MapData::Character(character)) => { self.renderer.draw_cell(col, row, character.icon)?; }
+                Some(MapData::Object(object)) => { self.renderer.draw_cell(col, row, object.icon)?; }
+                None => { self.renderer.draw_cell(col, row, map.tiles[x][y].icon)?; }
             }
         }
 
@@ -490,32 +101,69 @@ impl Screen {
     //    text centered in the box.
     // If highlight is true, adds a highlight ('=' signs around the inside of the border) to
     //    indicate the box is selected.
-    fn draw_text_box(&self, start_col: u16, start_row: u16, cols: u16, rows: u16, text: &str, highlight: bool) -> Result<()> {
+    fn draw_text_box(&mut self, start_col: u16, start_row: u16, cols: u16, rows: u16, text: &str, highlight: bool) -> Result<()> {
         if highlight {
-            self.draw_highlight_border(start_col, start_row, cols, rows)?;
+            self.renderer.draw_highlight_border(start_col, start_row, cols, rows)?;
         } else {
-            self.draw_border(start_col, start_row, cols, rows)?;
+            self.renderer.draw_border(start_col, start_row, cols, rows)?;
         }
         let text_rows = rows - 4;
         let text_cols = cols - 4;
         let text_start_col = start_col + 2;
         let text_start_row = start_row + 2;
         let lines = textwrap::wrap(text, text_cols as usize);
-        let mut row = text_start_row +
+        let start_row = text_start_row +
                            self.vertically_center_start_position(lines.len() as u16, text_rows);
-        for line in lines {
-            execute!(
-                stdout(),
-                MoveTo(self.horizontally_center_start_position(&line, text_cols) + text_start_col, row),
-                Print(line),
-            )?;
-            row += 1;
+        for (i, line) in lines.iter().enumerate() {
+            self.renderer.draw_text(self.horizontally_center_start_position(line, text_cols) + text_start_col, start_row + i as u16, line)?;
+        }
+
+        Ok(())
+    }
+
+    // Draws a box with a list of options inside, one after the other, with the selected option marked
+    //    with a '>' and options that can't be chosen marked with an 'x'. Options given as (text, can
+    //    be chosen) that are too long for one line are wrapped. When there are more options than fit,
+    //    the list scrolls to keep the selected option in view, with arrows on the right showing that
+    //    there are more options above or below.
+    fn draw_option_list(&mut self, start_col: u16, start_row: u16, cols: u16, rows: u16, options: &[(&str, bool)],
+                        selected: usize) -> Result<()> {
+        self.renderer.draw_border(start_col, start_row, cols, rows)?;
+
+        // Lay out every option's lines, remembering which lines belong to the selected option
+        let mut lines = Vec::<String>::new();
+        let (mut selected_start, mut selected_end) = (0, 0);
+        for (i, (option, enabled)) in options.iter().enumerate() {
+            if i == selected {
+                selected_start = lines.len();
+            }
+            let marker = format!("{}{}", if i == selected { ">" } else { " " }, if *enabled { " " } else { "x" });
+            for (l, line) in textwrap::wrap(option, (cols - 7) as usize).iter().enumerate() {
+                lines.push(format!("{} {}", if l == 0 { marker.as_str() } else { "  " }, line));
+            }
+            if i == selected {
+                selected_end = lines.len();
+            }
+        }
+
+        // Scroll just far enough that the selected option can be seen, starting from its first line if
+        //    it is too long to fit
+        let visible = (rows - 2) as usize;
+        let first = if selected_end > visible { (selected_end - visible).min(selected_start) } else { 0 };
+        for (r, line) in lines.iter().skip(first).take(visible).enumerate() {
+            self.renderer.draw_text(start_col + 2, start_row + 1 + r as u16, line)?;
+        }
+        if first > 0 {
+            self.renderer.draw_cell(start_col + cols - 2, start_row + 1, '^')?;
+        }
+        if first + visible < lines.len() {
+            self.renderer.draw_cell(start_col + cols - 2, start_row + rows - 2, 'v')?;
         }
 
         Ok(())
     }
 
-    fn draw_item_grid(&self, items: &[Vec<Option<Object>>], start_col: u16, start_row: u16,
+    fn draw_item_grid(&mut self, items: &[Vec<Option<Object>>], start_col: u16, start_row: u16,
                       selected_col: usize, selected_row: usize) -> Result<()> {
         let box_cols: u16 = 18;
         let mut box_rows: u16 = 7;
@@ -529,9 +177,9 @@ impl Screen {
                     box_rows += 1;
                 }
                 if selected_col == c && selected_row == r {
-                    self.draw_highlight_border(box_start_col, box_start_row, box_cols, box_rows)?;
+                    self.renderer.draw_highlight_border(box_start_col, box_start_row, box_cols, box_rows)?;
                 } else {
-                    self.draw_border(box_start_col, box_start_row, box_cols, box_rows)?;
+                    self.renderer.draw_border(box_start_col, box_start_row, box_cols, box_rows)?;
                 }
                 if item_slot.is_some() {
                     let item = items[c][r].as_ref().unwrap();
@@ -544,16 +192,31 @@ impl Screen {
                                               + self.horizontally_center_start_position("i", box_cols);
                     let icon_start_row = box_start_row
                                               + self.vertically_center_start_position(1, box_rows);
-                    stdout().execute(MoveTo(name_start_col, box_start_row+2))?;
-                    stdout().execute(Print(name))?;
-                    stdout().execute(MoveTo(icon_start_col, icon_start_row))?;
-                    stdout().execute(Print(icon))?;
+                    self.renderer.draw_text(name_start_col, box_start_row+2, &name)?;
+                    self.renderer.draw_cell(icon_start_col, icon_start_row, icon)?;
 
                     let use_start_col = box_start_col
                                             + self.horizontally_center_start_position("Use?", box_cols);
                     if r == selected_row && c == selected_col {
-                        stdout().execute(MoveTo(use_start_col, box_start_row + box_rows - 2))?;
-                        stdout().execute(Print("Use?"))?;
+                        self.renderer.draw_text(use_start_col, box_start_row + box_rows - 2, "Use?")?;
+                    }
+
+                    // Items that can be equipped show whether they are, along with how to change
+                    //    that when selected
+                    let equip_label =
+                        if item.equip.is_none() {
+                            ""
+                        } else if r == selected_row && c == selected_col {
+                            if item.equipped { "Space: Unequip" } else { "Space: Equip" }
+                        } else if item.equipped {
+                            "Equipped"
+                        } else {
+                            ""
+                        };
+                    if !equip_label.is_empty() {
+                        let equip_start_col = box_start_col
+                                                  + self.horizontally_center_start_position(equip_label, box_cols);
+                        self.renderer.draw_text(equip_start_col, box_start_row + 4, equip_label)?;
                     }
                 }
                 if r == 2 { // Temporary fix to make the items fit perfectly in the screen
@@ -565,58 +228,45 @@ impl Screen {
         Ok(())
     }
 
-    // Draw stats (attributes) in a list with the top right corner at (start_col, start_row).
-    //    Stats are in format: <stat name>: <current_value>/<max_value>
-    fn draw_stat_display(&self, stats: &[attribute::Attribute], start_col: u16, start_row: u16) -> Result<()> {
-        for (row, stat) in stats.iter().enumerate() {
+    // Draw the character's stats (attributes) in a list with the top right corner at (start_col, start_row).
+    //    Stats are in format: <stat name>: <current_value>/<max_value>, followed by how much
+    //    the character's equipment and buffs add or take away, if anything
+    fn draw_stat_display(&mut self, character: &Character, start_col: u16, start_row: u16) -> Result<()> {
+        for (row, stat) in character.attributes.iter().enumerate() {
             // TODO: Figure out stat bar formatting
-            let line = format!("{}: {}/{}", stat.display_name, stat.current_val, stat.max_val);
-            execute!(
-                stdout(),
-                MoveTo(start_col, start_row + row as u16),
-                Print(line),
-            )?;
+            let mut line = format!("{}: {}/{}", stat.display_name, stat.current_val, stat.max_val);
+            let bonus = character.attribute_bonus(&stat.id);
+            if bonus != 0 {
+                line = format!("{} {:+}", line, bonus);
+            }
+            self.renderer.draw_text(start_col, start_row + row as u16, &line)?;
         }
 
         Ok(())
     }
 
-    fn draw_face(&self, start_col: u16, start_row: u16, character: &Character) -> Result<()> {
+    fn draw_face(&mut self, start_col: u16, start_row: u16, character: &Character) -> Result<()> {
         let cols = 18;
         let rows = 7;
-        self.draw_border(start_col, start_row, cols, rows)?;
+        self.renderer.draw_border(start_col, start_row, cols, rows)?;
         if character.id == "player" {
-            execute!(
-                stdout(),
-                MoveTo(start_col+5, start_row + 1),
-                Print(".       ."),
-                MoveTo(start_col+3, start_row + 4),
-                Print("|          |"),
-                MoveTo(start_col+3, start_row + 5),
-                Print("+----------+"),
-                MoveTo(start_col + self.horizontally_center_start_position(&character.name, cols), start_row+rows+1),
-                Print(&character.name),
-            )?;
+            self.renderer.draw_text(start_col+5, start_row + 1, ".       .")?;
+            self.renderer.draw_text(start_col+3, start_row + 4, "|          |")?;
+            self.renderer.draw_text(start_col+3, start_row + 5, "+----------+")?;
+            self.renderer.draw_text(start_col + self.horizontally_center_start_position(&character.name, cols), start_row+rows+1, &character.name)?;
         } else {
-            execute!(
-                stdout(),
-                MoveTo(start_col+5, start_row + 1),
-                Print("v       v"),
-                MoveTo(start_col+3, start_row + 4),
-                Print("+----------+"),
-                MoveTo(start_col+3, start_row + 5),
-                Print("|          |"),
-                MoveTo(start_col + self.horizontally_center_start_position(&character.name, cols), start_row+rows+1),
-                Print(&character.name),
-            )?;
+            self.renderer.draw_text(start_col+5, start_row + 1, "v       v")?;
+            self.renderer.draw_text(start_col+3, start_row + 4, "+----------+")?;
+            self.renderer.draw_text(start_col+3, start_row + 5, "|          |")?;
+            self.renderer.draw_text(start_col + self.horizontally_center_start_position(&character.name, cols), start_row+rows+1, &character.name)?;
         }
 
         Ok(())
     }
 
     // Draws the start screen with the game name, author, description, and instructions for how to play
-    fn draw_start_screen(&self, game_data: &mut GameData, game_state: &mut GameState) -> Result<()> {
-        self.draw_border(0, 0, 80, 20)?;
+    fn draw_start_screen(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
+        self.renderer.draw_border(0, 0, 80, 20)?;
         // Print game info
         let mut lines = Vec::<&str>::new();
         let name_line = format!("Welcome to {}", game_data.info.name);
@@ -637,62 +287,50 @@ impl Screen {
         lines.push("Use WASD or the Arrow Keys to move around the world.");
         lines.push("Use Enter to interact with objects in the world.");
         lines.push("Use E to open your inventory, WASD/Arrows");
-        lines.push("to move within, and Enter to select items.");
+        lines.push("to move within, Enter to use items, and Space to equip them.");
         lines.push("Press Enter to select options within dialog boxes.");
+        lines.push("Use Q to see your quests, and P to save your progress.");
         lines.push("");
-        lines.push("");
-        lines.push("Press Enter to start the game.");
-
-        let mut row = self.vertically_center_start_position(lines.len() as u16, self.current_rows);
-        for line in lines {
-            execute!(
-                stdout(),
-                MoveTo(self.horizontally_center_start_position(line, self.current_columns), row),
-                Print(line),
-            )?;
-            row += 1;
+
+        let save_exists = game_state.save_path.exists();
+        if save_exists {
+            lines.push("Press Enter to start a new game, or C to continue.");
+        } else {
+            lines.push("Press Enter to start the game.");
         }
 
-        if !game_state.last_character_processed {
+        let start_row = self.vertically_center_start_position(lines.len() as u16, self.current_rows);
+        for (i, line) in lines.iter().enumerate() {
+            self.renderer.draw_text(self.horizontally_center_start_position(line, self.current_columns), start_row + i as u16, line)?;
+        }
 
-            let keycode = match game_state.last_character_pressed.as_ref().unwrap() {
-                Event::Key(x) => {
-                    x.code
-                },
-                _ => { KeyCode::Null }
-            };
+        Ok(())
+    }
 
-            // Process exiting the game
-            // TODO: Add processing for other key presses here
-            // For example, control+C via something like
-            // game_state.last_character_pressed.as_ref().unwrap().modifiers == KeyModifiers::CONTROL && keycode == KeyCode::Char('C')
-            if keycode == KeyCode::Esc {
-                self.handle_exit_key(game_state);
-
-            } else if keycode == KeyCode::Enter {
-                // Change to map view
-                game_state.visual_state = VisualState::PlayingMap;
-            }
+    // Handles key presses on the start screen
+    fn handle_start_screen_input(&self, keycode: KeyCode, game_data: &mut GameData, game_state: &mut GameState) {
+        let save_exists = game_state.save_path.exists();
 
-            game_state.last_character_processed = true;
-            match self.draw(game_data, game_state) {
-                Ok(_) => {},
-                Err(_) => {
-                    println!("ERROR: Problem encountered while drawing screen, exiting!");
-                    self.end()?;
-                }
-            }
-        }
+        // Process exiting the game
+        if keycode == KeyCode::Esc {
+            engine::request_exit(game_state);
 
-        Ok(())
+        } else if keycode == KeyCode::Enter {
+            // Change to map view
+            engine::start_game(game_state);
+
+        } else if save_exists && (keycode == KeyCode::Char('c') || keycode == KeyCode::Char('C')) {
+            // Continue the saved game
+            engine::continue_progress(game_data, game_state);
+        }
     }
 
-    // Draws the playing map (main gameplay screen) and handles keypress input for moving the character,
-    //      item interaction, and changing screens.
-    fn draw_playing_map(&self, game_data: &mut GameData, game_state: &mut GameState) -> Result<()> {
+    // Draws the playing map (main gameplay screen) with the room description, the room itself, and
+    //      the player.
+    fn draw_playing_map(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
 
-        // Clear the screen
-        self.draw_border(0, 0, 80, 20)?;
+        // Draw the screen border
+        self.renderer.draw_border(0, 0, 80, 20)?;
 
         // Find the current map that the player is in
         let map = &game_data.maps[game_state.current_map];
@@ -701,207 +339,134 @@ impl Screen {
         let description = textwrap::wrap(&map.info.description, 80-4);
 
         // Draw box at the top
-        self.draw_border(0, 0, 80, 2+description.len() as u16)?;
+        self.renderer.draw_border(0, 0, 80, 2+description.len() as u16)?;
 
         // Draw the map room description
         for (i, line) in description.iter().enumerate() {
-            execute!(
-                stdout(),
-                MoveTo(2, 1+i as u16),
-                Print(line),
-            )?;
+            self.renderer.draw_text(2, 1+i as u16, line)?;
         }
 
-        // Draw the map room itself
+        // Draw the map room itself. Small maps are drawn whole, while maps too big to fit in the
+        //    space below the description are drawn through a viewport that follows the player,
+        //    with arrows at the edges pointing towards the parts of the map that are off screen.
+        let map_cols = map.grid.len() as u16;
+        let map_rows = map.grid[0].len() as u16;
+        let player_x = game_state.current_player_x;
+        let player_y = game_state.current_player_y;
 
-        let start_c = 8;
-        let start_r = 6;
+        // The space the map can be drawn in, leaving room for the arrows inside the screen border
+        let area_start_r = 3 + description.len() as u16;
+        let area_rows = (self.current_rows - 2).saturating_sub(area_start_r);
+        let (start_c, first_c, visible_cols) = viewport(map_cols, player_x, 2, self.current_columns - 4, 8);
+        let (start_r, first_r, visible_rows) = viewport(map_rows, player_y, area_start_r, area_rows, 6);
 
-        // Border first
-        self.draw_border(start_c, start_r, map.grid.len() as u16, map.grid[0].len() as u16)?;
-
-        // Then items
         // Go first by each column
-        for c in 0..map.grid.len() {
+        for c in first_c..first_c+visible_cols {
             // Then by each row
-            for r in 0..map.grid[c].len() {
-                if map.grid[c][r].is_some() {
-                    match map.grid[c][r].as_ref().unwrap()  {
-                        MapData::Character(character) => {
-                            execute!(
-                                stdout(),
-                                MoveTo(start_c+c as u16, start_r+r as u16),
-                                Print(character.icon),
-                            )?;
-                        }
-                        MapData::Object(object) => {
-                            execute!(
-                                stdout(),
-                                MoveTo(start_c+c as u16, start_r+r as u16),
-                                Print(object.icon),
-                            )?;
-                        }
+            for r in first_r..first_r+visible_rows {
+                let col = start_c + c - first_c;
+                let row = start_r + r - first_r;
+                match &map.grid[c as usize][r as usize] {
+                    Some(MapData::Character(character)) => {
+                        self.renderer.draw_cell(col, row, character.icon)?;
+                    }
+                    Some(MapData::Object(object)) => {
+                        self.renderer.draw_cell(col, row, object.icon)?;
+                    }
+                    None => {
+                        // Otherwise the tile itself, such as a wall
+                        self.renderer.draw_cell(col, row, map.tiles[c as usize][r as usize].icon)?;
                     }
                 }
             }
         }
 
-        // Then the player
-        self.blink_player_cursor(game_data, game_state, start_c, start_r,
-                                 game_state.current_player_x,
-                                 game_state.current_player_y)?;
-
-
-        // Process any input the player provided
-        if !game_state.last_character_processed {
-
-            let keycode = match game_state.last_character_pressed.as_ref().unwrap() {
-                Event::Key(x) => {
-                    x.code
-                },
-                _ => { KeyCode::Null }
-            };
-
-            if keycode == KeyCode::Esc {
-                // Process exiting the game
-                self.handle_exit_key(game_state);
-
-            } else if keycode == KeyCode::Char('M') {
-                // Change to the next map
-                if game_state.current_map + 1 <  game_data.maps.len() {
-                    game_state.current_map += 1;
-                } else {
-                    game_state.current_map = 0;
-                }
-
-            } else if keycode == KeyCode::Char('H') {
-                // Change to home view
-                game_state.visual_state = VisualState::StartScreen;
-                execute!(
-                        stdout(),
-                        MoveTo(10, 11),
-                        Print("Changing to start screen")
-                    )?;
-
-            } else if keycode == KeyCode::Up || keycode == KeyCode::Char('w') {
-                // Handle moving the player upward
-                if self.check_move_available(game_data, game_state, 0, -1) {
-                    game_state.current_player_y -= 1;
-                    game_state.cursor_blink = true;
-                }
-
-            } else if keycode == KeyCode::Down || keycode == KeyCode::Char('s') {
-                // Handle moving the player downward
-                if self.check_move_available(game_data, game_state, 0, 1) {
-                    game_state.current_player_y += 1;
-                    game_state.cursor_blink = true;
-                }
-
-            } else if keycode == KeyCode::Left || keycode == KeyCode::Char('a') {
-                // Handle moving the player leftward
-                if self.check_move_available(game_data, game_state, -1, 0) {
-                    game_state.current_player_x -= 1;
-                    game_state.cursor_blink = true;
-                }
+        // Then the arrows for any parts of the map that are off screen
+        let middle_c = start_c + visible_cols/2;
+        let middle_r = start_r + visible_rows/2;
+        if first_c > 0 {
+            self.renderer.draw_cell(start_c-1, middle_r, '<')?;
+        }
+        if first_c + visible_cols < map_cols {
+            self.renderer.draw_cell(start_c+visible_cols, middle_r, '>')?;
+        }
+        if first_r > 0 {
+            self.renderer.draw_cell(middle_c, start_r-1, '^')?;
+        }
+        if first_r + visible_rows < map_rows {
+            self.renderer.draw_cell(middle_c, start_r+visible_rows, 'v')?;
+        }
 
-            } else if keycode == KeyCode::Right || keycode == KeyCode::Char('d') {
-                // Handle moving the player rightward
-                if self.check_move_available(game_data, game_state, 1, 0) {
-                    game_state.current_player_x += 1;
-                    game_state.cursor_blink = true;
-                }
+        // Then the player
+        if player_x >= first_c && player_x < first_c+visible_cols &&
+           player_y >= first_r && player_y < first_r+visible_rows {
+            self.blink_player_cursor(game_data, game_state,
+                                     start_c + player_x - first_c,
+                                     start_r + player_y - first_r)?;
+        }
 
-            } else if keycode == KeyCode::Enter {
-                // Handle interacting with an object the player is over
-                self.handle_interact_key(game_state, game_data);
-            } else if keycode == KeyCode::Char('E') || keycode == KeyCode::Char('e') {
-                // Handle opening the player's inventory
-                game_state.visual_state = VisualState::PlayingInventory;
-                execute!(
-                        stdout(),
-                        MoveTo(10, 11),
-                        Print("Changing to Inventory")
-                    )?;
-            }
-
-            game_state.last_character_processed = true;
-            match self.draw(game_data, game_state) {
-                Ok(_) => {},
-                Err(_) => {
-                    println!("ERROR: Problem encountered while drawing screen, exiting!");
-                    self.end()?;
-                }
+        // And a hint in the bottom border for what Enter does to the object the player is on
+        if let Some(MapData::Object(object)) = &map.grid[player_x as usize][player_y as usize] {
+            if let Some(prompt) = object.category.interact_prompt() {
+                let hint = format!(" Enter: {} {} ", prompt, object.name);
+                self.renderer.draw_text(self.horizontally_center_start_position(&hint, self.current_columns),
+                                        self.current_rows - 1, &hint)?;
             }
         }
 
         Ok(())
     }
 
-
-    // Returns true if there is space on the map for the player to move delta_x spaces right and delta_y spaces down.
-    // Returns false if there is an object in the way or the move is out of map bounds.
-    fn check_move_available(&self, game_data: &GameData, game_state: &mut GameState, delta_x: i16, delta_y: i16) -> bool {
-
-        let target_x = game_state.current_player_x as i16 + delta_x;
-        let target_y = game_state.current_player_y as i16 + delta_y;
-        let map = &game_data.maps[game_state.current_map];
-
-        // Check to make sure they can't leave the room boundaries (except for doors)
-        if target_x > 0 && target_x < (map.grid.len()-1) as i16 &&
-           target_y > 0 && target_y < (map.grid[0].len()-1) as i16 {
-            // Ok, they're within the room's boundaries, check objects/characters/empty spaces
-            if map.grid[target_x as usize][target_y as usize].is_some() {
-                // There's something here, find out what it is
-                match map.grid[target_x as usize][target_y as usize].as_ref().unwrap() {
-                    MapData::Character(_) => {
-                        // All characters can be walked over (for interacting)
-                        true
-                    }
-                    MapData::Object(object) => {
-                        // Only collidable objects can't be walked over
-                        match object.category.as_str() {
-                            "collidable" => { false }
-                            _ => { true }
-                        }
-                    }
-
-                }
-
-            } else {
-                // Empty space, they can obviously go there
-                true
-            }
-
-        } else if target_x == 0 || target_x == (map.grid.len()-1) as i16 ||
-            target_y == 0 || target_y == (map.grid[0].len()-1) as i16 {
-            // This is a wall, make sure a door is there
-            if map.grid[target_x as usize][target_y as usize].is_some() {
-                // There's something here, find out what it is
-                match map.grid[target_x as usize][target_y as usize].as_ref().unwrap() {
-                    MapData::Object(object) => {
-                        match object.category.as_str() {
-                            "door" => { true }
-                            _ => { false }
-                        }
-                    }
-                    _ => { false } // Although this should never happen (no characters in walls!)
-                }
-            } else {
-                // This is a wall, they cannot enter it
-                false
-            }
-        } else {
-            // No idea where they are, but they def shouldn't be there and can't go anywhere
-            false
+    // Handles key presses on the playing map for moving the character, item interaction, and
+    //      changing screens
+    fn handle_playing_map_input(&self, keycode: KeyCode, game_data: &mut GameData, game_state: &mut GameState) {
+        if keycode == KeyCode::Esc {
+            // Process exiting the game
+            engine::request_exit(game_state);
+
+        } else if keycode == KeyCode::Char('M') {
+            // Change to the next map
+            engine::next_map(game_data, game_state);
+
+        } else if keycode == KeyCode::Char('H') {
+            // Change to home view
+            engine::show_start_screen(game_state);
+
+        } else if keycode == KeyCode::Up || keycode == KeyCode::Char('w') {
+            // Handle moving the player upward
+            engine::move_player(game_data, game_state, Direction::Up);
+
+        } else if keycode == KeyCode::Down || keycode == KeyCode::Char('s') {
+            // Handle moving the player downward
+            engine::move_player(game_data, game_state, Direction::Down);
+
+        } else if keycode == KeyCode::Left || keycode == KeyCode::Char('a') {
+            // Handle moving the player leftward
+            engine::move_player(game_data, game_state, Direction::Left);
+
+        } else if keycode == KeyCode::Right || keycode == KeyCode::Char('d') {
+            // Handle moving the player rightward
+            engine::move_player(game_data, game_state, Direction::Right);
+
+        } else if keycode == KeyCode::Enter {
+            // Handle interacting with an object the player is over
+            engine::interact(game_data, game_state);
+        } else if keycode == KeyCode::Char('P') || keycode == KeyCode::Char('p') {
+            // Handle saving the game
+            engine::save_progress(game_data, game_state);
+        } else if keycode == KeyCode::Char('E') || keycode == KeyCode::Char('e') {
+            // Handle opening the player's inventory
+            engine::open_inventory(game_state);
+        } else if keycode == KeyCode::Char('Q') || keycode == KeyCode::Char('q') {
+            // Handle opening the quest log
+            engine::open_quest_log(game_state);
         }
-
     }
 
 
-
-    // Draws a 50 x 12 dialog box with two buttons. Dialog and button text is specified in game_state.
-    // Handles key presses and button highlighting.
-    fn draw_playing_dialog(&self, game_data: &mut GameData, game_state: &mut GameState) -> Result<()> {
+    // Draws a 50 x 12 dialog box with a row of buttons. Dialog and button text is specified in
+    //    game_state. Highlights the selected button.
+    fn draw_playing_dialog(&mut self, _game_data: &GameData, game_state: &GameState) -> Result<()> {
 
         // Dialog box width
         let cols = 80;
@@ -909,13 +474,13 @@ impl Screen {
         let width = 50;
         let height = 12;
         let button_height = 3;
+        let left = (cols-width)/2;
+        let button_row = (rows-height)/2+height-button_height;
 
         // Draw dialog box border
-        self.draw_border((cols-width)/2, (rows-height)/2,width,height)?;
+        self.renderer.draw_border(left, (rows-height)/2,width,height)?;
         // Draw buttons border
-        self.draw_border((cols-width)/2, (rows-height)/2+height-button_height, width, button_height)?;
-        // Split into 2 buttons
-        self.draw_border((cols-width)/2, (rows-height)/2+height-button_height, width/2, button_height)?;
+        self.renderer.draw_border(left, button_row, width, button_height)?;
 
         // Create a wrapped version of the dialog message
         let message = textwrap::wrap(&game_state.dialog_message, (width-4) as usize);
@@ -923,156 +488,89 @@ impl Screen {
         // Draw the dialog message
         let vertical_start = self.vertically_center_start_position(message.len() as u16, self.current_rows);
         for (i, line) in message.iter().enumerate() {
-            execute!(
-                stdout(),
-                //MoveTo((cols-width)/2+2, (rows-height)/2+1+i as u16),
-                MoveTo(self.horizontally_center_start_position(line, self.current_columns), vertical_start + i as u16 - 1),
-                Print(line),
-            )?;
+            self.renderer.draw_text(self.horizontally_center_start_position(line, self.current_columns), vertical_start + i as u16 - 1, line)?;
         }
 
-        // Display selected button highlighting
-        if game_state.dialog_selected == 0 {
-            for i in 0..width/2-2 {
-                execute!(
-                    stdout(),
-                    MoveTo((cols-width)/2+1+i, (rows-height)/2+height-button_height+1),
-                    Print("="),
-                )?;
-            }
-        } else {
-            for i in 0..width/2-1 {
-                execute!(
-                    stdout(),
-                    MoveTo((cols-width)/2 + width/2 + i, (rows-height)/2+height-button_height+1),
-                    Print("="),
-                )?;
+        // Split into a button for each option, sharing the borders between them
+        let buttons = game_state.dialog_options.len().max(1) as u16;
+        for (i, (text, _)) in game_state.dialog_options.iter().enumerate() {
+            let start = left + i as u16 * (width-1) / buttons;
+            let end = left + (i as u16 + 1) * (width-1) / buttons;
+            self.renderer.draw_border(start, button_row, end-start+1, button_height)?;
+
+            // Display selected button highlighting
+            if game_state.dialog_selected == i {
+                self.renderer.draw_text(start+1, button_row+1, &"=".repeat((end-start-1) as usize))?;
             }
+
+            // Display button text
+            let text_start = start + 1 + self.horizontally_center_start_position(text, end-start-1);
+            self.renderer.draw_text(text_start, button_row+1, text)?;
         }
 
-        // Display button text
-        let center_0 = (cols-width)/2 +
-                            self.horizontally_center_start_position(&game_state.dialog_option_0, width/2);
-        let center_1 = cols/2 +
-                            self.horizontally_center_start_position(&game_state.dialog_option_1, width/2);
-        execute!(
-            stdout(),
-            MoveTo(center_0, (rows-height)/2+height-button_height+1),
-            Print(&game_state.dialog_option_0),
-            MoveTo(center_1, (rows-height)/2+height-button_height+1),
-            Print(&game_state.dialog_option_1),
-        )?;
-
-        if !game_state.last_character_processed {
-
-            let keycode = match game_state.last_character_pressed.as_ref().unwrap() {
-                Event::Key(x) => {
-                    x.code
-                },
-                _ => { KeyCode::Null }
-            };
+        Ok(())
+    }
 
-            // Process exiting the game
-            // TODO: Add processing for other key presses here
-            // For example, control+C via something like
-            // game_state.last_character_pressed.as_ref().unwrap().modifiers == KeyModifiers::CONTROL && keycode == KeyCode::Char('C')
-            if keycode == KeyCode::Left {
-                if game_state.dialog_selected == 1 {
-                    game_state.dialog_selected = 0
-                }
+    // Handles key presses in the dialog box for choosing between its buttons
+    fn handle_playing_dialog_input(&self, keycode: KeyCode, _game_data: &mut GameData, game_state: &mut GameState) {
+        if keycode == KeyCode::Left {
+            if game_state.dialog_selected > 0 {
+                game_state.dialog_selected -= 1;
+            }
 
-            } else if keycode == KeyCode::Right {
-                if game_state.dialog_selected == 0 {
-                    game_state.dialog_selected = 1
-                }
+        } else if keycode == KeyCode::Right {
+            if game_state.dialog_selected + 1 < game_state.dialog_options.len() {
+                game_state.dialog_selected += 1;
+            }
 
-            } else if keycode == KeyCode::Enter {
-                // Check if we need to do a full exit or a return to the previous screen
-                if game_state.pre_exit {
-                    if game_state.dialog_selected == 1 {
-                        game_state.do_exit = true;
-                    } else { // If dialog_selected == 0
-                        game_state.pre_exit = false;
-                        game_state.visual_state = game_state.dialog_return_0.clone();
-                    }
-                } else {
-                    // Set up the result and return to the previous screen
-                    game_state.dialog_result_ready = true;
-                    if game_state.dialog_selected == 0 {
-                        game_state.visual_state = game_state.dialog_return_0.clone();
-                    } else { // if dialog_selected == 1
-                        game_state.visual_state = game_state.dialog_return_1.clone();
-                    }
-                }
+        } else if keycode == KeyCode::Enter {
+            engine::choose_message_option(game_state, game_state.dialog_selected);
 
-                // Reset selected dialog button
-                game_state.dialog_selected = 0;
-
-            } else if keycode == KeyCode::Esc {
-                // Set up the (lack of) result and return to the previous screen
-                game_state.dialog_result_ready = false;
-                game_state.visual_state = game_state.dialog_return_cancel.clone();
-                
-                // Reset selected dialog button
-                game_state.dialog_selected = 0;
-
-            } else if keycode == KeyCode::Char('H') {
-
-                // Change to map view
-                game_state.visual_state = VisualState::StartScreen;
-                execute!(
-                    stdout(),
-                    MoveTo(10, 11),
-                    Print("Changing to start screen")
-                )?;
-
-            } else if keycode == KeyCode::Char('M') {
-
-                // Change to map view
-                game_state.visual_state = VisualState::PlayingMap;
-                execute!(
-                        stdout(),
-                        MoveTo(10, 11),
-                        Print("Changing to map view")
-                    )?;
+            // Reset selected dialog button
+            game_state.dialog_selected = 0;
 
-            }
+        } else if keycode == KeyCode::Esc {
+            engine::cancel_message(game_state);
 
-            game_state.last_character_processed = true;
-            match self.draw(game_data, game_state) {
-                Ok(_) => {},
-                Err(_) => {
-                    println!("ERROR: Problem encountered while drawing screen, exiting!");
-                    self.end()?;
-                }
-            }
-        }
+            // Reset selected dialog button
+            game_state.dialog_selected = 0;
 
-        Ok(())
+        } else if keycode == KeyCode::Char('H') {
+            // Change to start screen
+            engine::show_start_screen(game_state);
+
+        } else if keycode == KeyCode::Char('M') {
+            // Change to map view
+            engine::return_to_map(game_state);
+        }
     }
 
     // Draws inventory/stat screen.
-    // Handles key presses.
-    fn draw_playing_inventory(&self, game_data: &mut GameData, game_state: &mut GameState) -> Result<()> {
+    fn draw_playing_inventory(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
         // Inventory screen width
         let cols = self.current_columns;
         let rows = self.current_rows;
         let grid_start_col: u16 = 28;
 
         // Draw screen border
-        self.draw_border(0, 0, cols, rows)?;
+        self.renderer.draw_border(0, 0, cols, rows)?;
 
         // Draw player face
         self.draw_face(5, 2, game_data.info.player.as_ref().unwrap())?;
 
         // Draw player stats
-        let player_stats = &game_data.info.player.as_ref().unwrap().attributes;
-        self.draw_stat_display(player_stats, 2, (rows*2)/3)?;
+        self.draw_stat_display(game_data.info.player.as_ref().unwrap(), 2, (rows*2)/3)?;
 
         // Draw all the items in the inventory
         let inventory = &game_data.info.player.as_ref().unwrap().inventory;
         self.draw_item_grid(inventory, grid_start_col, 0, game_state.inventory_x, game_state.inventory_y)?;
 
+        Ok(())
+    }
+
+    // Handles key presses on the inventory screen for selecting and using items
+    fn handle_playing_inventory_input(&self, keycode: KeyCode, game_data: &mut GameData, game_state: &mut GameState) {
+        let inventory = &game_data.info.player.as_ref().unwrap().inventory;
         let inventory_width = inventory.len();
         let inventory_height =
             if inventory_width > 0 {
@@ -1081,80 +579,50 @@ impl Screen {
                 0
             };
 
-        // If the keypress has not been processed yet, process it.
-        if !game_state.last_character_processed {
-
-            // Get keypress
-            let keycode = match game_state.last_character_pressed.as_ref().unwrap() {
-                Event::Key(x) => {
-                    x.code
-                },
-                _ => { KeyCode::Null }
-            };
-
-            // Process keypresses for selecting items
-            if keycode == KeyCode::Left {
-                if game_state.inventory_x == 0 {
-                    game_state.inventory_x = inventory_width - 1
-                } else {
-                    game_state.inventory_x -= 1;
-                }
-
-            } else if keycode == KeyCode::Right {
-                game_state.inventory_x = (game_state.inventory_x + 1) % inventory_width;
-
-            } else if keycode == KeyCode::Up {
-                if game_state.inventory_y == 0 {
-                    game_state.inventory_y = inventory_height - 1
-                } else {
-                    game_state.inventory_y -= 1;
-                }
+        // Process keypresses for selecting items
+        if keycode == KeyCode::Left {
+            if game_state.inventory_x == 0 {
+                game_state.inventory_x = inventory_width - 1
+            } else {
+                game_state.inventory_x -= 1;
+            }
 
-            } else if keycode == KeyCode::Down {
-                game_state.inventory_y = (game_state.inventory_y + 1) % inventory_height;
+        } else if keycode == KeyCode::Right {
+            game_state.inventory_x = (game_state.inventory_x + 1) % inventory_width;
 
-            } else if keycode == KeyCode::Enter {
-                self.use_object(game_state, game_data);
+        } else if keycode == KeyCode::Up {
+            if game_state.inventory_y == 0 {
+                game_state.inventory_y = inventory_height - 1
+            } else {
+                game_state.inventory_y -= 1;
             }
 
-            // Process keypresses for changing screens
-            if keycode == KeyCode::Char('H') {
-                // Change to start screen
-                game_state.visual_state = VisualState::StartScreen;
-                execute!(
-                    stdout(),
-                    MoveTo(10, 11),
-                    Print("Changing to start screen")
-                )?;
-            } else if keycode == KeyCode::Char('m')
-                   || keycode == KeyCode::Esc
-                   || keycode == KeyCode::Char('e') {
-                // Change to map view
-                game_state.visual_state = VisualState::PlayingMap;
-                execute!(
-                        stdout(),
-                        MoveTo(10, 11),
-                        Print("Changing to map view")
-                    )?;
-            }
+        } else if keycode == KeyCode::Down {
+            game_state.inventory_y = (game_state.inventory_y + 1) % inventory_height;
 
-            game_state.last_character_processed = true;
-            match self.draw(game_data, game_state) {
-                Ok(_) => {},
-                Err(_) => {
-                    println!("ERROR: Problem encountered while drawing screen, exiting!");
-                    self.end()?;
-                }
-            }
+        } else if keycode == KeyCode::Enter {
+            engine::use_item(game_data, game_state, game_state.inventory_x, game_state.inventory_y);
+
+        } else if keycode == KeyCode::Char(' ') {
+            engine::toggle_equipped(game_data, game_state, game_state.inventory_x, game_state.inventory_y);
         }
 
-        Ok(())
+        // Process keypresses for changing screens
+        if keycode == KeyCode::Char('H') {
+            // Change to start screen
+            engine::show_start_screen(game_state);
+        } else if keycode == KeyCode::Char('m')
+               || keycode == KeyCode::Esc
+               || keycode == KeyCode::Char('e') {
+            // Change to map view
+            engine::return_to_map(game_state);
+        }
     }
 
     // Draws the player interaction screen. Shows the player character and the
     //    NPC being interacted with. Shows the NPC dialog and gives the player
     //    dialog options that can be selected.
-    fn draw_playing_character_interaction(&self, game_data: &mut GameData, game_state: &mut GameState) -> Result<()> {
+    fn draw_playing_character_interaction(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
         // Interaction screen width
         let cols = self.current_columns;
         let rows = self.current_rows;
@@ -1163,28 +631,24 @@ impl Screen {
         // Get dialog from hashmap
         let dialog = game_data.dialogs.get(&game_state.npc_dialog_id).unwrap();
         let npc_dialog = &dialog.npc_dialog;
-        let dialog_0 = &dialog.option_0.dialog;
-        let dialog_1 = &dialog.option_1.dialog;
 
         // Draw screen borders
-        self.draw_border(0, 0, cols, rows)?;
-        self.draw_border(cols/2, 0, cols/2, rows)?;
+        self.renderer.draw_border(0, 0, cols, rows)?;
+        self.renderer.draw_border(cols/2, 0, cols/2, rows)?;
 
         // Draw dialog options
-        self.draw_text_box(0, rows-dialog_height,
-             cols/4, dialog_height, dialog_0, game_state.dialog_selected == 0)?;
-        self.draw_text_box((cols/4) - 1, rows-dialog_height,
-             (cols/4) + 2, dialog_height, dialog_1, game_state.dialog_selected == 1)?;
+        let options = engine::visible_dialog_options(game_data, game_state).into_iter()
+            .map(|(option, met)| (option.dialog.as_str(), met))
+            .collect::<Vec<(&str, bool)>>();
+        self.draw_option_list(0, rows-dialog_height, (cols/2) + 1, dialog_height, &options,
+                              game_state.dialog_selected)?;
 
         // Draw NPC dialog
         self.draw_text_box(cols/2, rows-dialog_height,
              cols/2, dialog_height, npc_dialog, false)?;
         // (cover up the dialog box line)
-        stdout().execute(MoveTo((cols/2)+1, rows-dialog_height))?;
-        for _i in 0..((cols/2) - 2) {
-            stdout().execute(Print(" "))?;
-        }
-        stdout().execute(Print("|"))?;
+        let cover = format!("{}|", " ".repeat(((cols/2) - 2) as usize));
+        self.renderer.draw_text((cols/2)+1, rows-dialog_height, &cover)?;
 
         // Draw player face
         self.draw_face(11, 2, game_data.info.player.as_ref().unwrap())?;
@@ -1199,92 +663,232 @@ impl Screen {
             MapData::Object(_object) => {}
         }
 
-        // If the keypress has not been processed yet, process it.
-        if !game_state.last_character_processed {
+        Ok(())
+    }
 
-            // Get keypress
-            let keycode = match game_state.last_character_pressed.as_ref().unwrap() {
-                Event::Key(x) => {
-                    x.code
-                },
-                _ => { KeyCode::Null }
-            };
+    // Handles key presses on the interaction screen for choosing dialog options
+    fn handle_playing_character_interaction_input(&self, keycode: KeyCode, game_data: &mut GameData, game_state: &mut GameState) {
+        // Process keypresses for selecting options
+        let options = engine::visible_dialog_options(game_data, game_state).len();
+        if keycode == KeyCode::Up || keycode == KeyCode::Left {
+            if game_state.dialog_selected > 0 {
+                game_state.dialog_selected -= 1;
+            }
 
-            // Process keypresses for selecting items
-            if keycode == KeyCode::Left {
-                game_state.dialog_selected = 0;
+        } else if keycode == KeyCode::Down || keycode == KeyCode::Right {
+            if game_state.dialog_selected + 1 < options {
+                game_state.dialog_selected += 1;
+            }
 
-            } else if keycode == KeyCode::Right {
-                game_state.dialog_selected = 1;
+        } else if keycode == KeyCode::Enter {
+            let option = game_state.dialog_selected;
+            // Reset selected dialog
+            game_state.dialog_selected = 0;
+            engine::choose_dialog_option(game_data, game_state, option);
+        }
 
-            } else if keycode == KeyCode::Enter {
-                let next =
-                    if game_state.dialog_selected == 0 {
-                        &dialog.option_0.next
-                    } else {
-                        &dialog.option_1.next
-                    };
-                // Reset selected dialog
-                game_state.dialog_selected = 0;
+        // Process keypresses for changing screens
+        if keycode == KeyCode::Char('H') {
+            // Change to start screen
+            engine::show_start_screen(game_state);
+        } else if keycode == KeyCode::Char('m')
+               || keycode == KeyCode::Esc {
+            // Reset selected dialog
+            game_state.dialog_selected = 0;
+            // Change to map view
+            engine::return_to_map(game_state);
+        }
+    }
+
+    // Draws the fight screen. Shows the player and the NPC being fought along with their health,
+    //    the list of attacks the player can choose from, and a message describing the last round.
+    fn draw_playing_character_fight(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
+        // Fight screen width
+        let cols = self.current_columns;
+        let rows = self.current_rows;
+        let box_height = 8;
 
-                if next == "exit" {
-                    game_state.visual_state = game_state.dialog_return_cancel.clone();
+        // Get the NPC character being fought. If there is no longer a character there, there is
+        //    nothing to fight, so the map is shown until the fight is ended.
+        let map = &game_data.maps[game_state.current_map];
+        let npc = match map.grid[game_state.fight_x][game_state.fight_y].as_ref() {
+            Some(MapData::Character(character)) => { character }
+            _ => {
+                return self.draw_playing_map(game_data, game_state);
+            }
+        };
+        let player = game_data.info.player.as_ref().unwrap().clone();
 
-                } else if next == "inventory" {
-                    game_state.visual_state = VisualState::PlayingInventory;
+        // Draw screen borders
+        self.renderer.draw_border(0, 0, cols, rows)?;
+        self.renderer.draw_border(cols/2, 0, cols/2, rows)?;
+
+        // Draw both faces, with the health of each character below their name
+        self.draw_face(11, 2, &player)?;
+        self.draw_face((cols/2) + 11, 2, npc)?;
+        self.draw_health(&player, 0, 11, cols/2)?;
+        self.draw_health(npc, cols/2, 11, cols/2)?;
+
+        // Draw the list of attacks the player can choose from
+        self.renderer.draw_border(0, rows-box_height, cols/2, box_height)?;
+        let attacks = &player.interactions.attacks;
+        if attacks.is_empty() {
+            self.renderer.draw_text(2, rows-box_height+1, "You have no attacks!")?;
+        }
+        // Scroll the list if there are more attacks than fit in the box
+        let visible_rows = (box_height - 2) as usize;
+        let first_visible = game_state.fight_selected.saturating_sub(visible_rows - 1);
+        for (i, attack) in attacks.iter().enumerate().skip(first_visible).take(visible_rows) {
+            let line =
+                if i == game_state.fight_selected {
+                    format!("> {}", attack.display_name)
+                } else {
+                    format!("  {}", attack.display_name)
+                };
+            self.renderer.draw_text(2, rows-box_height+1 + (i-first_visible) as u16, &line)?;
+        }
 
-                } else if game_data.dialogs.contains_key(next) { // if next is a dialog id
-                    game_state.npc_dialog_id = next.clone();
+        // Draw the message describing the last round of the fight
+        self.draw_text_box(cols/2, rows-box_height,
+             cols/2, box_height, &game_state.fight_message, false)?;
 
-                }
+        Ok(())
+    }
+
+    // Handles key presses on the fight screen for choosing and using attacks
+    fn handle_playing_character_fight_input(&self, keycode: KeyCode, game_data: &mut GameData, game_state: &mut GameState) {
+        let attack_count = game_data.info.player.as_ref().unwrap().interactions.attacks.len();
+
+        // Process keypresses for selecting attacks
+        if keycode == KeyCode::Up || keycode == KeyCode::Char('w') {
+            if game_state.fight_selected > 0 {
+                game_state.fight_selected -= 1;
             }
 
-            // Process keypresses for changing screens
-            if keycode == KeyCode::Char('H') {
-                // Change to start screen
-                game_state.visual_state = VisualState::StartScreen;
-                execute!(
-                    stdout(),
-                    MoveTo(10, 11),
-                    Print("Changing to start screen")
-                )?;
-            } else if keycode == KeyCode::Char('m')
-                   || keycode == KeyCode::Esc {
-                // Reset selected dialog
-                game_state.dialog_selected = 0;
-                // Change to map view
-                game_state.visual_state = VisualState::PlayingMap;
-                execute!(
-                        stdout(),
-                        MoveTo(10, 11),
-                        Print("Changing to map view")
-                    )?;
+        } else if keycode == KeyCode::Down || keycode == KeyCode::Char('s') {
+            if game_state.fight_selected + 1 < attack_count {
+                game_state.fight_selected += 1;
             }
 
-            game_state.last_character_processed = true;
-            match self.draw(game_data, game_state) {
-                Ok(_) => {},
-                Err(_) => {
-                    println!("ERROR: Problem encountered while drawing screen, exiting!");
-                    self.end()?;
+        } else if keycode == KeyCode::Enter {
+            engine::attack(game_data, game_state, game_state.fight_selected);
+
+        } else if keycode == KeyCode::Esc {
+            // Process exiting the game
+            engine::request_exit(game_state);
+        }
+    }
+
+    // Draws the quest log, with the quests the player has started listed on the left and the selected
+    //    quest on the right: its description, the stages finished so far, and the stage being worked on
+    fn draw_playing_quest_log(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
+        let cols = self.current_columns;
+        let rows = self.current_rows;
+        let list_cols = 30;
+        let text_cols = (cols - list_cols - 3) as usize;
+
+        // Draw screen border
+        self.renderer.draw_border(0, 0, cols, rows)?;
+
+        // Draw the list of quests, with a title in its top border
+        let log = engine::quest_log(game_data, game_state);
+        let names = log.iter()
+            .map(|(quest, progress)| match progress {
+                QuestProgress::Completed => { format!("{} (done)", quest.name) }
+                QuestProgress::Active(_) => { quest.name.clone() }
+            })
+            .collect::<Vec<String>>();
+        let options = names.iter().map(|name| (name.as_str(), true)).collect::<Vec<(&str, bool)>>();
+        self.draw_option_list(0, 0, list_cols, rows, &options, game_state.quest_selected)?;
+        let title = " Quests ";
+        self.renderer.draw_text(self.horizontally_center_start_position(title, list_cols), 0, title)?;
+
+        // Draw the selected quest
+        let mut lines = Vec::<String>::new();
+        match log.get(game_state.quest_selected) {
+            Some((quest, progress)) => {
+                lines.push(quest.name.clone());
+                lines.push("".to_string());
+                if !quest.description.is_empty() {
+                    lines.extend(textwrap::wrap(&quest.description, text_cols).iter().map(|line| line.to_string()));
+                    lines.push("".to_string());
+                }
+                // Stages after the one being worked on are kept a surprise
+                let done = progress.stages_done(quest);
+                for (i, stage) in quest.stages.iter().enumerate().take(done + 1) {
+                    let marker = if i < done { "[x]" } else { "[ ]" };
+                    for (l, line) in textwrap::wrap(&stage.description, text_cols - 4).iter().enumerate() {
+                        lines.push(format!("{} {}", if l == 0 { marker } else { "   " }, line));
+                    }
                 }
+                if **progress == QuestProgress::Completed {
+                    lines.push("".to_string());
+                    lines.push("Quest complete!".to_string());
+                }
+            }
+            None => {
+                lines.push("You haven't started any quests yet.".to_string());
             }
         }
+        for (r, line) in lines.iter().take((rows - 2) as usize).enumerate() {
+            self.renderer.draw_text(list_cols + 1, 1 + r as u16, line)?;
+        }
 
         Ok(())
     }
 
-    // TODO: Implementation, documentation
-    fn draw_playing_character_fight(&self, _game_data: &GameData, _game_state: &mut GameState) -> Result<()> {
-        todo!();
+    // Handles key presses on the quest log for looking through the quests
+    fn handle_playing_quest_log_input(&self, keycode: KeyCode, game_data: &mut GameData, game_state: &mut GameState) {
+        let quest_count = engine::quest_log(game_data, game_state).len();
+
+        // Process keypresses for selecting quests
+        if keycode == KeyCode::Up || keycode == KeyCode::Char('w') {
+            if game_state.quest_selected > 0 {
+                game_state.quest_selected -= 1;
+            }
+
+        } else if (keycode == KeyCode::Down || keycode == KeyCode::Char('s'))
+                  && game_state.quest_selected + 1 < quest_count {
+            game_state.quest_selected += 1;
+        }
+
+        // Process keypresses for changing screens
+        if keycode == KeyCode::Char('H') {
+            // Change to start screen
+            engine::show_start_screen(game_state);
+        } else if keycode == KeyCode::Char('m')
+               || keycode == KeyCode::Esc
+               || keycode == KeyCode::Char('q')
+               || keycode == KeyCode::Char('Q') {
+            // Change to map view
+            engine::return_to_map(game_state);
+        }
+    }
+
+    // Draws the health of a character, horizontally centered in the given container
+    fn draw_health(&mut self, character: &Character, start_col: u16, row: u16, container_cols: u16) -> Result<()> {
+        if let Some(health) = character.get_attribute(HEALTH_ATTRIBUTE_ID) {
+            let line = format!("{}: {}/{}", health.display_name, health.current_val, health.max_val);
+            self.renderer.draw_text(start_col + self.horizontally_center_start_position(&line, container_cols), row, &line)?;
+        }
+
+        Ok(())
     }
 
     // Draws the screen specified by VisualState.
-    pub fn draw(&self, game_data: &mut GameData, game_state: &mut GameState) -> Result<()> {
+    //    Only draws, any changes to the game happen in handle_input. Each frame is drawn from
+    //    scratch, and the renderer takes care of only showing what changed.
+    pub fn draw(&mut self, game_data: &GameData, game_state: &GameState) -> Result<()> {
+        self.renderer.clear()?;
+        self.draw_visual_state(&game_state.visual_state, game_data, game_state)?;
+        self.renderer.flush()?;
 
-        //self.draw_border(0, 0, 80, 20)?;
+        Ok(())
+    }
 
-        match game_state.visual_state {
+    // Draws the screen for a single VisualState
+    fn draw_visual_state(&mut self, visual_state: &VisualState, game_data: &GameData, game_state: &GameState) -> Result<()> {
+        match visual_state {
             VisualState::StartScreen => {
                 self.draw_start_screen(game_data, game_state)?;
             },
@@ -1292,6 +896,10 @@ impl Screen {
                 self.draw_playing_map(game_data, game_state)?;
             },
             VisualState::PlayingDialog => {
+                // Popup dialogs are drawn over the screen they were opened from
+                if game_state.dialog_return_cancel != VisualState::PlayingDialog {
+                    self.draw_visual_state(&game_state.dialog_return_cancel, game_data, game_state)?;
+                }
                 self.draw_playing_dialog(game_data, game_state)?;
             },
             VisualState::PlayingInventory => {
@@ -1303,40 +911,210 @@ impl Screen {
             VisualState::PlayingCharacterFight => {
                 self.draw_playing_character_fight(game_data, game_state)?;
             },
+            VisualState::PlayingQuestLog => {
+                self.draw_playing_quest_log(game_data, game_state)?;
+            },
         }
 
         Ok(())
     }
 
+    // Handles a single input event, passing key presses to the handler for the current screen.
+    //    Other events (such as the terminal being resized) don't change anything.
+    pub fn handle_input(&self, event: &Event, game_data: &mut GameData, game_state: &mut GameState) {
+        let keycode = match event {
+            Event::Key(x) => {
+                x.code
+            },
+            _ => { return; }
+        };
 
-    // Cleans up terminal and displays shutdown message
-    pub fn end(&self) -> Result<()> {
+        match game_state.visual_state {
+            VisualState::StartScreen => {
+                self.handle_start_screen_input(keycode, game_data, game_state);
+            },
+            VisualState::PlayingMap => {
+                self.handle_playing_map_input(keycode, game_data, game_state);
+            },
+            VisualState::PlayingDialog => {
+                self.handle_playing_dialog_input(keycode, game_data, game_state);
+            },
+            VisualState::PlayingInventory => {
+                self.handle_playing_inventory_input(keycode, game_data, game_state);
+            },
+            VisualState::PlayingCharacterInteraction => {
+                self.handle_playing_character_interaction_input(keycode, game_data, game_state);
+            },
+            VisualState::PlayingCharacterFight => {
+                self.handle_playing_character_fight_input(keycode, game_data, game_state);
+            },
+            VisualState::PlayingQuestLog => {
+                self.handle_playing_quest_log_input(keycode, game_data, game_state);
+            },
+        }
+    }
+}
 
-        self.reset()?;
-        println!("Shutting down, goodbye!");
+// Works out which part of a map to show along one direction (columns or rows), given the size of
+//    the map, the player's position, and the space on screen the map can be drawn in. Maps that
+//    fit are drawn whole, at the preferred start if there is room for it. Bigger maps only show
+//    as much as fits, keeping the player in the middle until the edge of the map is reached.
+//    Returns (where to start drawing on screen, first tile shown, number of tiles shown).
+fn viewport(map_len: u16, player: u16, area_start: u16, area_len: u16, preferred_start: u16) -> (u16, u16, u16) {
+    if preferred_start >= area_start && preferred_start + map_len <= area_start + area_len {
+        (preferred_start, 0, map_len)
+    } else if map_len <= area_len {
+        (area_start + (area_len - map_len)/2, 0, map_len)
+    } else {
+        let first = player.saturating_sub(area_len/2).min(map_len - area_len);
+        (area_start, first, area_len)
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::game::config_parsers::files::MemoryFiles;
+    use crate::game::engine::Direction::*;
+    use crate::game::playthrough::Playthrough;
+    use crate::game::playthrough::Step::*;
+
+    // Golden screens are stored as text in the golden folder next to this file. Run the tests with
+    //    UPDATE_GOLDEN=1 set to write the current frames as the new golden screens.
+    fn assert_golden(name: &str, frame: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src").join("game").join("screen").join("golden")
+            .join(format!("{}.txt", name));
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, format!("{}\n", frame)).unwrap();
+            return;
+        }
+        let expected = match fs::read_to_string(&path) {
+            Ok(expected) => { expected }
+            Err(_) => { panic!("Missing golden screen {:?}, run with UPDATE_GOLDEN=1 to create it", path); }
+        };
+        assert!(expected.trim_end_matches('\n') == frame,
+                "Screen doesn't match golden screen {:?}\nExpected:\n{}\nFound:\n{}", path, expected, frame);
     }
 
-    // Resets the terminal to a usable state
-    fn reset(&self) -> Result<()> {
-        // Be a good citizen and cleanup the terminal for program exit
+    fn example_game() -> Playthrough {
+        let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+        Playthrough::load(&config_path).unwrap()
+    }
 
-        // Get rid of raw mode
-        disable_raw_mode()?;
+    // The example game with some extra files, starting in a different map
+    fn example_game_starting_in(map_id: &str, x: usize, y: usize, extra_files: &[(&str, String)]) -> Playthrough {
+        let mut files = MemoryFiles::example_game_with(&[]);
+        let game_yaml = files.file("game.yaml").unwrap()
+            .replace("starting_map: \"Main Room\"", &format!("starting_map: \"{}\"", map_id))
+            .replace("  x: 1\n  y: 1", &format!("  x: {}\n  y: {}", x, y));
+        files.add_file("game.yaml", game_yaml);
+        for (path, contents) in extra_files {
+            files.add_file(path, contents.clone());
+        }
+        Playthrough::from_files(&files).unwrap()
+    }
 
-        // Turn on the cursor
-        stdout().execute(Show)?;
+    // The example game, starting instead in a dungeon much bigger than the screen
+    fn large_map_game() -> Playthrough {
+        let mut dungeon = String::from("id: \"dungeon\"\ndescription: \"A dungeon that goes on and on.\"\n\
+                                        size:\n  width: 200\n  height: 40\nobjects:\n");
+        for (x, y) in [(2, 2), (60, 12), (100, 20), (140, 30), (197, 37)] {
+            dungeon.push_str(&format!("  - id: \"table\"\n    position:\n      x: {}\n      y: {}\n", x, y));
+        }
+        example_game_starting_in("dungeon", 1, 1, &[("maps/dungeon.yaml", dungeon)])
+    }
 
-        // Restore the original size
-        execute!(stdout(), SetSize(self.original_columns, self.original_rows))?;
+    #[test]
+    fn playing_map() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 3), Move(Down, 2)]).unwrap();
+        assert_golden("playing_map", &game.frame());
+    }
+
+    #[test]
+    fn playing_map_cursor_blink() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 11), Move(Down, 4), Tick(1)]).unwrap();
+        assert_golden("playing_map_cursor_blink", &game.frame());
+    }
 
-        // Clear the screen
-        execute!(stdout(), Clear(ClearType::All))?;
+    #[test]
+    fn layout_map() {
+        let mut game = example_game_starting_in("basement", 5, 1, &[]);
+        game.run(&[Start, Move(Down, 1)]).unwrap();
+        assert_golden("layout_map", &game.frame());
+    }
 
-        // Move the cursor back down so the user can see the prompt
-        execute!(stdout(), MoveTo(0, 0))?;
+    #[test]
+    fn large_map_viewport_follows_player() {
+        let mut game = large_map_game();
+        game.run(&[Start]).unwrap();
+        assert_golden("large_map_start", &game.frame());
 
-        Ok(())
+        game.run(&[Move(Right, 100), Move(Down, 19)]).unwrap();
+        assert_eq!(game.player_position(), (101, 20));
+        assert_golden("large_map_middle", &game.frame());
+
+        game.run(&[Move(Right, 97), Move(Down, 18)]).unwrap();
+        assert_eq!(game.player_position(), (198, 38));
+        assert_golden("large_map_corner", &game.frame());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn playing_dialog() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 11), Move(Down, 4), Interact]).unwrap();
+        assert_golden("playing_dialog", &game.frame());
+    }
+
+    #[test]
+    fn playing_inventory() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 11), Move(Down, 4), Interact, ChooseMessageOption(1), OpenInventory]).unwrap();
+        assert_golden("playing_inventory", &game.frame());
+    }
+
+    #[test]
+    fn playing_character_interaction() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 10), Move(Down, 3), Interact]).unwrap();
+        assert_golden("playing_character_interaction", &game.frame());
+    }
+
+    #[test]
+    fn playing_character_interaction_scrolls() {
+        let mut dialogs = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("example_game").join("dialogs").join("dialog.yaml")).unwrap()
+            .replace("      - dialog: \"Never mind.\"", "      - dialog: \"more\"\n        next: \"more\"\n\
+                                                        \x20     - dialog: \"Never mind.\"");
+        dialogs.push_str("\n - id: \"more\"\n   npc_dialog: \"Pick a number.\"\n   options:\n");
+        for i in 1..=8 {
+            dialogs.push_str(&format!("      - dialog: \"Number {}\"\n        next: \"exit\"\n", i));
+        }
+        let mut game = example_game_starting_in("Main Room", 1, 1, &[("dialogs/dialog.yaml", dialogs)]);
+        game.run(&[Start, Move(Right, 10), Move(Down, 3), Interact, ChooseDialogOption(2)]).unwrap();
+        assert_golden("playing_character_interaction_top", &game.frame());
+
+        game.game_state.dialog_selected = 6;
+        game.draw().unwrap();
+        assert_golden("playing_character_interaction_scrolled", &game.frame());
+    }
+
+    #[test]
+    fn playing_character_fight() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 10), Move(Down, 3), Interact, ChooseDialogOption(0),
+                   ChooseDialogOption(1), Attack(0)]).unwrap();
+        assert_golden("playing_character_fight", &game.frame());
+    }
+
+    #[test]
+    fn playing_quest_log() {
+        let mut game = example_game();
+        game.run(&[Start, Move(Right, 11), Move(Down, 4), Interact, ChooseMessageOption(1), OpenQuestLog]).unwrap();
+        assert_golden("playing_quest_log", &game.frame());
+    }
+}
@@ -0,0 +1,377 @@
+use std::io::{Error, Write, stdout};
+use std::process::{exit};
+use crossterm::{
+    execute, queue,
+    style::{Print},
+    ExecutableCommand, Result,
+    terminal::{SetSize, size, disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    cursor::{MoveTo, Hide, Show},
+};
+
+// Where the screen is drawn to. The game is drawn to the terminal while it is being played, but
+// can also be drawn into a buffer in memory so that tests can check what each frame looks like.
+// Anything drawn past the edges of the renderer is cut off.
+pub trait Renderer {
+    // The number of (columns, rows) that can be drawn to
+    fn size(&self) -> (u16, u16);
+
+    // Draws a single character at (col, row)
+    fn draw_cell(&mut self, col: u16, row: u16, c: char) -> Result<()>;
+
+    // Draws text in a single row, starting at (col, row)
+    fn draw_text(&mut self, col: u16, row: u16, text: &str) -> Result<()> {
+        for (i, c) in text.chars().enumerate() {
+            self.draw_cell(col + i as u16, row, c)?;
+        }
+
+        Ok(())
+    }
+
+    // Fills the whole screen with blank space
+    fn clear(&mut self) -> Result<()>;
+
+    // Makes sure everything drawn so far is shown
+    fn flush(&mut self) -> Result<()>;
+
+    // Draws a rectangular border with given start coordinates and width/heights
+    fn draw_border(&mut self, start_col: u16, start_row: u16, cols: u16, rows: u16) -> Result<()> {
+        // Loop over each row
+        for r in start_row..start_row+rows {
+            // Loop over each column
+            let mut line = String::new();
+            for c in start_col..start_col+cols {
+                if r == start_row || r == start_row+rows-1 {
+                    if c == start_col || c == start_col+cols-1 {
+                        line.push('+');
+                    } else {
+                        line.push('-');
+                    }
+                } else if c == start_col || c == start_col+cols-1 {
+                    line.push('|');
+                } else {
+                    line.push(' ');
+                }
+            }
+            self.draw_text(start_col, r, &line)?;
+        }
+
+        Ok(())
+    }
+
+    // Draws a rectangular border with given start coordinates and width/heights.
+    // Adds a highlight ('=' signs around the inside of the border) to indicate the box is selected.
+    fn draw_highlight_border(&mut self, start_col: u16, start_row: u16, cols: u16, rows: u16) -> Result<()> {
+        self.draw_border(start_col, start_row, cols, rows)?;
+        // Loop over each row
+        let h_start_col = start_col+1;
+        let h_start_row = start_row+1;
+        let h_cols = cols-2;
+        let h_rows = rows-2;
+        for r in h_start_row..h_start_row+h_rows {
+            // Loop over each column
+            let mut line = String::new();
+            for c in h_start_col..h_start_col+h_cols {
+                if r == h_start_row || r == h_start_row+h_rows-1 ||
+                   c == h_start_col || c == h_start_col+h_cols-1 {
+                    line.push('=');
+                } else {
+                    line.push(' ');
+                }
+            }
+            self.draw_text(h_start_col, r, &line)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Draws to the terminal, which is set up for the game while the renderer is in use. Each frame is
+// put together in memory first, and flushing it only writes the cells that changed since the last
+// frame that was shown, all at once. This keeps the screen from flickering while it is redrawn.
+#[derive(Clone, Debug)]
+pub struct CrosstermRenderer {
+    original_columns: u16,
+    original_rows: u16,
+    // The frame being drawn
+    frame: BufferRenderer,
+    // The frame currently on the terminal, if it is known
+    shown: Option<BufferRenderer>,
+}
+
+impl CrosstermRenderer {
+
+    // Throw an error if the terminal size is too small to run the game
+    //      (Terminal must be at least 80x20)
+    fn check_screen_size() -> Result<()> {
+        let error_terminal_too_small = Error::other("Terminal size is too small, must be at least 80x20");
+        let (cols, rows) = size()?;
+        if cols < 80 || rows < 20 {
+            Err(error_terminal_too_small)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Set up the terminal to display the game
+    pub fn initialize() -> CrosstermRenderer {
+        // Get the original size
+        let (orig_cols, orig_rows) = size().unwrap();
+
+        // Make sure their terminal is big enough
+        match CrosstermRenderer::check_screen_size() {
+            Err(_) => {
+                println!("ERROR: Screen size ({}x{}) is too small, please use a larger terminal.",
+                         orig_cols, orig_rows);
+                exit(1);
+            },
+            Ok(_) => {
+                println!("Screen size check passed");
+            }
+        }
+
+        // Store original and desired sizes in a renderer struct
+        let renderer = CrosstermRenderer {
+            original_columns: orig_cols,
+            original_rows: orig_rows,
+            frame: BufferRenderer::new(80, 20),
+            shown: None,
+        };
+
+        // Turn off the cursor
+        // TODO: Make a match error check like those other ones
+        match stdout().execute(Hide) {
+            Err(_) => {
+                println!("ERROR: Unable to hide the terminal cursor, please try another terminal");
+                exit(1);
+            },
+            _ => {
+                println!("Terminal cursor hidden");
+            }
+        };
+
+        // Turn on raw mode for proper keyboard input access
+        match enable_raw_mode() {
+            Err(_) => {
+                println!("ERROR: Unable to enable raw terminal mode for input, please try another terminal");
+                exit(1);
+            },
+            _ => {
+                println!("Raw terminal mode enabled");
+            }
+        };
+
+        // Set the size
+        let (cols, rows) = renderer.size();
+        match execute!(stdout(), SetSize(cols, rows)) {
+            Err(_) => {
+                println!("ERROR: Failed to set the screen size");
+                exit(1);
+            },
+            _ => {
+                println!("Screen size set");
+            }
+        };
+
+        renderer
+    }
+
+    // Resets the terminal to a usable state
+    pub fn reset(&self) -> Result<()> {
+        // Be a good citizen and cleanup the terminal for program exit
+
+        // Get rid of raw mode
+        disable_raw_mode()?;
+
+        // Turn on the cursor
+        stdout().execute(Show)?;
+
+        // Restore the original size
+        execute!(stdout(), SetSize(self.original_columns, self.original_rows))?;
+
+        // Clear the screen
+        execute!(stdout(), Clear(ClearType::All))?;
+
+        // Move the cursor back down so the user can see the prompt
+        execute!(stdout(), MoveTo(0, 0))?;
+
+        Ok(())
+    }
+
+    // Forgets what is on the terminal, so that the next flush writes the whole frame. Used when
+    //    something else may have changed the terminal, such as it being resized.
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+}
+
+impl Renderer for CrosstermRenderer {
+    fn size(&self) -> (u16, u16) {
+        self.frame.size()
+    }
+
+    fn draw_cell(&mut self, col: u16, row: u16, c: char) -> Result<()> {
+        self.frame.draw_cell(col, row, c)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.frame.clear()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let mut output = Vec::<u8>::new();
+        // Start from a blank terminal if we don't know what is on it
+        if self.shown.is_none() {
+            queue!(output, Clear(ClearType::All))?;
+        }
+        for (col, row, text) in frame_changes(self.shown.as_ref(), &self.frame) {
+            queue!(
+                output,
+                MoveTo(col, row),
+                Print(text),
+            )?;
+        }
+
+        // Write the whole change in one go
+        let mut out = stdout();
+        out.write_all(&output)?;
+        out.flush()?;
+
+        self.shown = Some(self.frame.clone());
+
+        Ok(())
+    }
+}
+
+// Finds what needs to be written to change the terminal from showing the shown frame to showing
+//    the new frame. Each change is a run of cells in a single row, given as (col, row, text). If
+//    nothing is known to be shown, every row is written.
+fn frame_changes(shown: Option<&BufferRenderer>, frame: &BufferRenderer) -> Vec<(u16, u16, String)> {
+    let mut changes = vec![];
+    for (row, line) in frame.cells.iter().enumerate() {
+        let shown_line = shown.and_then(|shown| shown.cells.get(row));
+        let mut run_start: Option<usize> = None;
+        let mut run = String::new();
+        for (col, c) in line.iter().enumerate() {
+            let changed = match shown_line.and_then(|shown_line| shown_line.get(col)) {
+                Some(shown_c) => { shown_c != c }
+                None => { true }
+            };
+            if changed {
+                run_start.get_or_insert(col);
+                run.push(*c);
+            } else if let Some(start) = run_start.take() {
+                changes.push((start as u16, row as u16, run.clone()));
+                run.clear();
+            }
+        }
+        if let Some(start) = run_start {
+            changes.push((start as u16, row as u16, run));
+        }
+    }
+    changes
+}
+
+// Draws into a grid of characters in memory instead of the terminal
+#[derive(Clone, Debug)]
+pub struct BufferRenderer {
+    columns: u16,
+    rows: u16,
+    // Stored by row, then by column
+    cells: Vec<Vec<char>>,
+}
+
+impl BufferRenderer {
+    pub fn new(columns: u16, rows: u16) -> BufferRenderer {
+        BufferRenderer {
+            columns,
+            rows,
+            cells: vec![vec![' '; columns as usize]; rows as usize],
+        }
+    }
+
+    // The character at (col, row), or a space if that is off the screen
+    pub fn cell(&self, col: u16, row: u16) -> char {
+        match self.cells.get(row as usize).and_then(|line| line.get(col as usize)) {
+            Some(c) => { *c }
+            None => { ' ' }
+        }
+    }
+
+    // Everything on the screen as text, one line per row with any trailing spaces removed
+    pub fn text(&self) -> String {
+        self.cells.iter()
+            .map(|line| line.iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Renderer for BufferRenderer {
+    fn size(&self) -> (u16, u16) {
+        (self.columns, self.rows)
+    }
+
+    fn draw_cell(&mut self, col: u16, row: u16, c: char) -> Result<()> {
+        if col < self.columns && row < self.rows {
+            self.cells[row as usize][col as usize] = c;
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        for line in self.cells.iter_mut() {
+            for cell in line.iter_mut() {
+                *cell = ' ';
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_renderer_cuts_off_drawing_past_the_edges() {
+        let mut renderer = BufferRenderer::new(6, 3);
+        renderer.draw_border(0, 0, 4, 3).unwrap();
+        renderer.draw_text(3, 1, "abcdef").unwrap();
+        renderer.draw_cell(1, 5, 'x').unwrap();
+        assert_eq!(renderer.text(), "+--+\n|  abc\n+--+");
+        assert_eq!(renderer.cell(4, 1), 'b');
+        assert_eq!(renderer.cell(9, 9), ' ');
+
+        renderer.clear().unwrap();
+        assert_eq!(renderer.text(), "\n\n");
+    }
+
+    #[test]
+    fn only_changed_cells_are_written() {
+        let mut shown = BufferRenderer::new(10, 3);
+        shown.draw_border(0, 0, 10, 3).unwrap();
+
+        // With nothing shown yet, every row is written
+        let changes = frame_changes(None, &shown);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[1], (0, 1, "|        |".to_string()));
+
+        let mut frame = shown.clone();
+        assert!(frame_changes(Some(&shown), &frame).is_empty());
+
+        frame.draw_text(2, 1, "ab").unwrap();
+        frame.draw_cell(5, 1, 'c').unwrap();
+        frame.draw_cell(9, 2, '#').unwrap();
+        assert_eq!(frame_changes(Some(&shown), &frame), vec![
+            (2, 1, "ab".to_string()),
+            (5, 1, "c".to_string()),
+            (9, 2, "#".to_string()),
+        ]);
+    }
+}
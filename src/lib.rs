@@ -0,0 +1,4 @@
+// The Radiance Platform as a library, so that the game's modules can be used outside of the
+// executable, such as for playthrough tests of a game's content written in another crate's tests.
+
+pub mod game;
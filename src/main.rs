@@ -1,28 +1,100 @@
 use std::path::PathBuf;
-use clap::Parser;
-
-pub mod game;
+use std::process::exit;
+use clap::{CommandFactory, ErrorKind, Parser, Subcommand};
+use radiance::game;
+use radiance::game::check::OutputFormat;
+use radiance::game::config_parsers::files::{ConfigFiles, FolderFiles};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Full or relative path to the root folder containing the configuration files
+    /// Full or relative path to the root folder containing the configuration files. Not needed when
+    /// running a compiled game.
     #[clap(short, long, parse(from_os_str), value_name = "FILEPATH")]
-    config_path: PathBuf,
+    config_path: Option<PathBuf>,
 
-    /// Whether or not to compile the files into the binary
+    /// Compile the configuration files into a standalone executable instead of playing the game
     #[clap(long)]
     compile: bool,
+
+    /// Where to write the compiled game. Defaults to the name of the configuration folder.
+    #[clap(short, long, parse(from_os_str), value_name = "FILE", requires = "compile")]
+    output: Option<PathBuf>,
+
+    /// Full or relative path to a save file to continue playing from
+    #[clap(long, parse(from_os_str), value_name = "FILE")]
+    load: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check the configuration files for problems without starting the game. Exits with a non-zero
+    /// status if any errors are found.
+    Check {
+        /// Full or relative path to the root folder containing the configuration files
+        #[clap(short, long, parse(from_os_str), value_name = "FILEPATH")]
+        config_path: PathBuf,
+
+        /// How to print the problems that are found
+        #[clap(long, arg_enum, default_value = "human")]
+        format: OutputFormat,
+    },
 }
 
 fn main() {
-    // Make sure we have arguments or compiled-in
-    // TODO: Some way to compile config files in, might need a custom build script or something
     let args = Args::parse();
-    // Debug printing
-    println!("path: {:?}", args.config_path);
-    println!("compile: {:?}", args.compile);
 
-    let mut g = game::Game::initialize(args.config_path);
+    if let Some(Command::Check { config_path, format }) = args.command {
+        let passed = game::check::run(&config_path, format);
+        exit(if passed { 0 } else { 1 });
+    }
+
+    if args.compile {
+        let config_path = match &args.config_path {
+            Some(config_path) => { config_path }
+            None => { missing_config_path("--compile needs the configuration files to compile"); }
+        };
+        let output_path = match args.output {
+            Some(output_path) => { output_path }
+            None => { game::bundle::default_output_path(config_path) }
+        };
+        match game::bundle::compile(config_path, &output_path) {
+            Ok(_) => {
+                println!("Compiled game to {}", output_path.display());
+                exit(0);
+            }
+            Err(err) => {
+                println!("ERROR: Unable to compile game: {}", err);
+                exit(1);
+            }
+        }
+    }
+
+    // Make sure we have a config path or a game compiled in. A config path takes priority so that
+    //    a compiled executable can still play other games.
+    let files: Box<dyn ConfigFiles> = match args.config_path {
+        Some(config_path) => { Box::new(FolderFiles::new(&config_path)) }
+        None => {
+            match game::bundle::embedded_files() {
+                Ok(Some(files)) => { Box::new(files) }
+                Ok(None) => { missing_config_path("No game is compiled into this executable"); }
+                Err(err) => {
+                    println!("ERROR: Unable to read the game compiled into this executable: {}", err);
+                    exit(1);
+                }
+            }
+        }
+    };
+
+    let mut g = game::Game::initialize(files.as_ref(), args.load);
     g.start();
-}
\ No newline at end of file
+}
+
+// Exits with clap's usual message for a missing required argument
+fn missing_config_path(reason: &str) -> ! {
+    Args::command().error(ErrorKind::MissingRequiredArgument,
+                          format!("{}, so --config-path <FILEPATH> is required", reason)).exit()
+}
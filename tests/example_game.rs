@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+use radiance::game::engine::Direction::*;
+use radiance::game::playthrough::Playthrough;
+use radiance::game::playthrough::Step::*;
+
+// Plays part of the example game from outside of the platform, the same way the tests for a game's
+//    own content would
+#[test]
+fn example_game_can_be_played_from_other_crates() {
+    let config_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_game");
+    let mut game = Playthrough::load(&config_path).unwrap();
+
+    game.run(&[Start, Move(Right, 11), Move(Down, 4), Interact, ChooseMessageOption(1)]).unwrap();
+    assert_eq!(game.inventory_ids(), vec!["hat"]);
+
+    game.run(&[Move(Up, 1), Move(Left, 1), Interact, ChooseDialogOption(1), SelectSlot(0, 0), UseItem,
+               ReturnToMap]).unwrap();
+    assert!(game.inventory_ids().is_empty());
+    assert_eq!(game.character_at("Main Room", 11, 4).unwrap().dialog_id, "AA give hat");
+    assert!(game.run(&[Move(Up, 5)]).is_err());
+}